@@ -13,6 +13,27 @@ fn uri_parse_slash(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn uri_clone_slash(b: &mut Bencher) {
+    let uri: Uri = "/".parse().unwrap();
+    b.iter(|| uri.clone());
+}
+
+#[bench]
+fn uri_parse_absolute_short(b: &mut Bencher) {
+    let s = "http://example.com/";
+    b.bytes = s.len() as u64;
+    b.iter(|| {
+        s.parse::<Uri>().unwrap();
+    });
+}
+
+#[bench]
+fn uri_clone_absolute_short(b: &mut Bencher) {
+    let uri: Uri = "http://example.com/".parse().unwrap();
+    b.iter(|| uri.clone());
+}
+
 #[bench]
 fn uri_parse_relative_medium(b: &mut Bencher) {
     let s = "/wp-content/uploads/2010/03/hello-kitty-darth-vader-pink.jpg";
@@ -30,3 +51,12 @@ fn uri_parse_relative_query(b: &mut Bencher) {
         s.parse::<Uri>().unwrap();
     });
 }
+
+#[bench]
+fn uri_parse_long_query(b: &mut Bencher) {
+    let s = format!("/search?q={}", "a".repeat(100 * 1024));
+    b.bytes = s.len() as u64;
+    b.iter(|| {
+        s.parse::<Uri>().unwrap();
+    });
+}
@@ -0,0 +1,205 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str;
+
+use super::{AuthParams, Challenge, Credentials, ErrorKind, InvalidAuth};
+use crate::base64;
+use crate::header::HeaderValue;
+
+/// Credentials of the `Basic` authentication scheme, defined in [RFC 7617].
+///
+/// The user-id and password are encoded as UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// # use http::auth::Basic;
+/// # use http::header::HeaderValue;
+/// let basic = Basic::new("test", "123\u{a3}").unwrap();
+/// assert_eq!(HeaderValue::from(basic), "Basic dGVzdDoxMjPCow==");
+/// ```
+///
+/// [RFC 7617]: https://www.rfc-editor.org/rfc/rfc7617
+#[derive(Clone, PartialEq, Eq)]
+pub struct Basic {
+    user_id: String,
+    password: String,
+}
+
+impl Basic {
+    /// Creates `Basic` credentials.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user-id contains a colon, or if either the
+    /// user-id or the password contains control characters.
+    pub fn new(user_id: &str, password: &str) -> Result<Basic, InvalidAuth> {
+        if user_id.contains(':') || has_ctl(user_id) || has_ctl(password) {
+            return Err(InvalidAuth::new(ErrorKind::InvalidBasic));
+        }
+
+        Ok(Basic {
+            user_id: user_id.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Extracts `Basic` credentials from generic `Credentials`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scheme is not `Basic`, or if the `token68` is
+    /// not the base64 encoding of a UTF-8 `user-id:password` pair.
+    pub fn from_credentials(credentials: &Credentials) -> Result<Basic, InvalidAuth> {
+        if !credentials.is_scheme("Basic") {
+            return Err(InvalidAuth::new(ErrorKind::WrongScheme));
+        }
+
+        let invalid = || InvalidAuth::new(ErrorKind::InvalidBasic);
+
+        let token68 = credentials.token68().ok_or_else(invalid)?;
+        let decoded = base64::decode(token68.as_bytes()).ok_or_else(invalid)?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+
+        let colon = decoded.find(':').ok_or_else(invalid)?;
+        let (user_id, password) = (&decoded[..colon], &decoded[colon + 1..]);
+        if has_ctl(user_id) || has_ctl(password) {
+            return Err(invalid());
+        }
+
+        Ok(Basic {
+            user_id: user_id.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Creates a `Basic` challenge for a protection space.
+    ///
+    /// The challenge advertises `charset="UTF-8"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::Basic;
+    /// let challenge = Basic::challenge("WallyWorld").unwrap();
+    /// assert_eq!(challenge.to_string(), "Basic realm=\"WallyWorld\", charset=\"UTF-8\"");
+    /// ```
+    pub fn challenge(realm: &str) -> Result<Challenge, InvalidAuth> {
+        let mut params = AuthParams::new();
+        params.insert("realm", realm)?;
+        params.insert("charset", "UTF-8")?;
+        Challenge::with_params("Basic", params)
+    }
+
+    /// Returns the user-id.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns the password.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+impl<'a> TryFrom<&'a Credentials> for Basic {
+    type Error = InvalidAuth;
+
+    #[inline]
+    fn try_from(credentials: &'a Credentials) -> Result<Self, Self::Error> {
+        Basic::from_credentials(credentials)
+    }
+}
+
+impl<'a> TryFrom<&'a HeaderValue> for Basic {
+    type Error = InvalidAuth;
+
+    fn try_from(value: &'a HeaderValue) -> Result<Self, Self::Error> {
+        Basic::from_credentials(&Credentials::parse(value)?)
+    }
+}
+
+impl From<Basic> for Credentials {
+    fn from(basic: Basic) -> Credentials {
+        let pair = format!("{}:{}", basic.user_id, basic.password);
+        Credentials::with_token68("Basic", &base64::encode(pair.as_bytes()))
+            .expect("base64 is a valid token68")
+    }
+}
+
+impl From<Basic> for HeaderValue {
+    fn from(basic: Basic) -> HeaderValue {
+        Credentials::from(basic).into()
+    }
+}
+
+impl fmt::Debug for Basic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Basic")
+            .field("user_id", &self.user_id)
+            .field("password", &"[redacted]")
+            .finish()
+    }
+}
+
+fn has_ctl(s: &str) -> bool {
+    s.chars().any(char::is_control)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_rfc7617_examples() {
+        let basic = Basic::new("Aladdin", "open sesame").unwrap();
+        assert_eq!(
+            Credentials::from(basic).to_string(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+
+        let basic = Basic::new("test", "123\u{a3}").unwrap();
+        assert_eq!(
+            Credentials::from(basic).to_string(),
+            "Basic dGVzdDoxMjPCow=="
+        );
+    }
+
+    #[test]
+    fn decodes() {
+        let value = HeaderValue::from_static("basic dGVzdDoxMjPCow==");
+        let basic = Basic::try_from(&value).unwrap();
+        assert_eq!(basic.user_id(), "test");
+        assert_eq!(basic.password(), "123\u{a3}");
+
+        // The password may contain colons.
+        let value = HeaderValue::from_static("Basic YTpiOmM=");
+        assert_eq!(Basic::try_from(&value).unwrap().password(), "b:c");
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        assert!(Basic::new("a:b", "c").is_err());
+        assert!(Basic::new("a", "b\n").is_err());
+
+        let invalid = [
+            "Bearer YTpi",
+            "Basic realm=\"x\"",
+            "Basic YWJj",
+            "Basic ////",
+            "Basic YTpi==",
+            "Basic YQo6Yg==",
+        ];
+        for s in &invalid {
+            let value = HeaderValue::from_static(s);
+            assert!(Basic::try_from(&value).is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn password_is_redacted() {
+        let basic = Basic::new("user", "hunter2").unwrap();
+        assert!(!format!("{:?}", basic).contains("hunter2"));
+        assert!(HeaderValue::from(basic).is_sensitive());
+    }
+}
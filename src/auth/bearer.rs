@@ -0,0 +1,377 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::{AuthParams, Challenge, Credentials, ErrorKind, InvalidAuth};
+use crate::header::HeaderValue;
+use crate::StatusCode;
+
+/// Credentials of the `Bearer` authentication scheme, defined in [RFC 6750].
+///
+/// # Examples
+///
+/// ```
+/// # use http::auth::Bearer;
+/// # use http::header::HeaderValue;
+/// let bearer = Bearer::new("mF_9.B5f-4.1JqM").unwrap();
+/// assert_eq!(HeaderValue::from(bearer), "Bearer mF_9.B5f-4.1JqM");
+/// ```
+///
+/// [RFC 6750]: https://www.rfc-editor.org/rfc/rfc6750
+#[derive(Clone, PartialEq, Eq)]
+pub struct Bearer {
+    token: String,
+}
+
+/// A challenge of the `Bearer` authentication scheme.
+///
+/// # Examples
+///
+/// ```
+/// # use http::auth::{BearerChallenge, BearerError};
+/// let mut challenge = BearerChallenge::new();
+/// challenge.set_realm("example");
+/// challenge.set_error(BearerError::InvalidToken);
+/// challenge.set_error_description("The access token expired");
+///
+/// assert_eq!(
+///     challenge.to_challenge().unwrap().to_string(),
+///     "Bearer realm=\"example\", error=\"invalid_token\", \
+///      error_description=\"The access token expired\""
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BearerChallenge {
+    realm: Option<String>,
+    scope: Option<String>,
+    error: Option<BearerError>,
+    error_description: Option<String>,
+    error_uri: Option<String>,
+}
+
+/// An error code of a `Bearer` challenge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BearerError {
+    /// The request is malformed, for example because of a missing or
+    /// duplicated parameter.
+    InvalidRequest,
+    /// The access token is expired, revoked, malformed or otherwise invalid.
+    InvalidToken,
+    /// The access token doesn't grant enough privileges.
+    InsufficientScope,
+}
+
+// ===== impl Bearer =====
+
+impl Bearer {
+    /// Creates `Bearer` credentials from an access token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is not a valid `token68`.
+    pub fn new(token: &str) -> Result<Bearer, InvalidAuth> {
+        // Validate by building the credentials once.
+        Credentials::with_token68("Bearer", token)?;
+        Ok(Bearer {
+            token: token.to_string(),
+        })
+    }
+
+    /// Extracts `Bearer` credentials from generic `Credentials`.
+    pub fn from_credentials(credentials: &Credentials) -> Result<Bearer, InvalidAuth> {
+        if !credentials.is_scheme("Bearer") {
+            return Err(InvalidAuth::new(ErrorKind::WrongScheme));
+        }
+
+        let token = credentials
+            .token68()
+            .ok_or_else(|| InvalidAuth::new(ErrorKind::InvalidToken68))?;
+        Ok(Bearer {
+            token: token.to_string(),
+        })
+    }
+
+    /// Returns the access token.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl<'a> TryFrom<&'a Credentials> for Bearer {
+    type Error = InvalidAuth;
+
+    #[inline]
+    fn try_from(credentials: &'a Credentials) -> Result<Self, Self::Error> {
+        Bearer::from_credentials(credentials)
+    }
+}
+
+impl<'a> TryFrom<&'a HeaderValue> for Bearer {
+    type Error = InvalidAuth;
+
+    fn try_from(value: &'a HeaderValue) -> Result<Self, Self::Error> {
+        Bearer::from_credentials(&Credentials::parse(value)?)
+    }
+}
+
+impl From<Bearer> for Credentials {
+    fn from(bearer: Bearer) -> Credentials {
+        Credentials::with_token68("Bearer", &bearer.token).expect("validated token68")
+    }
+}
+
+impl From<Bearer> for HeaderValue {
+    fn from(bearer: Bearer) -> HeaderValue {
+        Credentials::from(bearer).into()
+    }
+}
+
+impl fmt::Debug for Bearer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bearer")
+            .field("token", &"[redacted]")
+            .finish()
+    }
+}
+
+// ===== impl BearerChallenge =====
+
+impl BearerChallenge {
+    /// Creates a `Bearer` challenge without any parameter.
+    pub fn new() -> BearerChallenge {
+        BearerChallenge::default()
+    }
+
+    /// Sets the protection space.
+    pub fn set_realm(&mut self, realm: &str) {
+        self.realm = Some(realm.to_string());
+    }
+
+    /// Sets the space-delimited list of scopes required to access the
+    /// resource.
+    pub fn set_scope(&mut self, scope: &str) {
+        self.scope = Some(scope.to_string());
+    }
+
+    /// Sets the reason the request was rejected.
+    pub fn set_error(&mut self, error: BearerError) {
+        self.error = Some(error);
+    }
+
+    /// Sets a human-readable explanation of the error.
+    pub fn set_error_description(&mut self, description: &str) {
+        self.error_description = Some(description.to_string());
+    }
+
+    /// Sets the URI of a human-readable web page explaining the error.
+    pub fn set_error_uri(&mut self, uri: &str) {
+        self.error_uri = Some(uri.to_string());
+    }
+
+    /// Extracts a `Bearer` challenge from a generic `Challenge`.
+    ///
+    /// Unknown parameters, and unknown error codes, are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::{BearerChallenge, BearerError, Challenge};
+    /// let challenge: Challenge = "Bearer realm=\"example\", error=\"insufficient_scope\", \
+    ///     scope=\"read write\"".parse().unwrap();
+    /// let bearer = BearerChallenge::from_challenge(&challenge).unwrap();
+    ///
+    /// assert_eq!(bearer.scope(), Some("read write"));
+    /// assert_eq!(bearer.error(), Some(BearerError::InsufficientScope));
+    /// ```
+    pub fn from_challenge(challenge: &Challenge) -> Result<BearerChallenge, InvalidAuth> {
+        if !challenge.is_scheme("Bearer") {
+            return Err(InvalidAuth::new(ErrorKind::WrongScheme));
+        }
+
+        let params = challenge.params();
+        let string = |name| params.get(name).map(str::to_string);
+
+        Ok(BearerChallenge {
+            realm: string("realm"),
+            scope: string("scope"),
+            error: params.get("error").and_then(BearerError::from_str),
+            error_description: string("error_description"),
+            error_uri: string("error_uri"),
+        })
+    }
+
+    /// Converts this into a generic `Challenge`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter contains characters that are not
+    /// allowed by RFC 6750.
+    pub fn to_challenge(&self) -> Result<Challenge, InvalidAuth> {
+        let mut params = AuthParams::new();
+
+        // RFC 6750 restricts most parameters to printable ASCII, without
+        // quotes or backslashes.
+        let mut insert = |name, value: &Option<String>| -> Result<(), InvalidAuth> {
+            if let Some(ref value) = *value {
+                let valid = value
+                    .bytes()
+                    .all(|b| (0x20..0x7f).contains(&b) && b != b'"' && b != b'\\');
+                if !valid {
+                    return Err(InvalidAuth::new(ErrorKind::InvalidParamValue));
+                }
+                params.insert(name, value)?;
+            }
+            Ok(())
+        };
+
+        insert("realm", &self.realm)?;
+        insert("scope", &self.scope)?;
+        insert("error", &self.error.map(|e| e.as_str().to_string()))?;
+        insert("error_description", &self.error_description)?;
+        insert("error_uri", &self.error_uri)?;
+
+        Challenge::with_params("Bearer", params)
+    }
+
+    /// Returns the protection space.
+    pub fn realm(&self) -> Option<&str> {
+        self.realm.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the scopes required to access the resource.
+    pub fn scope(&self) -> Option<&str> {
+        self.scope.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the reason the request was rejected.
+    pub fn error(&self) -> Option<BearerError> {
+        self.error
+    }
+
+    /// Returns the human-readable explanation of the error.
+    pub fn error_description(&self) -> Option<&str> {
+        self.error_description.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the URI of a web page explaining the error.
+    pub fn error_uri(&self) -> Option<&str> {
+        self.error_uri.as_ref().map(|s| &s[..])
+    }
+}
+
+impl<'a> TryFrom<&'a Challenge> for BearerChallenge {
+    type Error = InvalidAuth;
+
+    #[inline]
+    fn try_from(challenge: &'a Challenge) -> Result<Self, Self::Error> {
+        BearerChallenge::from_challenge(challenge)
+    }
+}
+
+// ===== impl BearerError =====
+
+impl BearerError {
+    /// Returns the error code, as used in the `error` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            BearerError::InvalidRequest => "invalid_request",
+            BearerError::InvalidToken => "invalid_token",
+            BearerError::InsufficientScope => "insufficient_scope",
+        }
+    }
+
+    /// Returns the status code a response with this error should have.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::BearerError;
+    /// # use http::StatusCode;
+    /// assert_eq!(BearerError::InvalidToken.status_code(), StatusCode::UNAUTHORIZED);
+    /// ```
+    pub fn status_code(&self) -> StatusCode {
+        match *self {
+            BearerError::InvalidRequest => StatusCode::BAD_REQUEST,
+            BearerError::InvalidToken => StatusCode::UNAUTHORIZED,
+            BearerError::InsufficientScope => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn from_str(s: &str) -> Option<BearerError> {
+        match s {
+            "invalid_request" => Some(BearerError::InvalidRequest),
+            "invalid_token" => Some(BearerError::InvalidToken),
+            "insufficient_scope" => Some(BearerError::InsufficientScope),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BearerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials() {
+        let value = HeaderValue::from_static("bearer mF_9.B5f-4.1JqM");
+        let bearer = Bearer::try_from(&value).unwrap();
+        assert_eq!(bearer.token(), "mF_9.B5f-4.1JqM");
+        assert!(!format!("{:?}", bearer).contains("mF_9"));
+
+        let value = HeaderValue::from(bearer);
+        assert_eq!(value, "Bearer mF_9.B5f-4.1JqM");
+        assert!(value.is_sensitive());
+
+        assert!(Bearer::new("a b").is_err());
+        assert!(Bearer::try_from(&HeaderValue::from_static("Basic abc")).is_err());
+        assert!(Bearer::try_from(&HeaderValue::from_static("Bearer a=\"b\"")).is_err());
+    }
+
+    #[test]
+    fn challenge_roundtrip() {
+        let mut challenge = BearerChallenge::new();
+        challenge.set_realm("example");
+        challenge.set_scope("openid profile");
+        challenge.set_error(BearerError::InsufficientScope);
+        challenge.set_error_uri("https://example.com/errors");
+
+        let generic = challenge.to_challenge().unwrap();
+        assert_eq!(
+            generic.to_string(),
+            "Bearer realm=\"example\", scope=\"openid profile\", \
+             error=\"insufficient_scope\", error_uri=\"https://example.com/errors\""
+        );
+        assert_eq!(BearerChallenge::try_from(&generic).unwrap(), challenge);
+
+        assert_eq!(
+            BearerChallenge::new().to_challenge().unwrap().to_string(),
+            "Bearer"
+        );
+    }
+
+    #[test]
+    fn challenge_parsing_is_lenient() {
+        let challenge: Challenge = "Bearer error=unknown_code, foo=bar".parse().unwrap();
+        let bearer = BearerChallenge::from_challenge(&challenge).unwrap();
+        assert_eq!(bearer, BearerChallenge::new());
+
+        let challenge: Challenge = "Basic realm=x".parse().unwrap();
+        assert!(BearerChallenge::from_challenge(&challenge).is_err());
+    }
+
+    #[test]
+    fn challenge_rejects_invalid_characters() {
+        let mut challenge = BearerChallenge::new();
+        challenge.set_error_description("say \"hi\"");
+        assert!(challenge.to_challenge().is_err());
+
+        let mut challenge = BearerChallenge::new();
+        challenge.set_realm("caf\u{e9}");
+        assert!(challenge.to_challenge().is_err());
+    }
+}
@@ -0,0 +1,732 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::{hex, md5, AuthParams, Challenge, Credentials, ErrorKind, InvalidAuth};
+use crate::header::HeaderValue;
+use crate::sha256;
+use crate::Method;
+
+/// A challenge of the `Digest` authentication scheme, defined in [RFC 7616].
+///
+/// Only the `auth` quality of protection is supported.
+///
+/// # Examples
+///
+/// ```
+/// # use http::auth::{Algorithm, Challenge, DigestChallenge};
+/// # use http::Method;
+/// let challenge: Challenge = "Digest realm=\"http-auth@example.org\", qop=\"auth, auth-int\", \
+///     algorithm=SHA-256, nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+///     opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\"".parse().unwrap();
+/// let digest = DigestChallenge::from_challenge(&challenge).unwrap();
+/// assert_eq!(digest.algorithm(), Algorithm::Sha256);
+///
+/// let credentials = digest
+///     .respond(
+///         "Mufasa",
+///         "Circle of Life",
+///         &Method::GET,
+///         "/dir/index.html",
+///         "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+///         1,
+///     )
+///     .unwrap();
+///
+/// assert_eq!(
+///     credentials.response(),
+///     "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+/// );
+/// ```
+///
+/// [RFC 7616]: https://www.rfc-editor.org/rfc/rfc7616
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestChallenge {
+    realm: String,
+    domain: Option<String>,
+    nonce: String,
+    opaque: Option<String>,
+    stale: bool,
+    algorithm: Algorithm,
+    userhash: bool,
+}
+
+/// Credentials of the `Digest` authentication scheme.
+///
+/// These are usually created with [`DigestChallenge::respond`].
+///
+/// [`DigestChallenge::respond`]: struct.DigestChallenge.html#method.respond
+#[derive(Clone, PartialEq, Eq)]
+pub struct DigestCredentials {
+    username: String,
+    realm: String,
+    uri: String,
+    algorithm: Algorithm,
+    nonce: String,
+    nc: u32,
+    cnonce: String,
+    response: String,
+    opaque: Option<String>,
+    userhash: bool,
+}
+
+/// A hash algorithm of the `Digest` authentication scheme.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// `MD5`, only supported for compatibility with RFC 2617.
+    Md5,
+    /// `MD5-sess`.
+    Md5Sess,
+    /// `SHA-256`.
+    Sha256,
+    /// `SHA-256-sess`.
+    Sha256Sess,
+}
+
+// ===== impl DigestChallenge =====
+
+impl DigestChallenge {
+    /// Creates a `Digest` challenge for a protection space, using the
+    /// `SHA-256` algorithm.
+    ///
+    /// The nonce should be uniquely generated each time a 401 response is
+    /// made.
+    pub fn new(realm: &str, nonce: &str) -> DigestChallenge {
+        DigestChallenge {
+            realm: realm.to_string(),
+            domain: None,
+            nonce: nonce.to_string(),
+            opaque: None,
+            stale: false,
+            algorithm: Algorithm::Sha256,
+            userhash: false,
+        }
+    }
+
+    /// Extracts a `Digest` challenge from a generic `Challenge`.
+    ///
+    /// If the challenge has no `algorithm` parameter, `MD5` is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scheme is not `Digest`, if the `realm` or
+    /// `nonce` parameters are missing, if the algorithm is not supported, or
+    /// if the challenge doesn't offer the `auth` quality of protection.
+    pub fn from_challenge(challenge: &Challenge) -> Result<DigestChallenge, InvalidAuth> {
+        if !challenge.is_scheme("Digest") {
+            return Err(InvalidAuth::new(ErrorKind::WrongScheme));
+        }
+
+        let params = challenge.params();
+
+        let qop = params.require("qop")?;
+        if !qop.split(',').any(|qop| qop.trim() == "auth") {
+            return Err(InvalidAuth::new(ErrorKind::UnsupportedQop));
+        }
+
+        Ok(DigestChallenge {
+            realm: params.require("realm")?.to_string(),
+            domain: params.get("domain").map(str::to_string),
+            nonce: params.require("nonce")?.to_string(),
+            opaque: params.get("opaque").map(str::to_string),
+            stale: params.get("stale").map_or(false, is_true),
+            algorithm: Algorithm::from_param(params.get("algorithm"))?,
+            userhash: params.get("userhash").map_or(false, is_true),
+        })
+    }
+
+    /// Converts this into a generic `Challenge`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a parameter contains control characters.
+    pub fn to_challenge(&self) -> Result<Challenge, InvalidAuth> {
+        let mut params = AuthParams::new();
+
+        params.insert("realm", &self.realm)?;
+        if let Some(ref domain) = self.domain {
+            params.insert("domain", domain)?;
+        }
+        params.insert("nonce", &self.nonce)?;
+        if let Some(ref opaque) = self.opaque {
+            params.insert("opaque", opaque)?;
+        }
+        if self.stale {
+            params.insert_token("stale", "true")?;
+        }
+        params.insert_token("algorithm", self.algorithm.as_str())?;
+        params.insert("qop", "auth")?;
+        params.insert_token("charset", "UTF-8")?;
+        if self.userhash {
+            params.insert_token("userhash", "true")?;
+        }
+
+        Challenge::with_params("Digest", params)
+    }
+
+    /// Computes the credentials answering this challenge.
+    ///
+    /// `uri` is the request target, `cnonce` an unpredictable string chosen
+    /// by the client, and `nc` the number of requests (including this one)
+    /// the client has sent with this nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `cnonce` is empty, or if the user name or
+    /// `uri` contain control characters.
+    pub fn respond(
+        &self,
+        username: &str,
+        password: &str,
+        method: &Method,
+        uri: &str,
+        cnonce: &str,
+        nc: u32,
+    ) -> Result<DigestCredentials, InvalidAuth> {
+        let invalid = |s: &str| s.chars().any(char::is_control);
+        if cnonce.is_empty() || invalid(cnonce) || invalid(username) || invalid(uri) {
+            return Err(InvalidAuth::new(ErrorKind::InvalidParamValue));
+        }
+
+        let mut credentials = DigestCredentials {
+            username: if self.userhash {
+                self.algorithm
+                    .hash(format!("{}:{}", username, self.realm).as_bytes())
+            } else {
+                username.to_string()
+            },
+            realm: self.realm.clone(),
+            uri: uri.to_string(),
+            algorithm: self.algorithm,
+            nonce: self.nonce.clone(),
+            nc,
+            cnonce: cnonce.to_string(),
+            response: String::new(),
+            opaque: self.opaque.clone(),
+            userhash: self.userhash,
+        };
+        credentials.response = credentials.compute_response(username, password, method);
+
+        Ok(credentials)
+    }
+
+    /// Sets the URIs, separated by spaces, of the protection space.
+    pub fn set_domain(&mut self, domain: &str) {
+        self.domain = Some(domain.to_string());
+    }
+
+    /// Sets an opaque string that clients must return unchanged.
+    pub fn set_opaque(&mut self, opaque: &str) {
+        self.opaque = Some(opaque.to_string());
+    }
+
+    /// Sets whether the previous request was rejected only because its nonce
+    /// was stale, and may be retried with the new nonce without prompting
+    /// the user.
+    pub fn set_stale(&mut self, stale: bool) {
+        self.stale = stale;
+    }
+
+    /// Sets the hash algorithm.
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+    }
+
+    /// Sets whether clients should hash the user name.
+    pub fn set_userhash(&mut self, userhash: bool) {
+        self.userhash = userhash;
+    }
+
+    /// Returns the protection space.
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    /// Returns the URIs of the protection space.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the nonce.
+    pub fn nonce(&self) -> &str {
+        &self.nonce
+    }
+
+    /// Returns the opaque string.
+    pub fn opaque(&self) -> Option<&str> {
+        self.opaque.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns whether the previous request was rejected because of a stale
+    /// nonce.
+    pub fn stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Returns the hash algorithm.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Returns whether clients should hash the user name.
+    pub fn userhash(&self) -> bool {
+        self.userhash
+    }
+}
+
+impl<'a> TryFrom<&'a Challenge> for DigestChallenge {
+    type Error = InvalidAuth;
+
+    #[inline]
+    fn try_from(challenge: &'a Challenge) -> Result<Self, Self::Error> {
+        DigestChallenge::from_challenge(challenge)
+    }
+}
+
+// ===== impl DigestCredentials =====
+
+impl DigestCredentials {
+    /// Extracts `Digest` credentials from generic `Credentials`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scheme is not `Digest`, if a required
+    /// parameter is missing or malformed, if the algorithm is not supported,
+    /// or if the quality of protection is not `auth`.
+    pub fn from_credentials(credentials: &Credentials) -> Result<DigestCredentials, InvalidAuth> {
+        if !credentials.is_scheme("Digest") {
+            return Err(InvalidAuth::new(ErrorKind::WrongScheme));
+        }
+
+        let params = credentials.params();
+
+        if params.require("qop")? != "auth" {
+            return Err(InvalidAuth::new(ErrorKind::UnsupportedQop));
+        }
+
+        let nc = params.require("nc")?;
+        let nc = if nc.len() == 8 {
+            u32::from_str_radix(nc, 16).ok()
+        } else {
+            None
+        }
+        .ok_or_else(|| InvalidAuth::new(ErrorKind::InvalidParamValue))?;
+
+        Ok(DigestCredentials {
+            username: params.require("username")?.to_string(),
+            realm: params.require("realm")?.to_string(),
+            uri: params.require("uri")?.to_string(),
+            algorithm: Algorithm::from_param(params.get("algorithm"))?,
+            nonce: params.require("nonce")?.to_string(),
+            nc,
+            cnonce: params.require("cnonce")?.to_string(),
+            response: params.require("response")?.to_string(),
+            opaque: params.get("opaque").map(str::to_string),
+            userhash: params.get("userhash").map_or(false, is_true),
+        })
+    }
+
+    /// Checks that these credentials were computed with the given user name
+    /// and password.
+    ///
+    /// A server must also check that the `nonce` is one it issued and is
+    /// still valid, that the `uri` matches the request target, and that the
+    /// `nc` increased since the last request with this nonce.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::{DigestChallenge, DigestCredentials};
+    /// # use http::header::HeaderValue;
+    /// # use http::Method;
+    /// # use std::convert::TryFrom;
+    /// let value = HeaderValue::from_static(
+    ///     "Digest username=\"Mufasa\", realm=\"testrealm@host.com\", \
+    ///      nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", uri=\"/dir/index.html\", \
+    ///      qop=auth, nc=00000001, cnonce=\"0a4f113b\", \
+    ///      response=\"6629fae49393a05397450978507c4ef1\"",
+    /// );
+    /// let credentials = DigestCredentials::try_from(&value).unwrap();
+    ///
+    /// assert!(credentials.verify("Mufasa", "Circle Of Life", &Method::GET));
+    /// assert!(!credentials.verify("Mufasa", "Circle of Life", &Method::GET));
+    /// ```
+    pub fn verify(&self, username: &str, password: &str, method: &Method) -> bool {
+        let expected_username = if self.userhash {
+            self.algorithm
+                .hash(format!("{}:{}", username, self.realm).as_bytes())
+        } else {
+            username.to_string()
+        };
+        if self.username != expected_username {
+            return false;
+        }
+
+        let expected = self.compute_response(username, password, method);
+        constant_time_eq(expected.as_bytes(), self.response.as_bytes())
+    }
+
+    /// Returns the user name, or its hash if `userhash` is set.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the protection space.
+    pub fn realm(&self) -> &str {
+        &self.realm
+    }
+
+    /// Returns the request target.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns the hash algorithm.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Returns the server nonce.
+    pub fn nonce(&self) -> &str {
+        &self.nonce
+    }
+
+    /// Returns the nonce count.
+    pub fn nc(&self) -> u32 {
+        self.nc
+    }
+
+    /// Returns the client nonce.
+    pub fn cnonce(&self) -> &str {
+        &self.cnonce
+    }
+
+    /// Returns the response digest, in lowercase hexadecimal.
+    pub fn response(&self) -> &str {
+        &self.response
+    }
+
+    /// Returns the opaque string of the challenge.
+    pub fn opaque(&self) -> Option<&str> {
+        self.opaque.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns whether the user name is hashed.
+    pub fn userhash(&self) -> bool {
+        self.userhash
+    }
+
+    // RFC 7616, section 3.4.1, with qop=auth.
+    fn compute_response(&self, username: &str, password: &str, method: &Method) -> String {
+        let h = |s: String| self.algorithm.hash(s.as_bytes());
+
+        let mut ha1 = h(format!("{}:{}:{}", username, self.realm, password));
+        if self.algorithm.is_session() {
+            ha1 = h(format!("{}:{}:{}", ha1, self.nonce, self.cnonce));
+        }
+        let ha2 = h(format!("{}:{}", method, self.uri));
+
+        h(format!(
+            "{}:{}:{:08x}:{}:auth:{}",
+            ha1, self.nonce, self.nc, self.cnonce, ha2
+        ))
+    }
+}
+
+impl<'a> TryFrom<&'a Credentials> for DigestCredentials {
+    type Error = InvalidAuth;
+
+    #[inline]
+    fn try_from(credentials: &'a Credentials) -> Result<Self, Self::Error> {
+        DigestCredentials::from_credentials(credentials)
+    }
+}
+
+impl<'a> TryFrom<&'a HeaderValue> for DigestCredentials {
+    type Error = InvalidAuth;
+
+    fn try_from(value: &'a HeaderValue) -> Result<Self, Self::Error> {
+        DigestCredentials::from_credentials(&Credentials::parse(value)?)
+    }
+}
+
+impl From<DigestCredentials> for Credentials {
+    fn from(digest: DigestCredentials) -> Credentials {
+        let nc = format!("{:08x}", digest.nc);
+
+        // (name, value, quoted)
+        let mut fields = vec![
+            ("username", &digest.username[..], true),
+            ("realm", &digest.realm[..], true),
+            ("uri", &digest.uri[..], true),
+            ("algorithm", digest.algorithm.as_str(), false),
+            ("nonce", &digest.nonce[..], true),
+            ("nc", &nc[..], false),
+            ("cnonce", &digest.cnonce[..], true),
+            ("qop", "auth", false),
+            ("response", &digest.response[..], true),
+        ];
+        if let Some(ref opaque) = digest.opaque {
+            fields.push(("opaque", opaque, true));
+        }
+        if digest.userhash {
+            fields.push(("userhash", "true", false));
+        }
+
+        let mut params = AuthParams::new();
+        for (name, value, quoted) in fields {
+            if quoted {
+                params.insert(name, value)
+            } else {
+                params.insert_token(name, value)
+            }
+            .expect("digest parameters are validated");
+        }
+
+        Credentials::with_params("Digest", params).expect("Digest is a valid scheme")
+    }
+}
+
+impl From<DigestCredentials> for HeaderValue {
+    fn from(digest: DigestCredentials) -> HeaderValue {
+        Credentials::from(digest).into()
+    }
+}
+
+impl fmt::Debug for DigestCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DigestCredentials")
+            .field("username", &self.username)
+            .field("realm", &self.realm)
+            .field("uri", &self.uri)
+            .field("algorithm", &self.algorithm)
+            .field("nc", &self.nc)
+            .finish()
+    }
+}
+
+// ===== impl Algorithm =====
+
+impl Algorithm {
+    /// Returns the name of the algorithm, as used in the `algorithm`
+    /// parameter.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Sess => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn from_param(param: Option<&str>) -> Result<Algorithm, InvalidAuth> {
+        let param = match param {
+            Some(param) => param,
+            None => return Ok(Algorithm::Md5),
+        };
+
+        [
+            Algorithm::Md5,
+            Algorithm::Md5Sess,
+            Algorithm::Sha256,
+            Algorithm::Sha256Sess,
+        ]
+        .iter()
+        .cloned()
+        .find(|algorithm| algorithm.as_str().eq_ignore_ascii_case(param))
+        .ok_or_else(|| InvalidAuth::new(ErrorKind::UnsupportedAlgorithm))
+    }
+
+    fn is_session(&self) -> bool {
+        match *self {
+            Algorithm::Md5Sess | Algorithm::Sha256Sess => true,
+            Algorithm::Md5 | Algorithm::Sha256 => false,
+        }
+    }
+
+    fn hash(&self, data: &[u8]) -> String {
+        match *self {
+            Algorithm::Md5 | Algorithm::Md5Sess => hex(&md5::digest(data)),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => hex(&sha256::digest(data)),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn is_true(s: &str) -> bool {
+    s.eq_ignore_ascii_case("true")
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONCE: &str = "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v";
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+    const OPAQUE: &str = "FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS";
+
+    fn rfc7616_challenge(algorithm: Algorithm) -> DigestChallenge {
+        let mut challenge = DigestChallenge::new("http-auth@example.org", NONCE);
+        challenge.set_algorithm(algorithm);
+        challenge.set_opaque(OPAQUE);
+        challenge
+    }
+
+    #[test]
+    fn rfc7616_examples() {
+        let md5 = rfc7616_challenge(Algorithm::Md5)
+            .respond(
+                "Mufasa",
+                "Circle of Life",
+                &Method::GET,
+                "/dir/index.html",
+                CNONCE,
+                1,
+            )
+            .unwrap();
+        assert_eq!(md5.response(), "8ca523f5e9506fed4657c9700eebdbec");
+
+        let sha256 = rfc7616_challenge(Algorithm::Sha256)
+            .respond(
+                "Mufasa",
+                "Circle of Life",
+                &Method::GET,
+                "/dir/index.html",
+                CNONCE,
+                1,
+            )
+            .unwrap();
+        assert_eq!(
+            sha256.response(),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+
+        assert_eq!(
+            Credentials::from(sha256).to_string(),
+            "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+             uri=\"/dir/index.html\", algorithm=SHA-256, \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", nc=00000001, \
+             cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", qop=auth, \
+             response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        );
+    }
+
+    #[test]
+    fn challenge_roundtrip() {
+        let mut challenge = rfc7616_challenge(Algorithm::Sha256Sess);
+        challenge.set_stale(true);
+        challenge.set_userhash(true);
+        challenge.set_domain("/a /b");
+
+        let generic = challenge.to_challenge().unwrap();
+        assert_eq!(
+            generic.to_string(),
+            "Digest realm=\"http-auth@example.org\", domain=\"/a /b\", \
+             nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+             opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\", stale=true, \
+             algorithm=SHA-256-sess, qop=\"auth\", charset=UTF-8, userhash=true"
+        );
+        assert_eq!(DigestChallenge::try_from(&generic).unwrap(), challenge);
+    }
+
+    #[test]
+    fn challenge_parsing() {
+        let challenge: Challenge = "Digest realm=\"r\", nonce=\"n\", qop=\"auth-int\""
+            .parse()
+            .unwrap();
+        assert!(DigestChallenge::from_challenge(&challenge)
+            .unwrap_err()
+            .to_string()
+            .contains("quality of protection"));
+
+        let challenge: Challenge =
+            "Digest realm=\"r\", nonce=\"n\", qop=\"auth\", algorithm=SHA-512-256"
+                .parse()
+                .unwrap();
+        assert!(DigestChallenge::from_challenge(&challenge).is_err());
+
+        let challenge: Challenge = "Digest realm=\"r\", qop=\"auth\"".parse().unwrap();
+        assert!(DigestChallenge::from_challenge(&challenge).is_err());
+
+        let challenge: Challenge = "digest realm=\"r\", nonce=\"n\", qop=\"auth\", stale=TRUE"
+            .parse()
+            .unwrap();
+        let digest = DigestChallenge::from_challenge(&challenge).unwrap();
+        assert_eq!(digest.algorithm(), Algorithm::Md5);
+        assert!(digest.stale());
+    }
+
+    #[test]
+    fn verify_roundtrip() {
+        for &algorithm in &[
+            Algorithm::Md5,
+            Algorithm::Md5Sess,
+            Algorithm::Sha256,
+            Algorithm::Sha256Sess,
+        ] {
+            let mut challenge = rfc7616_challenge(algorithm);
+            challenge.set_userhash(algorithm == Algorithm::Sha256);
+
+            let credentials = challenge
+                .respond("J\u{e4}s\u{f8}n", "pw", &Method::POST, "/x?y", "abc", 0x1a)
+                .unwrap();
+            let value = HeaderValue::from(credentials);
+            assert!(value.is_sensitive());
+
+            let parsed = DigestCredentials::try_from(&value).unwrap();
+            assert_eq!(parsed.nc(), 0x1a);
+            assert_eq!(parsed.algorithm(), algorithm);
+            assert!(parsed.verify("J\u{e4}s\u{f8}n", "pw", &Method::POST));
+            assert!(!parsed.verify("J\u{e4}s\u{f8}n", "pw", &Method::GET));
+            assert!(!parsed.verify("Jason", "pw", &Method::POST));
+            assert!(!parsed.verify("J\u{e4}s\u{f8}n", "pw2", &Method::POST));
+        }
+    }
+
+    #[test]
+    fn userhash() {
+        let mut challenge = rfc7616_challenge(Algorithm::Sha256);
+        challenge.set_userhash(true);
+        let credentials = challenge
+            .respond("Mufasa", "Circle of Life", &Method::GET, "/", CNONCE, 1)
+            .unwrap();
+        assert_eq!(
+            credentials.username(),
+            hex(&sha256::digest(b"Mufasa:http-auth@example.org"))
+        );
+        assert!(credentials.userhash());
+    }
+
+    #[test]
+    fn rejects_invalid_credentials() {
+        let invalid = [
+            "Basic abc",
+            "Digest username=\"a\"",
+            "Digest username=\"a\", realm=\"r\", nonce=\"n\", uri=\"/\", qop=auth-int, \
+             nc=00000001, cnonce=\"c\", response=\"x\"",
+            "Digest username=\"a\", realm=\"r\", nonce=\"n\", uri=\"/\", qop=auth, \
+             nc=1, cnonce=\"c\", response=\"x\"",
+        ];
+        for s in &invalid {
+            let value = HeaderValue::from_static(s);
+            assert!(DigestCredentials::try_from(&value).is_err(), "{:?}", s);
+        }
+
+        let challenge = rfc7616_challenge(Algorithm::Md5);
+        assert!(challenge
+            .respond("a", "b", &Method::GET, "/", "", 1)
+            .is_err());
+        assert!(challenge
+            .respond("a\n", "b", &Method::GET, "/", "c", 1)
+            .is_err());
+    }
+}
@@ -0,0 +1,859 @@
+//! HTTP authentication.
+//!
+//! This module implements the HTTP authentication framework of [RFC 9110,
+//! section 11]: the [`Credentials`] sent by clients in the `Authorization`
+//! and `Proxy-Authorization` headers, and the [`Challenge`]s sent by servers
+//! in the `WWW-Authenticate` and `Proxy-Authenticate` headers. Both consist
+//! of an authentication scheme, followed by either a single `token68` or a
+//! list of [`AuthParams`].
+//!
+//! On top of this generic model, the [`Basic`], [`Bearer`] and [`Digest`]
+//! schemes are implemented.
+//!
+//! Credentials are secrets: the `HeaderValue`s created from them are always
+//! marked as [sensitive].
+//!
+//! # Examples
+//!
+//! ```
+//! use http::auth::{Basic, Challenge};
+//! use http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+//! use http::{Request, Response, StatusCode};
+//! use std::convert::TryFrom;
+//!
+//! // A server asks for credentials...
+//! let response = Response::builder()
+//!     .status(StatusCode::UNAUTHORIZED)
+//!     .header(WWW_AUTHENTICATE, Basic::challenge("admin").unwrap())
+//!     .body(())
+//!     .unwrap();
+//!
+//! // ...which the client provides.
+//! let challenges = Challenge::from_headers(response.headers()).unwrap();
+//! assert_eq!(challenges[0].scheme(), "Basic");
+//! assert_eq!(challenges[0].params().get("realm"), Some("admin"));
+//!
+//! let request = Request::get("/admin")
+//!     .header(AUTHORIZATION, Basic::new("Aladdin", "open sesame").unwrap())
+//!     .body(())
+//!     .unwrap();
+//!
+//! let header = &request.headers()[AUTHORIZATION];
+//! assert_eq!(header, "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+//! assert!(header.is_sensitive());
+//!
+//! let basic = Basic::try_from(header).unwrap();
+//! assert_eq!(basic.user_id(), "Aladdin");
+//! assert_eq!(basic.password(), "open sesame");
+//! ```
+//!
+//! [RFC 9110, section 11]: https://www.rfc-editor.org/rfc/rfc9110#section-11
+//! [`Credentials`]: struct.Credentials.html
+//! [`Challenge`]: struct.Challenge.html
+//! [`AuthParams`]: struct.AuthParams.html
+//! [`Basic`]: struct.Basic.html
+//! [`Bearer`]: struct.Bearer.html
+//! [`Digest`]: struct.DigestChallenge.html
+//! [sensitive]: ../header/struct.HeaderValue.html#method.set_sensitive
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::{self, FromStr};
+
+use bytes::Bytes;
+
+use crate::header::{self, GetAll, HeaderMap, HeaderValue};
+use crate::token::{is_token, Parser};
+
+pub use self::basic::Basic;
+pub use self::bearer::{Bearer, BearerChallenge, BearerError};
+pub use self::digest::{Algorithm, DigestChallenge, DigestCredentials};
+
+mod basic;
+mod bearer;
+mod digest;
+mod md5;
+
+/// The credentials of an `Authorization` or `Proxy-Authorization` header.
+///
+/// # Examples
+///
+/// ```
+/// # use http::auth::Credentials;
+/// let credentials: Credentials = "Bearer mF_9.B5f-4.1JqM".parse().unwrap();
+///
+/// assert!(credentials.is_scheme("bearer"));
+/// assert_eq!(credentials.token68(), Some("mF_9.B5f-4.1JqM"));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    inner: Parts,
+}
+
+/// A challenge of a `WWW-Authenticate` or `Proxy-Authenticate` header.
+///
+/// A single header value may contain several challenges, see
+/// [`Challenge::parse_all`].
+///
+/// [`Challenge::parse_all`]: #method.parse_all
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    inner: Parts,
+}
+
+/// The `auth-param`s of a `Credentials` or `Challenge`.
+///
+/// Parameter names are case-insensitive. Parameters keep the order in which
+/// they were inserted or parsed.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct AuthParams {
+    params: Vec<Param>,
+}
+
+/// An iterator over the names and values of `AuthParams`.
+#[derive(Debug)]
+pub struct Iter<'a> {
+    inner: std::slice::Iter<'a, Param>,
+}
+
+/// An error resulting from invalid or unsupported authentication data.
+pub struct InvalidAuth {
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    InvalidSyntax,
+    InvalidScheme,
+    InvalidToken68,
+    InvalidParamName,
+    InvalidParamValue,
+    WrongScheme,
+    MissingParam,
+    InvalidBasic,
+    UnsupportedAlgorithm,
+    UnsupportedQop,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Parts {
+    scheme: String,
+    token68: Option<String>,
+    params: AuthParams,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Param {
+    name: String,
+    value: String,
+    quoted: bool,
+}
+
+// ===== impl Credentials =====
+
+impl Credentials {
+    /// Creates credentials with the given scheme and a `token68`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::Credentials;
+    /// let credentials = Credentials::with_token68("Bearer", "abc.def").unwrap();
+    /// assert_eq!(credentials.to_string(), "Bearer abc.def");
+    ///
+    /// assert!(Credentials::with_token68("Bearer", "not a token").is_err());
+    /// ```
+    pub fn with_token68(scheme: &str, token68: &str) -> Result<Credentials, InvalidAuth> {
+        Parts::with_token68(scheme, token68).map(|inner| Credentials { inner })
+    }
+
+    /// Creates credentials with the given scheme and parameters.
+    pub fn with_params(scheme: &str, params: AuthParams) -> Result<Credentials, InvalidAuth> {
+        Parts::with_params(scheme, params).map(|inner| Credentials { inner })
+    }
+
+    /// Parses the credentials of an `Authorization` or
+    /// `Proxy-Authorization` header value.
+    pub fn parse(value: &HeaderValue) -> Result<Credentials, InvalidAuth> {
+        str::from_utf8(value.as_bytes())
+            .map_err(|_| InvalidAuth::new(ErrorKind::InvalidSyntax))?
+            .parse()
+    }
+
+    /// Returns the authentication scheme, as it was written.
+    pub fn scheme(&self) -> &str {
+        &self.inner.scheme
+    }
+
+    /// Returns `true` if the authentication scheme is `scheme`, compared
+    /// case-insensitively.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.inner.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    /// Returns the `token68`, if the credentials have one.
+    pub fn token68(&self) -> Option<&str> {
+        self.inner.token68.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the parameters of the credentials.
+    ///
+    /// This is empty if the credentials have a `token68`.
+    pub fn params(&self) -> &AuthParams {
+        &self.inner.params
+    }
+}
+
+impl FromStr for Credentials {
+    type Err = InvalidAuth;
+
+    fn from_str(s: &str) -> Result<Credentials, InvalidAuth> {
+        let mut list = parse_list(s)?;
+        if list.len() != 1 {
+            return Err(InvalidAuth::new(ErrorKind::InvalidSyntax));
+        }
+        Ok(Credentials {
+            inner: list.pop().unwrap(),
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a HeaderValue> for Credentials {
+    type Error = InvalidAuth;
+
+    #[inline]
+    fn try_from(value: &'a HeaderValue) -> Result<Self, Self::Error> {
+        Credentials::parse(value)
+    }
+}
+
+impl fmt::Display for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("scheme", &self.inner.scheme)
+            .finish()
+    }
+}
+
+impl From<Credentials> for HeaderValue {
+    fn from(credentials: Credentials) -> HeaderValue {
+        let mut value = to_header_value(&credentials);
+        value.set_sensitive(true);
+        value
+    }
+}
+
+// ===== impl Challenge =====
+
+impl Challenge {
+    /// Creates a challenge with the given scheme and a `token68`.
+    pub fn with_token68(scheme: &str, token68: &str) -> Result<Challenge, InvalidAuth> {
+        Parts::with_token68(scheme, token68).map(|inner| Challenge { inner })
+    }
+
+    /// Creates a challenge with the given scheme and parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::{AuthParams, Challenge};
+    /// let mut params = AuthParams::new();
+    /// params.insert("realm", "apps").unwrap();
+    /// params.insert_token("type", "1").unwrap();
+    ///
+    /// let challenge = Challenge::with_params("Newauth", params).unwrap();
+    /// assert_eq!(challenge.to_string(), "Newauth realm=\"apps\", type=1");
+    /// ```
+    pub fn with_params(scheme: &str, params: AuthParams) -> Result<Challenge, InvalidAuth> {
+        Parts::with_params(scheme, params).map(|inner| Challenge { inner })
+    }
+
+    /// Parses all the challenges of a `WWW-Authenticate` or
+    /// `Proxy-Authenticate` header value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::auth::Challenge;
+    /// # use http::header::HeaderValue;
+    /// let value = HeaderValue::from_static(
+    ///     "Newauth realm=\"apps\", type=1, title=\"Login to \\\"apps\\\"\", Basic realm=\"simple\"",
+    /// );
+    ///
+    /// let challenges = Challenge::parse_all(&value).unwrap();
+    /// assert_eq!(challenges.len(), 2);
+    /// assert_eq!(challenges[0].scheme(), "Newauth");
+    /// assert_eq!(challenges[0].params().get("title"), Some("Login to \"apps\""));
+    /// assert_eq!(challenges[1].scheme(), "Basic");
+    /// assert_eq!(challenges[1].params().get("realm"), Some("simple"));
+    /// ```
+    pub fn parse_all(value: &HeaderValue) -> Result<Vec<Challenge>, InvalidAuth> {
+        let s = str::from_utf8(value.as_bytes())
+            .map_err(|_| InvalidAuth::new(ErrorKind::InvalidSyntax))?;
+        Ok(parse_list(s)?
+            .into_iter()
+            .map(|inner| Challenge { inner })
+            .collect())
+    }
+
+    /// Parses all the challenges of a set of header values.
+    pub fn from_values(values: GetAll<'_, HeaderValue>) -> Result<Vec<Challenge>, InvalidAuth> {
+        let mut challenges = Vec::new();
+        for value in values {
+            challenges.extend(Challenge::parse_all(value)?);
+        }
+        Ok(challenges)
+    }
+
+    /// Parses all the challenges of the `WWW-Authenticate` headers in a
+    /// `HeaderMap`.
+    pub fn from_headers(headers: &HeaderMap<HeaderValue>) -> Result<Vec<Challenge>, InvalidAuth> {
+        Challenge::from_values(headers.get_all(header::WWW_AUTHENTICATE))
+    }
+
+    /// Returns the authentication scheme, as it was written.
+    pub fn scheme(&self) -> &str {
+        &self.inner.scheme
+    }
+
+    /// Returns `true` if the authentication scheme is `scheme`, compared
+    /// case-insensitively.
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.inner.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    /// Returns the `token68`, if the challenge has one.
+    pub fn token68(&self) -> Option<&str> {
+        self.inner.token68.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the parameters of the challenge.
+    ///
+    /// This is empty if the challenge has a `token68`.
+    pub fn params(&self) -> &AuthParams {
+        &self.inner.params
+    }
+}
+
+impl FromStr for Challenge {
+    type Err = InvalidAuth;
+
+    /// Parses a single challenge.
+    fn from_str(s: &str) -> Result<Challenge, InvalidAuth> {
+        let mut list = parse_list(s)?;
+        if list.len() != 1 {
+            return Err(InvalidAuth::new(ErrorKind::InvalidSyntax));
+        }
+        Ok(Challenge {
+            inner: list.pop().unwrap(),
+        })
+    }
+}
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl From<Challenge> for HeaderValue {
+    fn from(challenge: Challenge) -> HeaderValue {
+        to_header_value(&challenge)
+    }
+}
+
+// ===== impl Parts =====
+
+impl Parts {
+    fn with_token68(scheme: &str, token68: &str) -> Result<Parts, InvalidAuth> {
+        let mut parser = Parser::new(token68);
+        if parser.token68().is_none() || !parser.is_end() {
+            return Err(InvalidAuth::new(ErrorKind::InvalidToken68));
+        }
+
+        Ok(Parts {
+            scheme: check_scheme(scheme)?,
+            token68: Some(token68.to_string()),
+            params: AuthParams::new(),
+        })
+    }
+
+    fn with_params(scheme: &str, params: AuthParams) -> Result<Parts, InvalidAuth> {
+        Ok(Parts {
+            scheme: check_scheme(scheme)?,
+            token68: None,
+            params,
+        })
+    }
+}
+
+impl fmt::Display for Parts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.scheme)?;
+
+        if let Some(ref token68) = self.token68 {
+            write!(f, " {}", token68)?;
+        }
+
+        for (i, param) in self.params.params.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { ", " })?;
+            f.write_str(&param.name)?;
+            f.write_char('=')?;
+            if param.quoted {
+                f.write_char('"')?;
+                for c in param.value.chars() {
+                    if c == '"' || c == '\\' {
+                        f.write_char('\\')?;
+                    }
+                    f.write_char(c)?;
+                }
+                f.write_char('"')?;
+            } else {
+                f.write_str(&param.value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn check_scheme(scheme: &str) -> Result<String, InvalidAuth> {
+    if is_token(scheme) {
+        Ok(scheme.to_string())
+    } else {
+        Err(InvalidAuth::new(ErrorKind::InvalidScheme))
+    }
+}
+
+fn to_header_value<T: fmt::Display>(value: &T) -> HeaderValue {
+    let buf = value.to_string();
+    // Safety: schemes, token68s and parameters are validated to not contain
+    // any control characters.
+    unsafe { HeaderValue::from_maybe_shared_unchecked(Bytes::from(buf)) }
+}
+
+// ===== impl AuthParams =====
+
+impl AuthParams {
+    /// Creates an empty list of parameters.
+    pub fn new() -> AuthParams {
+        AuthParams::default()
+    }
+
+    /// Returns the value of the parameter with the given name.
+    ///
+    /// If there are several parameters with this name, the first one is
+    /// returned.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name).map(|i| &self.params[i].value[..])
+    }
+
+    /// Sets a parameter, serialized as a quoted string.
+    ///
+    /// If a parameter with this name already exists, its value is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is not a token, or if `value` contains
+    /// control characters.
+    pub fn insert(&mut self, name: &str, value: &str) -> Result<(), InvalidAuth> {
+        if value.bytes().any(|b| (b < 0x20 && b != b'\t') || b == 0x7f) {
+            return Err(InvalidAuth::new(ErrorKind::InvalidParamValue));
+        }
+        self.insert_param(name, value, true)
+    }
+
+    /// Sets a parameter, serialized as a bare token.
+    ///
+    /// Some parameters, such as the `algorithm` of the Digest scheme, must not
+    /// be quoted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` or `value` is not a token.
+    pub fn insert_token(&mut self, name: &str, value: &str) -> Result<(), InvalidAuth> {
+        if !is_token(value) {
+            return Err(InvalidAuth::new(ErrorKind::InvalidParamValue));
+        }
+        self.insert_param(name, value, false)
+    }
+
+    /// Removes a parameter, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.position(name).map(|i| self.params.remove(i).value)
+    }
+
+    /// Returns an iterator over the names and values of the parameters.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.params.iter(),
+        }
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    fn insert_param(&mut self, name: &str, value: &str, quoted: bool) -> Result<(), InvalidAuth> {
+        if !is_token(name) {
+            return Err(InvalidAuth::new(ErrorKind::InvalidParamName));
+        }
+
+        let param = Param {
+            name: name.to_string(),
+            value: value.to_string(),
+            quoted,
+        };
+        match self.position(name) {
+            Some(i) => self.params[i] = param,
+            None => self.params.push(param),
+        }
+        Ok(())
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.params
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    // Returns the value of a required parameter.
+    fn require(&self, name: &str) -> Result<&str, InvalidAuth> {
+        self.get(name)
+            .ok_or_else(|| InvalidAuth::new(ErrorKind::MissingParam))
+    }
+}
+
+impl fmt::Debug for AuthParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a AuthParams {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|p| (&p.name[..], &p.value[..]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+// ===== parsing =====
+
+// #challenge, or a single credentials:
+//
+//   challenge   = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+//   auth-param  = token BWS "=" BWS ( token / quoted-string )
+//   token68     = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+//
+// Since both challenges and auth-params are separated by commas, an element
+// following a comma is an auth-param if it starts with `token BWS "="`, and a
+// new challenge otherwise.
+fn parse_list(s: &str) -> Result<Vec<Parts>, InvalidAuth> {
+    let mut parser = Parser::new(s);
+    let mut list = Vec::new();
+
+    loop {
+        parser.skip_empty_elements();
+        if parser.is_end() {
+            break;
+        }
+
+        let scheme = parser.token().ok_or_else(InvalidAuth::syntax)?;
+        let mut parts = Parts {
+            scheme: scheme.to_string(),
+            token68: None,
+            params: AuthParams::new(),
+        };
+
+        let spaces = parser.skip_sp();
+        if parser.is_end() || parser.peek() == Some(b',') {
+            list.push(parts);
+            continue;
+        }
+        if spaces == 0 {
+            return Err(InvalidAuth::syntax());
+        }
+
+        let start = parser.pos;
+        if let Some(token68) = parser.token68() {
+            parser.skip_ows();
+            if parser.is_end() || parser.peek() == Some(b',') {
+                parts.token68 = Some(token68.to_string());
+                list.push(parts);
+                continue;
+            }
+            parser.pos = start;
+        }
+
+        loop {
+            let name = parser.token().ok_or_else(InvalidAuth::syntax)?;
+            parser.skip_ows();
+            if !parser.eat(b'=') {
+                return Err(InvalidAuth::syntax());
+            }
+            parser.skip_ows();
+
+            let (value, quoted) = if parser.peek() == Some(b'"') {
+                (
+                    parser.quoted_string().ok_or_else(InvalidAuth::syntax)?,
+                    true,
+                )
+            } else {
+                let token = parser.token().ok_or_else(InvalidAuth::syntax)?;
+                (token.to_string(), false)
+            };
+            parts.params.params.push(Param {
+                name: name.to_string(),
+                value,
+                quoted,
+            });
+
+            parser.skip_ows();
+            if parser.is_end() {
+                break;
+            }
+            if !parser.eat(b',') {
+                return Err(InvalidAuth::syntax());
+            }
+
+            let mark = parser.pos;
+            parser.skip_empty_elements();
+            let next = parser.pos;
+            if parser.token().is_some() {
+                parser.skip_ows();
+                if parser.peek() == Some(b'=') {
+                    parser.pos = next;
+                    continue;
+                }
+            }
+            parser.pos = mark;
+            break;
+        }
+
+        list.push(parts);
+    }
+
+    Ok(list)
+}
+
+/// Lowercase hexadecimal encoding, as used by the Digest scheme.
+fn hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        write!(s, "{:02x}", b).unwrap();
+    }
+    s
+}
+
+// ===== impl InvalidAuth =====
+
+impl InvalidAuth {
+    fn new(kind: ErrorKind) -> InvalidAuth {
+        InvalidAuth { kind }
+    }
+
+    fn syntax() -> InvalidAuth {
+        InvalidAuth::new(ErrorKind::InvalidSyntax)
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::InvalidSyntax => "invalid authentication header syntax",
+            ErrorKind::InvalidScheme => "invalid authentication scheme",
+            ErrorKind::InvalidToken68 => "invalid token68",
+            ErrorKind::InvalidParamName => "invalid auth-param name",
+            ErrorKind::InvalidParamValue => "invalid auth-param value",
+            ErrorKind::WrongScheme => "unexpected authentication scheme",
+            ErrorKind::MissingParam => "missing required auth-param",
+            ErrorKind::InvalidBasic => "invalid Basic credentials",
+            ErrorKind::UnsupportedAlgorithm => "unsupported Digest algorithm",
+            ErrorKind::UnsupportedQop => "unsupported Digest quality of protection",
+        }
+    }
+}
+
+impl fmt::Debug for InvalidAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidAuth").field(&self.kind).finish()
+    }
+}
+
+impl fmt::Display for InvalidAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for InvalidAuth {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(challenge: &Challenge) -> Vec<(&str, &str)> {
+        challenge.params().iter().collect()
+    }
+
+    #[test]
+    fn parses_token68() {
+        let credentials: Credentials = "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==".parse().unwrap();
+        assert_eq!(credentials.scheme(), "Basic");
+        assert_eq!(credentials.token68(), Some("QWxhZGRpbjpvcGVuIHNlc2FtZQ=="));
+        assert!(credentials.params().is_empty());
+
+        let credentials: Credentials = "Negotiate   abc/+=  ".parse().unwrap();
+        assert_eq!(credentials.token68(), Some("abc/+="));
+    }
+
+    #[test]
+    fn parses_params() {
+        let challenge: Challenge = "Digest realm=\"a, b\" ,qop = auth, stale=TRUE"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            params(&challenge),
+            [("realm", "a, b"), ("qop", "auth"), ("stale", "TRUE")]
+        );
+        assert_eq!(challenge.params().get("REALM"), Some("a, b"));
+        assert_eq!(challenge.params().get("nonce"), None);
+    }
+
+    #[test]
+    fn parses_multiple_challenges() {
+        let value = HeaderValue::from_static(
+            "Basic, Bearer realm=\"x\", error=invalid_token,, Negotiate abc=, Digest nonce=\"n\"",
+        );
+        let challenges = Challenge::parse_all(&value).unwrap();
+        let schemes: Vec<_> = challenges.iter().map(Challenge::scheme).collect();
+        assert_eq!(schemes, ["Basic", "Bearer", "Negotiate", "Digest"]);
+
+        assert!(challenges[0].params().is_empty());
+        assert_eq!(
+            params(&challenges[1]),
+            [("realm", "x"), ("error", "invalid_token")]
+        );
+        assert_eq!(challenges[2].token68(), Some("abc="));
+        assert_eq!(params(&challenges[3]), [("nonce", "n")]);
+
+        let mut headers = HeaderMap::new();
+        headers.append(header::WWW_AUTHENTICATE, value);
+        headers.append(header::WWW_AUTHENTICATE, HeaderValue::from_static("Mutual"));
+        assert_eq!(Challenge::from_headers(&headers).unwrap().len(), 5);
+
+        assert!(Challenge::parse_all(&HeaderValue::from_static(""))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_syntax() {
+        let invalid = [
+            "",
+            "Basic a b",
+            "Basic realm=x=",
+            "Basic realm=\"unterminated",
+            "Basic realm=\"x\" y",
+            "Basic=x",
+            "Basic\trealm=x",
+            "Basic a, Bearer b",
+            "Basic realm=\"x\", =y",
+        ];
+        for s in &invalid {
+            assert!(s.parse::<Credentials>().is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn quoted_string_escapes() {
+        let challenge: Challenge = r#"X a="\"q\" \\ \é", b="""#.parse().unwrap();
+        assert_eq!(params(&challenge), [("a", "\"q\" \\ é"), ("b", "")]);
+
+        let mut params = AuthParams::new();
+        params.insert("a", "\"q\" \\").unwrap();
+        let challenge = Challenge::with_params("X", params).unwrap();
+        assert_eq!(challenge.to_string(), r#"X a="\"q\" \\""#);
+        assert_eq!(
+            challenge.to_string().parse::<Challenge>().unwrap(),
+            challenge
+        );
+    }
+
+    #[test]
+    fn roundtrips() {
+        let inputs = [
+            "Basic",
+            "Bearer abc.def",
+            "Newauth realm=\"apps\", type=1, title=\"Login to \\\"apps\\\"\"",
+        ];
+        for s in &inputs {
+            assert_eq!(s.parse::<Challenge>().unwrap().to_string(), *s);
+        }
+    }
+
+    #[test]
+    fn auth_params() {
+        let mut params = AuthParams::new();
+        params.insert("realm", "a").unwrap();
+        params.insert_token("algorithm", "MD5").unwrap();
+        params.insert("Realm", "b").unwrap();
+        assert_eq!(
+            params.iter().collect::<Vec<_>>(),
+            [("Realm", "b"), ("algorithm", "MD5")]
+        );
+
+        assert!(params.insert("bad name", "x").is_err());
+        assert!(params.insert("ok", "new\nline").is_err());
+        assert!(params.insert_token("ok", "not token").is_err());
+
+        assert_eq!(params.remove("REALM"), Some("b".to_string()));
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn credentials_are_sensitive() {
+        let credentials = Credentials::with_token68("Bearer", "secret").unwrap();
+        assert!(!format!("{:?}", credentials).contains("secret"));
+
+        let value = HeaderValue::from(credentials);
+        assert_eq!(value, "Bearer secret");
+        assert!(value.is_sensitive());
+
+        let challenge: Challenge = "Bearer realm=\"x\"".parse().unwrap();
+        assert!(!HeaderValue::from(challenge).is_sensitive());
+    }
+
+    #[test]
+    fn rejects_invalid_parts() {
+        assert!(Credentials::with_token68("Bad scheme", "abc").is_err());
+        assert!(Credentials::with_token68("Bearer", "").is_err());
+        assert!(Credentials::with_token68("Bearer", "a=b").is_err());
+        assert!(Challenge::with_params("", AuthParams::new()).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Standard base64 encoding, as defined in RFC 4648, section 4.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as padded base64.
+pub(crate) fn encode(src: &[u8]) -> String {
+    let mut dst = String::with_capacity((src.len() + 2) / 3 * 4);
+
+    for chunk in src.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).cloned().unwrap_or(0),
+            chunk.get(2).cloned().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                dst.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                dst.push('=');
+            }
+        }
+    }
+
+    dst
+}
+
+/// Decodes padded base64.
+///
+/// Returns `None` if the input contains characters outside the alphabet, is
+/// not padded to a multiple of four characters, or has non-zero trailing
+/// bits.
+pub(crate) fn decode(src: &[u8]) -> Option<Vec<u8>> {
+    if src.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut dst = Vec::with_capacity(src.len() / 4 * 3);
+
+    for (i, chunk) in src.chunks(4).enumerate() {
+        let last = i == src.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for &b in &chunk[..4 - padding] {
+            n = (n << 6) | u32::from(value(b)?);
+        }
+        n <<= 6 * padding as u32;
+
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        let len = 3 - padding;
+        if bytes[len..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        dst.extend_from_slice(&bytes[..len]);
+    }
+
+    Some(dst)
+}
+
+/// Decodes base64 leniently: padding may be omitted, and non-zero trailing
+/// bits are ignored.
+pub(crate) fn decode_lenient(src: &[u8]) -> Option<Vec<u8>> {
+    let data = match src.iter().position(|&b| b == b'=') {
+        Some(i) if src.len() % 4 == 0 && src.len() - i <= 2 => {
+            if src[i..].iter().any(|&b| b != b'=') {
+                return None;
+            }
+            &src[..i]
+        }
+        Some(_) => return None,
+        None => src,
+    };
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut dst = Vec::with_capacity(data.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut n = 0;
+    for &b in data {
+        bits = (bits << 6) | u32::from(value(b)?);
+        n += 6;
+        if n >= 8 {
+            n -= 8;
+            dst.push((bits >> n) as u8);
+        }
+    }
+
+    Some(dst)
+}
+
+/// Decodes base64 with the WHATWG "forgiving-base64 decode" algorithm:
+/// ASCII whitespace is ignored, and otherwise like `decode_lenient`.
+pub(crate) fn decode_forgiving(src: &[u8]) -> Option<Vec<u8>> {
+    let data: Vec<u8> = src
+        .iter()
+        .cloned()
+        .filter(|&b| !matches!(b, b'\t' | b'\n' | b'\x0c' | b'\r' | b' '))
+        .collect();
+    decode_lenient(&data)
+}
+
+fn value(b: u8) -> Option<u8> {
+    match b {
+        b'A'..=b'Z' => Some(b - b'A'),
+        b'a'..=b'z' => Some(b - b'a' + 26),
+        b'0'..=b'9' => Some(b - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VECTORS: &[(&str, &str)] = &[
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn rfc4648_vectors() {
+        for &(plain, encoded) in VECTORS {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded.as_bytes()).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(encode(&bytes).as_bytes()).unwrap(), bytes);
+        assert_eq!(encode(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        assert_eq!(decode(b"Zg="), None);
+        assert_eq!(decode(b"Zg"), None);
+        assert_eq!(decode(b"Z==="), None);
+        assert_eq!(decode(b"Zg==Zg=="), None);
+        assert_eq!(decode(b"Zh=="), None);
+        assert_eq!(decode(b"Zm9v YmFy"), None);
+        assert_eq!(decode(b"Zm9-"), None);
+    }
+
+    #[test]
+    fn lenient() {
+        for &(plain, encoded) in VECTORS {
+            assert_eq!(
+                decode_lenient(encoded.as_bytes()).unwrap(),
+                plain.as_bytes()
+            );
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(
+                decode_lenient(unpadded.as_bytes()).unwrap(),
+                plain.as_bytes()
+            );
+        }
+        assert_eq!(decode_lenient(b"Zh==").unwrap(), b"f");
+        assert_eq!(decode_lenient(b"Zg="), None);
+        assert_eq!(decode_lenient(b"Z"), None);
+        assert_eq!(decode_lenient(b"Zg=a"), None);
+        assert_eq!(decode_lenient(b"Zm9v YmFy"), None);
+    }
+
+    #[test]
+    fn forgiving() {
+        assert_eq!(decode_forgiving(b" Zm9v\tYm\r\nFy ").unwrap(), b"foobar");
+        assert_eq!(decode_forgiving(b"Zg = =").unwrap(), b"f");
+        assert_eq!(decode_forgiving(b"Zg="), None);
+        assert_eq!(decode_forgiving(b"Z\x0bg"), None);
+    }
+}
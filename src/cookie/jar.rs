@@ -0,0 +1,512 @@
+use std::cmp;
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+
+use super::{SetCookie, SameSite};
+use crate::header::{self, HeaderValue};
+use crate::uri::Scheme;
+use crate::{Request, Response, Uri};
+
+/// A client-side store of cookies.
+///
+/// A `CookieJar` records the cookies set by `Response`s and attaches the
+/// matching ones to outgoing `Request`s, following the storage and retrieval
+/// model of RFC 6265bis, Sections 5.7 and 5.8.
+///
+/// Cookies are matched against requests using the host (`Uri::host()`), path
+/// (`Uri::path()`) and scheme of the request URI:
+///
+/// * a cookie without a `Domain` attribute is only sent back to the exact host
+///   that set it, a cookie with one is also sent to its subdomains,
+/// * a cookie is only sent to paths at or below its `Path`,
+/// * a `Secure` cookie is only sent over `https`.
+///
+/// The jar does not consult a public suffix list, so it is up to the
+/// application to reject cookies scoped to domains like `co.uk` if it needs
+/// to. `SameSite` is not enforced since the jar has no notion of the site that
+/// initiated a request.
+///
+/// # Examples
+///
+/// ```
+/// use http::cookie::CookieJar;
+/// use http::header::{COOKIE, SET_COOKIE};
+/// use http::{Request, Response, Uri};
+///
+/// let mut jar = CookieJar::new();
+///
+/// let uri = Uri::from_static("https://example.com/login");
+/// let response = Response::builder()
+///     .header(SET_COOKIE, "session=abc; Path=/; Secure")
+///     .header(SET_COOKIE, "theme=dark; Path=/settings")
+///     .body(())
+///     .unwrap();
+/// jar.store(&uri, &response);
+///
+/// let mut request = Request::get("https://example.com/account").body(()).unwrap();
+/// jar.add_cookie_header(&mut request);
+/// assert_eq!(request.headers()[COOKIE], "session=abc");
+///
+/// let mut request = Request::get("https://example.com/settings").body(()).unwrap();
+/// jar.add_cookie_header(&mut request);
+/// assert_eq!(request.headers()[COOKIE], "theme=dark; session=abc");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+    next_creation: u64,
+}
+
+#[derive(Clone, Debug)]
+struct StoredCookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+    expires: Option<SystemTime>,
+    // Monotonic creation order, used to order cookies with equal paths.
+    creation: u64,
+}
+
+impl CookieJar {
+    /// Creates an empty `CookieJar`.
+    pub fn new() -> CookieJar {
+        CookieJar::default()
+    }
+
+    /// Stores the cookies from all the `Set-Cookie` headers of a `Response`.
+    ///
+    /// `request_uri` is the URI of the request the response answers. Invalid
+    /// `Set-Cookie` headers, and cookies the request URI is not allowed to
+    /// set, are ignored.
+    pub fn store<T>(&mut self, request_uri: &Uri, response: &Response<T>) {
+        let now = SystemTime::now();
+        for value in response.headers().get_all(header::SET_COOKIE) {
+            if let Ok(cookie) = SetCookie::parse(value) {
+                self.store_cookie_at(request_uri, cookie, now);
+            }
+        }
+    }
+
+    /// Stores a single cookie set by a response to `request_uri`.
+    ///
+    /// Returns `false` if the cookie was rejected, for example because its
+    /// `Domain` doesn't match the request host, or because it is `Secure` and
+    /// the request was not. A cookie that is already expired removes any
+    /// stored cookie it would replace and returns `true`.
+    pub fn store_cookie(&mut self, request_uri: &Uri, cookie: SetCookie) -> bool {
+        self.store_cookie_at(request_uri, cookie, SystemTime::now())
+    }
+
+    /// Adds a `Cookie` header with the cookies matching the request's URI.
+    ///
+    /// Any existing `Cookie` header is replaced. If no cookies match, the
+    /// request is left unchanged.
+    pub fn add_cookie_header<T>(&self, request: &mut Request<T>) {
+        if let Some(value) = self.header_value(request.uri()) {
+            request.headers_mut().insert(header::COOKIE, value);
+        }
+    }
+
+    /// Returns the `Cookie` header value for a request to `uri`, if any
+    /// cookies match.
+    pub fn header_value(&self, uri: &Uri) -> Option<HeaderValue> {
+        self.header_value_at(uri, SystemTime::now())
+    }
+
+    /// Returns the name/value pairs of the cookies that would be sent with a
+    /// request to `uri`, in the order they would be sent.
+    pub fn matches(&self, uri: &Uri) -> Vec<(&str, &str)> {
+        self.matches_at(uri, SystemTime::now())
+            .into_iter()
+            .map(|c| (&c.name[..], &c.value[..]))
+            .collect()
+    }
+
+    /// Removes all cookies whose expiry time has passed.
+    pub fn remove_expired(&mut self) {
+        let now = SystemTime::now();
+        self.cookies.retain(|c| !c.is_expired(now));
+    }
+
+    /// Removes all cookies.
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    /// Returns the number of cookies stored, including expired ones that
+    /// have not been removed yet.
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// Returns `true` if the jar contains no cookies.
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    fn store_cookie_at(&mut self, request_uri: &Uri, cookie: SetCookie, now: SystemTime) -> bool {
+        let host = match request_uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        };
+        let secure = is_secure(request_uri);
+
+        // Only secure origins may set (or overwrite) secure cookies.
+        if cookie.secure() && !secure {
+            return false;
+        }
+        if cookie.check_prefixes().is_err() {
+            return false;
+        }
+        // SameSite=None without Secure is rejected by user agents.
+        if cookie.same_site() == Some(SameSite::None) && !cookie.secure() {
+            return false;
+        }
+
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) if is_ip_address(&host) => {
+                if domain != host {
+                    return false;
+                }
+                (host, true)
+            }
+            Some(domain) => {
+                if !domain_match(&host, domain) {
+                    return false;
+                }
+                (domain.to_string(), false)
+            }
+            None => (host, true),
+        };
+
+        let path = match cookie.path() {
+            Some(path) => path.to_string(),
+            None => default_path(request_uri.path()).to_string(),
+        };
+
+        // Max-Age takes precedence over Expires, and both are capped.
+        let max_expires = now + MAX_AGE;
+        let expires = match (cookie.max_age(), cookie.expires()) {
+            (Some(max_age), _) => Some(now + cmp::min(max_age, MAX_AGE)),
+            (None, Some(expires)) => Some(cmp::min(SystemTime::from(expires), max_expires)),
+            (None, None) => None,
+        };
+
+        if !secure {
+            // A non-secure origin must not shadow a secure cookie with the
+            // same name (RFC 6265bis, Section 5.7, step 16).
+            let shadows_secure = self.cookies.iter().any(|c| {
+                c.secure
+                    && c.name == cookie.name()
+                    && (domain_match(&domain, &c.domain) || domain_match(&c.domain, &domain))
+                    && path_match(&path, &c.path)
+            });
+            if shadows_secure {
+                return false;
+            }
+        }
+
+        let existing = self.cookies.iter().position(|c| {
+            c.name == cookie.name() && c.domain == domain && c.host_only == host_only && c.path == path
+        });
+
+        let creation = match existing {
+            Some(i) => self.cookies.remove(i).creation,
+            None => {
+                self.next_creation += 1;
+                self.next_creation
+            }
+        };
+
+        let stored = StoredCookie {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain,
+            host_only,
+            path,
+            secure: cookie.secure(),
+            expires,
+            creation,
+        };
+
+        if !stored.is_expired(now) {
+            self.cookies.push(stored);
+        }
+
+        true
+    }
+
+    fn matches_at(&self, uri: &Uri, now: SystemTime) -> Vec<&StoredCookie> {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return Vec::new(),
+        };
+        let secure = is_secure(uri);
+        let path = uri.path();
+
+        let mut matches: Vec<_> = self
+            .cookies
+            .iter()
+            .filter(|c| {
+                let domain_ok = if c.host_only {
+                    c.domain == host
+                } else {
+                    domain_match(&host, &c.domain)
+                };
+
+                domain_ok && path_match(path, &c.path) && (secure || !c.secure) && !c.is_expired(now)
+            })
+            .collect();
+
+        // Longer paths first, then earlier creation times.
+        matches.sort_by(|a, b| {
+            b.path
+                .len()
+                .cmp(&a.path.len())
+                .then(a.creation.cmp(&b.creation))
+        });
+
+        matches
+    }
+
+    fn header_value_at(&self, uri: &Uri, now: SystemTime) -> Option<HeaderValue> {
+        let matches = self.matches_at(uri, now);
+        if matches.is_empty() {
+            return None;
+        }
+
+        let mut buf = String::new();
+        for cookie in matches {
+            if !buf.is_empty() {
+                buf.push_str("; ");
+            }
+            buf.push_str(&cookie.name);
+            buf.push('=');
+            buf.push_str(&cookie.value);
+        }
+
+        // Safety: cookie names and values were parsed from header values and
+        // are visible ASCII.
+        Some(unsafe { HeaderValue::from_maybe_shared_unchecked(Bytes::from(buf)) })
+    }
+}
+
+impl StoredCookie {
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.map_or(false, |expires| expires <= now)
+    }
+}
+
+// Cookie lifetimes are capped at 400 days (RFC 6265bis, Section 5.5).
+const MAX_AGE: Duration = Duration::from_secs(400 * 24 * 60 * 60);
+
+fn is_secure(uri: &Uri) -> bool {
+    uri.scheme().map_or(false, Scheme::is_secure)
+}
+
+fn is_ip_address(host: &str) -> bool {
+    host.starts_with('[') || host.parse::<Ipv4Addr>().is_ok()
+}
+
+// RFC 6265bis, Section 5.1.3
+fn domain_match(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+
+    host.len() > domain.len()
+        && host.ends_with(domain)
+        && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+        && !is_ip_address(host)
+}
+
+// RFC 6265bis, Section 5.1.4
+fn default_path(uri_path: &str) -> &str {
+    if !uri_path.starts_with('/') {
+        return "/";
+    }
+
+    match uri_path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &uri_path[..i],
+    }
+}
+
+// RFC 6265bis, Section 5.1.4
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    if request_path == cookie_path {
+        return true;
+    }
+
+    request_path.starts_with(cookie_path)
+        && (cookie_path.ends_with('/') || request_path.as_bytes()[cookie_path.len()] == b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(s: &'static str) -> Uri {
+        Uri::from_static(s)
+    }
+
+    fn set(jar: &mut CookieJar, request_uri: &'static str, set_cookie: &str) -> bool {
+        jar.store_cookie(&uri(request_uri), set_cookie.parse().unwrap())
+    }
+
+    fn cookies(jar: &CookieJar, request_uri: &'static str) -> Vec<String> {
+        jar.matches(&uri(request_uri))
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect()
+    }
+
+    #[test]
+    fn host_only_cookies() {
+        let mut jar = CookieJar::new();
+        assert!(set(&mut jar, "http://example.com/", "a=1"));
+
+        assert_eq!(cookies(&jar, "http://example.com/"), ["a=1"]);
+        assert_eq!(cookies(&jar, "http://EXAMPLE.com/"), ["a=1"]);
+        assert!(cookies(&jar, "http://www.example.com/").is_empty());
+        assert!(cookies(&jar, "http://other.com/").is_empty());
+        assert!(cookies(&jar, "/relative").is_empty());
+    }
+
+    #[test]
+    fn domain_cookies() {
+        let mut jar = CookieJar::new();
+        assert!(set(&mut jar, "http://www.example.com/", "a=1; Domain=example.com"));
+        assert!(!set(&mut jar, "http://www.example.com/", "b=2; Domain=other.com"));
+        assert!(!set(&mut jar, "http://example.com/", "c=3; Domain=www.example.com"));
+        assert!(!set(&mut jar, "http://wwwexample.com/", "d=4; Domain=example.com"));
+
+        assert_eq!(cookies(&jar, "http://example.com/"), ["a=1"]);
+        assert_eq!(cookies(&jar, "http://a.b.example.com/"), ["a=1"]);
+        assert!(cookies(&jar, "http://notexample.com/").is_empty());
+    }
+
+    #[test]
+    fn ip_address_hosts() {
+        let mut jar = CookieJar::new();
+        assert!(set(&mut jar, "http://127.0.0.1/", "a=1; Domain=127.0.0.1"));
+        assert!(!set(&mut jar, "http://127.0.0.1/", "b=2; Domain=0.0.1"));
+        assert!(set(&mut jar, "http://[::1]/", "c=3"));
+
+        assert_eq!(cookies(&jar, "http://127.0.0.1/"), ["a=1"]);
+        assert_eq!(cookies(&jar, "http://[::1]/"), ["c=3"]);
+    }
+
+    #[test]
+    fn path_matching() {
+        let mut jar = CookieJar::new();
+        set(&mut jar, "http://example.com/", "root=1; Path=/");
+        set(&mut jar, "http://example.com/", "docs=1; Path=/docs");
+        set(&mut jar, "http://example.com/", "slash=1; Path=/docs/");
+        set(&mut jar, "http://example.com/a/b/page", "default=1");
+
+        assert_eq!(cookies(&jar, "http://example.com/"), ["root=1"]);
+        assert_eq!(cookies(&jar, "http://example.com/docs"), ["docs=1", "root=1"]);
+        assert_eq!(
+            cookies(&jar, "http://example.com/docs/x"),
+            ["slash=1", "docs=1", "root=1"]
+        );
+        assert_eq!(cookies(&jar, "http://example.com/docsx"), ["root=1"]);
+        assert_eq!(cookies(&jar, "http://example.com/a/b"), ["default=1", "root=1"]);
+        assert_eq!(cookies(&jar, "http://example.com/a"), ["root=1"]);
+    }
+
+    #[test]
+    fn default_paths() {
+        assert_eq!(default_path(""), "/");
+        assert_eq!(default_path("/"), "/");
+        assert_eq!(default_path("/a"), "/");
+        assert_eq!(default_path("/a/"), "/a");
+        assert_eq!(default_path("/a/b"), "/a");
+        assert_eq!(default_path("*"), "/");
+    }
+
+    #[test]
+    fn secure_cookies() {
+        let mut jar = CookieJar::new();
+        assert!(!set(&mut jar, "http://example.com/", "a=1; Secure"));
+        assert!(set(&mut jar, "https://example.com/", "a=1; Secure"));
+        assert!(set(&mut jar, "https://example.com/", "b=2"));
+
+        assert_eq!(cookies(&jar, "https://example.com/"), ["a=1", "b=2"]);
+        assert_eq!(cookies(&jar, "http://example.com/"), ["b=2"]);
+
+        // http can't shadow a secure cookie
+        assert!(!set(&mut jar, "http://example.com/", "a=2"));
+        assert_eq!(cookies(&jar, "https://example.com/"), ["a=1", "b=2"]);
+    }
+
+    #[test]
+    fn prefixed_cookies() {
+        let mut jar = CookieJar::new();
+        assert!(!set(&mut jar, "https://example.com/", "__Secure-a=1"));
+        assert!(set(&mut jar, "https://example.com/", "__Secure-a=1; Secure"));
+        assert!(!set(&mut jar, "https://example.com/", "__Host-b=1; Secure"));
+        assert!(!set(&mut jar, "https://example.com/", "__Host-b=1; Secure; Path=/; Domain=example.com"));
+        assert!(set(&mut jar, "https://example.com/", "__Host-b=1; Secure; Path=/"));
+        assert_eq!(jar.len(), 2);
+    }
+
+    #[test]
+    fn replaces_and_expires_cookies() {
+        let mut jar = CookieJar::new();
+        set(&mut jar, "http://example.com/", "a=1");
+        set(&mut jar, "http://example.com/", "b=1");
+        set(&mut jar, "http://example.com/", "a=2");
+
+        // replacing keeps the original creation time
+        assert_eq!(cookies(&jar, "http://example.com/"), ["a=2", "b=1"]);
+
+        set(&mut jar, "http://example.com/", "a=; Max-Age=0");
+        assert_eq!(cookies(&jar, "http://example.com/"), ["b=1"]);
+
+        set(&mut jar, "http://example.com/", "b=; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn expiry_is_checked_on_retrieval() {
+        let mut jar = CookieJar::new();
+        let now = SystemTime::now();
+        let cookie = "a=1; Max-Age=10".parse().unwrap();
+        jar.store_cookie_at(&uri("http://example.com/"), cookie, now);
+
+        let request = uri("http://example.com/");
+        assert!(jar.header_value_at(&request, now + Duration::from_secs(9)).is_some());
+        assert!(jar.header_value_at(&request, now + Duration::from_secs(10)).is_none());
+    }
+
+    #[test]
+    fn stores_from_responses_and_adds_to_requests() {
+        let mut jar = CookieJar::new();
+        let response = Response::builder()
+            .header(header::SET_COOKIE, "a=1")
+            .header(header::SET_COOKIE, "not a cookie")
+            .header(header::SET_COOKIE, "b=2; Path=/b")
+            .body(())
+            .unwrap();
+        jar.store(&uri("http://example.com/"), &response);
+        assert_eq!(jar.len(), 2);
+
+        let mut request = Request::get("http://example.com/b/c")
+            .header(header::COOKIE, "stale=1")
+            .body(())
+            .unwrap();
+        jar.add_cookie_header(&mut request);
+        assert_eq!(request.headers()[header::COOKIE], "b=2; a=1");
+
+        let mut request = Request::get("http://other.com/").body(()).unwrap();
+        jar.add_cookie_header(&mut request);
+        assert!(request.headers().get(header::COOKIE).is_none());
+    }
+}
@@ -0,0 +1,274 @@
+//! HTTP cookies.
+//!
+//! This module implements the state management mechanism described by
+//! [RFC 6265bis]: parsing the `Cookie` request header on servers, building
+//! and parsing `Set-Cookie` response headers, and a client-side [`CookieJar`]
+//! that stores cookies received in responses and attaches them to subsequent
+//! requests.
+//!
+//! # Examples
+//!
+//! Reading cookies sent by a client:
+//!
+//! ```
+//! use http::{cookie, Request};
+//!
+//! let request = Request::get("/")
+//!     .header("Cookie", "theme=dark; lang=en")
+//!     .header("Cookie", "session=abc123")
+//!     .body(())
+//!     .unwrap();
+//!
+//! let cookies: Vec<_> = cookie::Cookies::from_headers(request.headers()).collect();
+//! assert_eq!(cookies, [("theme", "dark"), ("lang", "en"), ("session", "abc123")]);
+//! ```
+//!
+//! Setting a cookie on a response:
+//!
+//! ```
+//! use http::cookie::{SameSite, SetCookie};
+//! use http::header::SET_COOKIE;
+//! use http::Response;
+//!
+//! let cookie = SetCookie::builder("session", "abc123")
+//!     .path("/")
+//!     .secure(true)
+//!     .http_only(true)
+//!     .same_site(SameSite::Lax)
+//!     .build()
+//!     .unwrap();
+//!
+//! let response = Response::builder()
+//!     .header(SET_COOKIE, cookie)
+//!     .body(())
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     response.headers()[SET_COOKIE],
+//!     "session=abc123; Path=/; Secure; HttpOnly; SameSite=Lax"
+//! );
+//! ```
+//!
+//! [RFC 6265bis]: https://datatracker.ietf.org/doc/draft-ietf-httpbis-rfc6265bis/
+//! [`CookieJar`]: struct.CookieJar.html
+
+use std::{fmt, str};
+
+use crate::header::{self, GetAll, HeaderMap, HeaderValue, ValueIter};
+use crate::token::is_tchar;
+
+pub use self::jar::CookieJar;
+pub use self::set_cookie::{Builder, InvalidSetCookie, SameSite, SetCookie};
+
+mod jar;
+mod set_cookie;
+
+/// An iterator over the name/value pairs of one or more `Cookie` headers.
+///
+/// Pairs are yielded in the order they appear, first across all the pairs
+/// of a single header value and then across header values. Pairs that are
+/// not well formed, or that contain bytes that are not valid UTF-8, are
+/// skipped. A value wrapped in double quotes is yielded without the quotes.
+///
+/// Since a client may send multiple cookies with the same name (for example
+/// with different paths), duplicate names are yielded as is.
+pub struct Cookies<'a> {
+    values: Option<ValueIter<'a, HeaderValue>>,
+    current: &'a [u8],
+}
+
+impl<'a> Cookies<'a> {
+    /// Iterate over the cookies of all the `Cookie` headers in a `HeaderMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cookie::Cookies;
+    /// # use http::header::{HeaderMap, HeaderValue, COOKIE};
+    /// let mut headers = HeaderMap::new();
+    /// headers.append(COOKIE, HeaderValue::from_static("a=1; b=\"2\""));
+    /// headers.append(COOKIE, HeaderValue::from_static("c=3"));
+    ///
+    /// let mut cookies = Cookies::from_headers(&headers);
+    /// assert_eq!(cookies.next(), Some(("a", "1")));
+    /// assert_eq!(cookies.next(), Some(("b", "2")));
+    /// assert_eq!(cookies.next(), Some(("c", "3")));
+    /// assert_eq!(cookies.next(), None);
+    /// ```
+    pub fn from_headers(headers: &'a HeaderMap<HeaderValue>) -> Cookies<'a> {
+        Cookies::new(headers.get_all(header::COOKIE))
+    }
+
+    /// Iterate over the cookies of a set of `Cookie` header values.
+    pub fn new(values: GetAll<'a, HeaderValue>) -> Cookies<'a> {
+        Cookies {
+            values: Some(values.iter()),
+            current: &[],
+        }
+    }
+
+    /// Iterate over the cookies of a single `Cookie` header value.
+    pub fn from_value(value: &'a HeaderValue) -> Cookies<'a> {
+        Cookies {
+            values: None,
+            current: value.as_bytes(),
+        }
+    }
+
+    /// Returns the value of the first cookie with the given name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cookie::Cookies;
+    /// # use http::header::HeaderValue;
+    /// let value = HeaderValue::from_static("a=1; b=2; a=3");
+    /// assert_eq!(Cookies::from_value(&value).get("a"), Some("1"));
+    /// assert_eq!(Cookies::from_value(&value).get("c"), None);
+    /// ```
+    pub fn get(self, name: &str) -> Option<&'a str> {
+        self.filter(|&(n, _)| n == name).map(|(_, v)| v).next()
+    }
+}
+
+impl<'a> Iterator for Cookies<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.current.is_empty() {
+                self.current = self.values.as_mut()?.next()?.as_bytes();
+            }
+
+            let (pair, rest) = match self.current.iter().position(|&b| b == b';') {
+                Some(i) => (&self.current[..i], &self.current[i + 1..]),
+                None => (self.current, &[][..]),
+            };
+            self.current = rest;
+
+            if let Some(pair) = parse_pair(pair) {
+                return Some(pair);
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Cookies<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cookies").finish()
+    }
+}
+
+fn parse_pair(pair: &[u8]) -> Option<(&str, &str)> {
+    let pair = trim(pair);
+    let eq = pair.iter().position(|&b| b == b'=')?;
+
+    let name = trim(&pair[..eq]);
+    let value = unquote(trim(&pair[eq + 1..]));
+
+    if name.is_empty() || !name.iter().all(|&b| is_tchar(b)) {
+        return None;
+    }
+    if !value.iter().all(|&b| is_cookie_octet(b)) {
+        return None;
+    }
+
+    // Safety: tokens and cookie-octets are all ASCII.
+    unsafe {
+        Some((
+            str::from_utf8_unchecked(name),
+            str::from_utf8_unchecked(value),
+        ))
+    }
+}
+
+fn trim(mut s: &[u8]) -> &[u8] {
+    while let Some((&b, rest)) = s.split_first() {
+        if b != b' ' && b != b'\t' {
+            break;
+        }
+        s = rest;
+    }
+    while let Some((&b, rest)) = s.split_last() {
+        if b != b' ' && b != b'\t' {
+            break;
+        }
+        s = rest;
+    }
+    s
+}
+
+fn unquote(s: &[u8]) -> &[u8] {
+    if s.len() >= 2 && s[0] == b'"' && s[s.len() - 1] == b'"' {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    }
+}
+
+// cookie-octet = %x21 / %x23-2B / %x2D-3A / %x3C-5B / %x5D-7E
+fn is_cookie_octet(b: u8) -> bool {
+    matches!(b, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::COOKIE;
+
+    fn parse(s: &'static str) -> Vec<(String, String)> {
+        let value = HeaderValue::from_static(s);
+        Cookies::from_value(&value)
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_pairs() {
+        assert_eq!(parse("a=1"), pairs(&[("a", "1")]));
+        assert_eq!(parse("a=1; b=2"), pairs(&[("a", "1"), ("b", "2")]));
+        assert_eq!(parse("a=1;b=2;"), pairs(&[("a", "1"), ("b", "2")]));
+        assert_eq!(parse("  a = 1 ;\tb=2 "), pairs(&[("a", "1"), ("b", "2")]));
+        assert_eq!(parse("a="), pairs(&[("a", "")]));
+        assert_eq!(parse("a=\"x y\""), pairs(&[]));
+        assert_eq!(parse("a=\"xy\""), pairs(&[("a", "xy")]));
+        assert_eq!(parse("a=b=c"), pairs(&[("a", "b=c")]));
+    }
+
+    #[test]
+    fn skips_malformed_pairs() {
+        assert_eq!(parse(""), pairs(&[]));
+        assert_eq!(parse(";;"), pairs(&[]));
+        assert_eq!(parse("novalue; a=1"), pairs(&[("a", "1")]));
+        assert_eq!(parse("=1; a=1"), pairs(&[("a", "1")]));
+        assert_eq!(parse("a b=1; c=2"), pairs(&[("c", "2")]));
+        assert_eq!(parse("a=1,2; c=2"), pairs(&[("c", "2")]));
+    }
+
+    #[test]
+    fn skips_non_ascii_pairs() {
+        let value = HeaderValue::from_bytes(b"a=\xff; b=2").unwrap();
+        let cookies: Vec<_> = Cookies::from_value(&value).collect();
+        assert_eq!(cookies, [("b", "2")]);
+    }
+
+    #[test]
+    fn iterates_all_header_values() {
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("a=1; b=2"));
+        headers.append(COOKIE, HeaderValue::from_static(""));
+        headers.append(COOKIE, HeaderValue::from_static("c=3"));
+
+        let cookies: Vec<_> = Cookies::from_headers(&headers).collect();
+        assert_eq!(cookies, [("a", "1"), ("b", "2"), ("c", "3")]);
+
+        assert_eq!(Cookies::from_headers(&HeaderMap::new()).next(), None);
+    }
+}
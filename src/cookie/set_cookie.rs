@@ -0,0 +1,864 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use super::is_cookie_octet;
+use crate::header::{HeaderValue, HttpDate};
+use crate::token::is_tchar;
+
+/// A cookie sent by a server in a `Set-Cookie` header.
+///
+/// A `SetCookie` is either created with a [`Builder`], which validates the
+/// cookie against the rules a user agent applies before storing it, or parsed
+/// from a `Set-Cookie` header value using the lenient algorithm user agents
+/// follow.
+///
+/// # Examples
+///
+/// ```
+/// # use http::cookie::{SameSite, SetCookie};
+/// # use http::header::HeaderValue;
+/// let value = HeaderValue::from_static("id=a3fWa; Max-Age=2592000; SameSite=Strict");
+/// let cookie = SetCookie::parse(&value).unwrap();
+///
+/// assert_eq!(cookie.name(), "id");
+/// assert_eq!(cookie.value(), "a3fWa");
+/// assert_eq!(cookie.max_age().unwrap().as_secs(), 2592000);
+/// assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+/// ```
+///
+/// [`Builder`]: struct.Builder.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    expires: Option<HttpDate>,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    partitioned: bool,
+}
+
+/// A builder for `SetCookie`s.
+///
+/// Errors from any of the methods are deferred until `build` is called.
+#[derive(Debug)]
+pub struct Builder {
+    inner: Result<SetCookie, InvalidSetCookie>,
+}
+
+/// The value of the `SameSite` cookie attribute.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SameSite {
+    /// The cookie is only sent with same-site requests.
+    Strict,
+    /// The cookie is sent with same-site requests and cross-site top-level
+    /// navigations.
+    Lax,
+    /// The cookie is sent with all requests. Requires the `Secure` attribute.
+    None,
+}
+
+/// An error resulting from an invalid `Set-Cookie` header or an invalid
+/// combination of cookie attributes.
+pub struct InvalidSetCookie {
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    InvalidName,
+    InvalidValue,
+    InvalidDomain,
+    InvalidPath,
+    InvalidSyntax,
+    TooLong,
+    SecurePrefix,
+    HostPrefix,
+    SameSiteNoneInsecure,
+    PartitionedInsecure,
+}
+
+// RFC 6265bis, Section 5.6: the combined length of the name and value, and
+// the length of any attribute value, is limited.
+const MAX_NAME_VALUE_LEN: usize = 4096;
+const MAX_ATTRIBUTE_VALUE_LEN: usize = 1024;
+
+impl SetCookie {
+    /// Creates a new `Builder` for a cookie with the given name and value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cookie::SetCookie;
+    /// let cookie = SetCookie::builder("lang", "en").path("/docs").build().unwrap();
+    /// assert_eq!(cookie.to_string(), "lang=en; Path=/docs");
+    /// ```
+    pub fn builder<N, V>(name: N, value: V) -> Builder
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        Builder::new(name, value)
+    }
+
+    /// Parses a `Set-Cookie` header value.
+    ///
+    /// Parsing follows the algorithm used by user agents (RFC 6265bis, Section
+    /// 5.6): unknown attributes and attributes with invalid values are
+    /// ignored, and when an attribute appears more than once the last one
+    /// wins. An error is returned if the name-value pair itself is malformed
+    /// or if the value contains bytes that are not visible ASCII.
+    ///
+    /// Note that the cookie prefix rules are *not* checked when parsing; they
+    /// are applied by a `CookieJar` when the cookie is stored.
+    pub fn parse(value: &HeaderValue) -> Result<SetCookie, InvalidSetCookie> {
+        value
+            .to_str()
+            .map_err(|_| InvalidSetCookie::new(ErrorKind::InvalidSyntax))?
+            .parse()
+    }
+
+    /// Returns the name of the cookie.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the value of the cookie.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the value of the `Expires` attribute.
+    pub fn expires(&self) -> Option<HttpDate> {
+        self.expires
+    }
+
+    /// Returns the value of the `Max-Age` attribute.
+    ///
+    /// A zero or negative `Max-Age` is represented as a zero `Duration`.
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Returns the value of the `Domain` attribute, without any leading dot
+    /// and in lower case.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the value of the `Path` attribute.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns `true` if the `Secure` attribute is set.
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Returns `true` if the `HttpOnly` attribute is set.
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    /// Returns the value of the `SameSite` attribute.
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    /// Returns `true` if the `Partitioned` attribute is set.
+    pub fn partitioned(&self) -> bool {
+        self.partitioned
+    }
+
+    // Checks the requirements of the `__Secure-` and `__Host-` cookie name
+    // prefixes (RFC 6265bis, Section 4.1.3).
+    pub(super) fn check_prefixes(&self) -> Result<(), InvalidSetCookie> {
+        if starts_with_ignore_case(&self.name, "__Secure-") && !self.secure {
+            return Err(InvalidSetCookie::new(ErrorKind::SecurePrefix));
+        }
+
+        if starts_with_ignore_case(&self.name, "__Host-") {
+            let path_is_root = self.path.as_ref().map_or(false, |p| p == "/");
+            if !self.secure || self.domain.is_some() || !path_is_root {
+                return Err(InvalidSetCookie::new(ErrorKind::HostPrefix));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn empty(name: String, value: String) -> SetCookie {
+        SetCookie {
+            name,
+            value,
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            partitioned: false,
+        }
+    }
+
+    fn set_attribute(&mut self, name: &str, value: &str) {
+        if value.len() > MAX_ATTRIBUTE_VALUE_LEN {
+            return;
+        }
+
+        if name.eq_ignore_ascii_case("Expires") {
+            if let Some(date) = parse_cookie_date(value) {
+                self.expires = Some(date);
+            }
+        } else if name.eq_ignore_ascii_case("Max-Age") {
+            if let Some(max_age) = parse_max_age(value) {
+                self.max_age = Some(max_age);
+            }
+        } else if name.eq_ignore_ascii_case("Domain") {
+            let domain = value.trim_start_matches('.');
+            self.domain = if domain.is_empty() {
+                None
+            } else {
+                Some(domain.to_ascii_lowercase())
+            };
+        } else if name.eq_ignore_ascii_case("Path") {
+            self.path = if value.starts_with('/') {
+                Some(value.to_string())
+            } else {
+                None
+            };
+        } else if name.eq_ignore_ascii_case("Secure") {
+            self.secure = true;
+        } else if name.eq_ignore_ascii_case("HttpOnly") {
+            self.http_only = true;
+        } else if name.eq_ignore_ascii_case("SameSite") {
+            self.same_site = SameSite::parse(value);
+        } else if name.eq_ignore_ascii_case("Partitioned") {
+            self.partitioned = true;
+        }
+    }
+}
+
+impl FromStr for SetCookie {
+    type Err = InvalidSetCookie;
+
+    fn from_str(s: &str) -> Result<SetCookie, InvalidSetCookie> {
+        // Everything must be representable as a `HeaderValue` again.
+        if s.bytes().any(|b| (b < 0x20 && b != b'\t') || b >= 0x7f) {
+            return Err(InvalidSetCookie::new(ErrorKind::InvalidSyntax));
+        }
+
+        let mut parts = s.split(';');
+        let pair = parts.next().expect("split always has at least 1 item");
+        let eq = pair
+            .find('=')
+            .ok_or_else(|| InvalidSetCookie::new(ErrorKind::InvalidSyntax))?;
+
+        let name = trim_str(&pair[..eq]);
+        let value = trim_str(&pair[eq + 1..]);
+
+        if name.is_empty() {
+            return Err(InvalidSetCookie::new(ErrorKind::InvalidName));
+        }
+        if name.len() + value.len() > MAX_NAME_VALUE_LEN {
+            return Err(InvalidSetCookie::new(ErrorKind::TooLong));
+        }
+
+        let mut cookie = SetCookie::empty(name.to_string(), value.to_string());
+
+        for attr in parts {
+            let (name, value) = match attr.find('=') {
+                Some(i) => (trim_str(&attr[..i]), trim_str(&attr[i + 1..])),
+                None => (trim_str(attr), ""),
+            };
+            cookie.set_attribute(name, value);
+        }
+
+        Ok(cookie)
+    }
+}
+
+impl<'a> TryFrom<&'a HeaderValue> for SetCookie {
+    type Error = InvalidSetCookie;
+
+    #[inline]
+    fn try_from(value: &'a HeaderValue) -> Result<Self, Self::Error> {
+        SetCookie::parse(value)
+    }
+}
+
+impl fmt::Display for SetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+
+        if let Some(ref expires) = self.expires {
+            write!(f, "; Expires={}", expires)?;
+        }
+        if let Some(ref max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(ref domain) = self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(ref path) = self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        if self.partitioned {
+            f.write_str("; Partitioned")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<SetCookie> for HeaderValue {
+    fn from(cookie: SetCookie) -> HeaderValue {
+        let mut buf = String::new();
+        write!(buf, "{}", cookie).expect("writing to a String never fails");
+
+        // Safety: both the builder and the parser reject anything that isn't
+        // visible ASCII or horizontal tab.
+        unsafe { HeaderValue::from_maybe_shared_unchecked(Bytes::from(buf)) }
+    }
+}
+
+// ===== impl Builder =====
+
+impl Builder {
+    /// Creates a new `Builder` for a cookie with the given name and value.
+    ///
+    /// The name must be a token and the value must consist of cookie-octets,
+    /// optionally surrounded by double quotes.
+    pub fn new<N, V>(name: N, value: V) -> Builder
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        let name = name.into();
+        let value = value.into();
+
+        let inner = if name.is_empty() || !name.bytes().all(is_tchar) {
+            Err(InvalidSetCookie::new(ErrorKind::InvalidName))
+        } else if !is_cookie_value(&value) {
+            Err(InvalidSetCookie::new(ErrorKind::InvalidValue))
+        } else if name.len() + value.len() > MAX_NAME_VALUE_LEN {
+            Err(InvalidSetCookie::new(ErrorKind::TooLong))
+        } else {
+            Ok(SetCookie::empty(name, value))
+        };
+
+        Builder { inner }
+    }
+
+    /// Sets the `Expires` attribute.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cookie::SetCookie;
+    /// # use http::header::HttpDate;
+    /// let cookie = SetCookie::builder("a", "b")
+    ///     .expires(HttpDate::UNIX_EPOCH)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(cookie.to_string(), "a=b; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+    /// ```
+    pub fn expires(self, expires: HttpDate) -> Builder {
+        self.and_then(|mut cookie| {
+            cookie.expires = Some(expires);
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `Max-Age` attribute, truncated to whole seconds.
+    pub fn max_age(self, max_age: Duration) -> Builder {
+        self.and_then(|mut cookie| {
+            cookie.max_age = Some(Duration::from_secs(max_age.as_secs()));
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `Domain` attribute.
+    ///
+    /// A leading dot is ignored and the domain is stored in lower case.
+    pub fn domain<T: Into<String>>(self, domain: T) -> Builder {
+        self.and_then(|mut cookie| {
+            let domain = domain.into();
+            let domain = domain.trim_start_matches('.');
+
+            if domain.is_empty() || !domain.bytes().all(is_attribute_octet) {
+                return Err(InvalidSetCookie::new(ErrorKind::InvalidDomain));
+            }
+            if domain.len() > MAX_ATTRIBUTE_VALUE_LEN {
+                return Err(InvalidSetCookie::new(ErrorKind::TooLong));
+            }
+
+            cookie.domain = Some(domain.to_ascii_lowercase());
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `Path` attribute. The path must start with a `/`.
+    pub fn path<T: Into<String>>(self, path: T) -> Builder {
+        self.and_then(|mut cookie| {
+            let path = path.into();
+
+            if !path.starts_with('/') || !path.bytes().all(is_attribute_octet) {
+                return Err(InvalidSetCookie::new(ErrorKind::InvalidPath));
+            }
+            if path.len() > MAX_ATTRIBUTE_VALUE_LEN {
+                return Err(InvalidSetCookie::new(ErrorKind::TooLong));
+            }
+
+            cookie.path = Some(path);
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `Secure` attribute.
+    pub fn secure(self, secure: bool) -> Builder {
+        self.and_then(|mut cookie| {
+            cookie.secure = secure;
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `HttpOnly` attribute.
+    pub fn http_only(self, http_only: bool) -> Builder {
+        self.and_then(|mut cookie| {
+            cookie.http_only = http_only;
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `SameSite` attribute.
+    pub fn same_site(self, same_site: SameSite) -> Builder {
+        self.and_then(|mut cookie| {
+            cookie.same_site = Some(same_site);
+            Ok(cookie)
+        })
+    }
+
+    /// Sets the `Partitioned` attribute.
+    pub fn partitioned(self, partitioned: bool) -> Builder {
+        self.and_then(|mut cookie| {
+            cookie.partitioned = partitioned;
+            Ok(cookie)
+        })
+    }
+
+    /// Consumes this builder, returning the `SetCookie`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the previous calls failed, or if the
+    /// attributes don't satisfy the rules a user agent enforces:
+    ///
+    /// * a `__Secure-` prefixed name requires `Secure`,
+    /// * a `__Host-` prefixed name requires `Secure`, `Path=/` and no
+    ///   `Domain`,
+    /// * `SameSite=None` requires `Secure`,
+    /// * `Partitioned` requires `Secure`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cookie::SetCookie;
+    /// let result = SetCookie::builder("__Host-id", "1").path("/").build();
+    /// assert!(result.is_err());
+    ///
+    /// let result = SetCookie::builder("__Host-id", "1").path("/").secure(true).build();
+    /// assert!(result.is_ok());
+    /// ```
+    pub fn build(self) -> Result<SetCookie, InvalidSetCookie> {
+        let cookie = self.inner?;
+
+        cookie.check_prefixes()?;
+
+        if cookie.same_site == Some(SameSite::None) && !cookie.secure {
+            return Err(InvalidSetCookie::new(ErrorKind::SameSiteNoneInsecure));
+        }
+        if cookie.partitioned && !cookie.secure {
+            return Err(InvalidSetCookie::new(ErrorKind::PartitionedInsecure));
+        }
+
+        Ok(cookie)
+    }
+
+    // private
+
+    fn and_then<F>(self, func: F) -> Self
+    where
+        F: FnOnce(SetCookie) -> Result<SetCookie, InvalidSetCookie>,
+    {
+        Builder {
+            inner: self.inner.and_then(func),
+        }
+    }
+}
+
+// ===== impl SameSite =====
+
+impl SameSite {
+    fn parse(s: &str) -> Option<SameSite> {
+        if s.eq_ignore_ascii_case("Strict") {
+            Some(SameSite::Strict)
+        } else if s.eq_ignore_ascii_case("Lax") {
+            Some(SameSite::Lax)
+        } else if s.eq_ignore_ascii_case("None") {
+            Some(SameSite::None)
+        } else {
+            None
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match *self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// ===== impl InvalidSetCookie =====
+
+impl InvalidSetCookie {
+    fn new(kind: ErrorKind) -> InvalidSetCookie {
+        InvalidSetCookie { kind }
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::InvalidName => "invalid cookie name",
+            ErrorKind::InvalidValue => "invalid cookie value",
+            ErrorKind::InvalidDomain => "invalid cookie domain",
+            ErrorKind::InvalidPath => "invalid cookie path",
+            ErrorKind::InvalidSyntax => "invalid set-cookie header",
+            ErrorKind::TooLong => "cookie too long",
+            ErrorKind::SecurePrefix => "__Secure- prefix requires the Secure attribute",
+            ErrorKind::HostPrefix => "__Host- prefix requires Secure, Path=/ and no Domain",
+            ErrorKind::SameSiteNoneInsecure => "SameSite=None requires the Secure attribute",
+            ErrorKind::PartitionedInsecure => "Partitioned requires the Secure attribute",
+        }
+    }
+}
+
+impl fmt::Debug for InvalidSetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidSetCookie").field(&self.kind).finish()
+    }
+}
+
+impl fmt::Display for InvalidSetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for InvalidSetCookie {}
+
+// ===== parsing helpers =====
+
+fn trim_str(s: &str) -> &str {
+    s.trim_matches(|c| c == ' ' || c == '\t')
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len() && s.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
+
+// cookie-value = *cookie-octet / ( DQUOTE *cookie-octet DQUOTE )
+fn is_cookie_value(s: &str) -> bool {
+    let b = s.as_bytes();
+    let b = if b.len() >= 2 && b[0] == b'"' && b[b.len() - 1] == b'"' {
+        &b[1..b.len() - 1]
+    } else {
+        b
+    };
+    b.iter().all(|&b| is_cookie_octet(b))
+}
+
+// av-octet = %x20-3A / %x3C-7E  ; any CHAR except CTLs or ";"
+fn is_attribute_octet(b: u8) -> bool {
+    b != b';' && (0x20..0x7f).contains(&b)
+}
+
+fn parse_max_age(s: &str) -> Option<Duration> {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    if s.starts_with('-') {
+        return Some(Duration::from_secs(0));
+    }
+
+    // Saturate absurdly large values instead of ignoring them.
+    Some(Duration::from_secs(digits.parse().unwrap_or(u64::MAX)))
+}
+
+// Parse a cookie date (RFC 6265bis, Section 5.1.1).
+//
+// This is deliberately much more lenient than `HttpDate`'s parser, matching
+// what user agents accept in the `Expires` attribute.
+fn parse_cookie_date(s: &str) -> Option<HttpDate> {
+    fn is_delimiter(b: u8) -> bool {
+        matches!(b, 0x09 | 0x20..=0x2F | 0x3B..=0x40 | 0x5B..=0x60 | 0x7B..=0x7E)
+    }
+
+    // Parses 1*max DIGIT followed by anything that isn't a digit.
+    fn leading_digits(token: &[u8], min: usize, max: usize) -> Option<(u32, &[u8])> {
+        let n = token.iter().take_while(|b| b.is_ascii_digit()).count();
+        if n < min || n > max {
+            return None;
+        }
+        let value = token[..n]
+            .iter()
+            .fold(0u32, |acc, &b| acc * 10 + (b - b'0') as u32);
+        Some((value, &token[n..]))
+    }
+
+    fn colon(token: &[u8]) -> Option<&[u8]> {
+        match token.split_first() {
+            Some((&b':', rest)) => Some(rest),
+            _ => None,
+        }
+    }
+
+    fn time(token: &[u8]) -> Option<(u32, u32, u32)> {
+        let (hour, rest) = leading_digits(token, 1, 2)?;
+        let (min, rest) = leading_digits(colon(rest)?, 1, 2)?;
+        let (sec, _) = leading_digits(colon(rest)?, 1, 2)?;
+        Some((hour, min, sec))
+    }
+
+    fn month(token: &[u8]) -> Option<u32> {
+        const MONTHS: [&[u8]; 12] = [
+            b"jan", b"feb", b"mar", b"apr", b"may", b"jun", b"jul", b"aug", b"sep", b"oct",
+            b"nov", b"dec",
+        ];
+        if token.len() < 3 {
+            return None;
+        }
+        MONTHS
+            .iter()
+            .position(|m| m.eq_ignore_ascii_case(&token[..3]))
+            .map(|i| i as u32 + 1)
+    }
+
+    let mut found_time = None;
+    let mut found_day = None;
+    let mut found_month = None;
+    let mut found_year = None;
+
+    for token in s.as_bytes().split(|&b| is_delimiter(b)) {
+        if token.is_empty() {
+            continue;
+        }
+
+        if found_time.is_none() {
+            if let Some(time) = time(token) {
+                found_time = Some(time);
+                continue;
+            }
+        }
+        if found_day.is_none() {
+            if let Some((day, _)) = leading_digits(token, 1, 2) {
+                found_day = Some(day);
+                continue;
+            }
+        }
+        if found_month.is_none() {
+            if let Some(month) = month(token) {
+                found_month = Some(month);
+                continue;
+            }
+        }
+        if found_year.is_none() {
+            if let Some((year, _)) = leading_digits(token, 2, 4) {
+                found_year = Some(year);
+                continue;
+            }
+        }
+    }
+
+    let (hour, min, sec) = found_time?;
+    let day = found_day?;
+    let mon = found_month?;
+    let mut year = found_year?;
+
+    if (70..=99).contains(&year) {
+        year += 1900;
+    } else if year <= 69 {
+        year += 2000;
+    }
+
+    // Years before 1601 are rejected by the algorithm; anything before the
+    // epoch can't be represented, so treat those as the epoch itself: they are
+    // in the past either way.
+    if year < 1601 {
+        return None;
+    }
+    if year < 1970 {
+        return Some(HttpDate::UNIX_EPOCH);
+    }
+    if year > 9999 {
+        return None;
+    }
+
+    HttpDate::from_fields(year as u16, mon as u8, day as u8, hour as u8, min as u8, sec as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> SetCookie {
+        s.parse().expect(&format!("parsing {}", s))
+    }
+
+    #[test]
+    fn parses_name_value_and_attributes() {
+        let cookie = parse(
+            "sid=31d4d96e407aad42; Path=/; Domain=.Example.COM; Secure; HttpOnly; \
+             SameSite=lax; Partitioned; Max-Age=60; Expires=Wed, 09 Jun 2021 10:18:14 GMT",
+        );
+
+        assert_eq!(cookie.name(), "sid");
+        assert_eq!(cookie.value(), "31d4d96e407aad42");
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.domain(), Some("example.com"));
+        assert!(cookie.secure());
+        assert!(cookie.http_only());
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert!(cookie.partitioned());
+        assert_eq!(cookie.max_age(), Some(Duration::from_secs(60)));
+        assert_eq!(
+            cookie.expires(),
+            Some("Wed, 09 Jun 2021 10:18:14 GMT".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn ignores_unknown_and_invalid_attributes() {
+        let cookie = parse("a=b; Foo=bar; Max-Age=abc; Path=relative; SameSite=Weird; Domain=");
+        assert_eq!(cookie, SetCookie::empty("a".into(), "b".into()));
+    }
+
+    #[test]
+    fn last_attribute_wins() {
+        let cookie = parse("a=b; Path=/one; Path=/two; Max-Age=1; Max-Age=-5");
+        assert_eq!(cookie.path(), Some("/two"));
+        assert_eq!(cookie.max_age(), Some(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn rejects_malformed_name_value_pairs() {
+        assert!("".parse::<SetCookie>().is_err());
+        assert!("novalue".parse::<SetCookie>().is_err());
+        assert!("=value".parse::<SetCookie>().is_err());
+        assert!("a=b\n".parse::<SetCookie>().is_err());
+        assert!(SetCookie::parse(&HeaderValue::from_bytes(b"a=\xff").unwrap()).is_err());
+    }
+
+    #[test]
+    fn parses_lenient_cookie_dates() {
+        let expected = "Sun, 06 Nov 1994 08:49:37 GMT".parse().ok();
+        assert_eq!(parse_cookie_date("Sun, 06 Nov 1994 08:49:37 GMT"), expected);
+        assert_eq!(parse_cookie_date("Sunday, 06-Nov-94 08:49:37 GMT"), expected);
+        assert_eq!(parse_cookie_date("Sun Nov  6 08:49:37 1994"), expected);
+        assert_eq!(parse_cookie_date("6 november 94 8:49:37"), expected);
+        assert_eq!(
+            parse_cookie_date("Thu, 01-Jan-1900 00:00:00 GMT"),
+            Some(HttpDate::UNIX_EPOCH)
+        );
+        assert_eq!(parse_cookie_date("Sun, 31 Nov 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_cookie_date("tomorrow"), None);
+    }
+
+    #[test]
+    fn builds_and_formats() {
+        let cookie = SetCookie::builder("id", "a3fWa")
+            .expires("Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap())
+            .max_age(Duration::from_millis(2500))
+            .domain(".Example.com")
+            .path("/docs")
+            .secure(true)
+            .http_only(true)
+            .same_site(SameSite::None)
+            .partitioned(true)
+            .build()
+            .unwrap();
+
+        let expected = "id=a3fWa; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=2; \
+                        Domain=example.com; Path=/docs; Secure; HttpOnly; SameSite=None; \
+                        Partitioned";
+        assert_eq!(cookie.to_string(), expected);
+        assert_eq!(HeaderValue::from(cookie.clone()), expected);
+        assert_eq!(parse(expected), cookie);
+    }
+
+    #[test]
+    fn builder_validates_name_value_and_attributes() {
+        assert!(SetCookie::builder("", "v").build().is_err());
+        assert!(SetCookie::builder("a b", "v").build().is_err());
+        assert!(SetCookie::builder("a", "v;w").build().is_err());
+        assert!(SetCookie::builder("a", "v w").build().is_err());
+        assert!(SetCookie::builder("a", "\"quoted\"").build().is_ok());
+        assert!(SetCookie::builder("a", "v").path("docs").build().is_err());
+        assert!(SetCookie::builder("a", "v").path("/a;b").build().is_err());
+        assert!(SetCookie::builder("a", "v").domain("").build().is_err());
+        assert!(SetCookie::builder("a", "v").domain("a\nb").build().is_err());
+    }
+
+    #[test]
+    fn builder_enforces_prefixes_and_secure_requirements() {
+        let err = |b: Builder| b.build().unwrap_err().kind;
+
+        assert_eq!(err(SetCookie::builder("__Secure-a", "v")), ErrorKind::SecurePrefix);
+        assert_eq!(err(SetCookie::builder("__secure-a", "v")), ErrorKind::SecurePrefix);
+        assert!(SetCookie::builder("__Secure-a", "v").secure(true).build().is_ok());
+
+        let host = || SetCookie::builder("__Host-a", "v").secure(true);
+        assert_eq!(err(host()), ErrorKind::HostPrefix);
+        assert_eq!(err(host().path("/docs")), ErrorKind::HostPrefix);
+        assert_eq!(err(host().path("/").domain("example.com")), ErrorKind::HostPrefix);
+        assert!(host().path("/").build().is_ok());
+
+        assert_eq!(
+            err(SetCookie::builder("a", "v").same_site(SameSite::None)),
+            ErrorKind::SameSiteNoneInsecure
+        );
+        assert_eq!(
+            err(SetCookie::builder("a", "v").partitioned(true)),
+            ErrorKind::PartitionedInsecure
+        );
+    }
+}
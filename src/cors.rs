@@ -0,0 +1,1029 @@
+//! Cross-Origin Resource Sharing (CORS).
+//!
+//! This module contains a [`Policy`] describing which cross-origin requests a
+//! server accepts, and the logic to apply it to a `Request` following the
+//! [CORS protocol] of the Fetch standard: classifying requests as preflight,
+//! actual CORS or non-CORS requests, and computing the
+//! `Access-Control-Allow-*` response headers.
+//!
+//! # Examples
+//!
+//! ```
+//! use http::cors::{AllowOrigin, Outcome, Policy};
+//! use http::header::{self, HeaderValue};
+//! use http::{Method, Request, Response, StatusCode};
+//! use std::time::Duration;
+//!
+//! let policy = Policy::builder()
+//!     .allow_origin(AllowOrigin::exact(HeaderValue::from_static("https://app.example")))
+//!     .allow_methods(vec![Method::GET, Method::PUT])
+//!     .allow_headers(vec![header::AUTHORIZATION, header::CONTENT_TYPE])
+//!     .allow_credentials(true)
+//!     .max_age(Duration::from_secs(600))
+//!     .build()
+//!     .unwrap();
+//!
+//! let preflight = Request::options("/api/items/1")
+//!     .header(header::ORIGIN, "https://app.example")
+//!     .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+//!     .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+//!     .body(())
+//!     .unwrap();
+//!
+//! match policy.check(&preflight) {
+//!     Ok(Outcome::Preflight(headers)) => {
+//!         let mut response = Response::new(());
+//!         *response.status_mut() = StatusCode::NO_CONTENT;
+//!         response.headers_mut().extend(headers);
+//!
+//!         assert_eq!(
+//!             response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+//!             "https://app.example"
+//!         );
+//!         assert_eq!(
+//!             response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS],
+//!             "GET, PUT"
+//!         );
+//!     }
+//!     other => panic!("unexpected outcome: {:?}", other),
+//! }
+//! ```
+//!
+//! [`Policy`]: struct.Policy.html
+//! [CORS protocol]: https://fetch.spec.whatwg.org/#http-cors-protocol
+
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::{Method, Request};
+
+/// A CORS policy.
+///
+/// A `Policy` is created with a [`Builder`] and is cheap to clone.
+///
+/// [`Builder`]: struct.Builder.html
+#[derive(Clone, Debug)]
+pub struct Policy {
+    origin: AllowOrigin,
+    methods: Any<Vec<Method>>,
+    headers: Any<Vec<HeaderName>>,
+    expose_headers: Any<Vec<HeaderName>>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+/// A builder for a CORS `Policy`.
+///
+/// By default no origins, methods or headers are allowed, credentials are not
+/// allowed, no headers are exposed and no `Access-Control-Max-Age` is sent.
+#[derive(Debug)]
+pub struct Builder {
+    inner: Result<Policy, InvalidPolicy>,
+}
+
+/// The origins allowed by a CORS `Policy`.
+#[derive(Clone)]
+pub struct AllowOrigin {
+    inner: OriginInner,
+}
+
+#[derive(Clone)]
+enum OriginInner {
+    Any,
+    List(Vec<HeaderValue>),
+    Predicate(Arc<dyn Fn(&HeaderValue) -> bool + Send + Sync>),
+}
+
+#[derive(Clone, Debug)]
+enum Any<T> {
+    Any,
+    List(T),
+}
+
+/// The kind of a request, as far as CORS is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// An `OPTIONS` request with `Origin` and `Access-Control-Request-Method`
+    /// headers, sent by a user agent before a non-simple cross-origin request.
+    Preflight,
+    /// A request with an `Origin` header.
+    Cors,
+    /// A request without an `Origin` header.
+    NotCors,
+}
+
+/// The result of successfully checking a request against a `Policy`.
+///
+/// Each variant contains the headers to add to the response.
+#[derive(Clone, Debug)]
+pub enum Outcome {
+    /// The request is an allowed preflight request.
+    ///
+    /// The server should respond immediately, typically with `204 No
+    /// Content`, and these headers, without passing the request on.
+    Preflight(HeaderMap),
+    /// The request is an allowed CORS request.
+    ///
+    /// The request should be handled as usual, adding these headers to the
+    /// response.
+    Cors(HeaderMap),
+    /// The request is not a CORS request.
+    ///
+    /// The request should be handled as usual. The headers may contain a
+    /// `Vary` header so caches don't serve this response to CORS requests.
+    NotCors(HeaderMap),
+}
+
+/// The reason a request was rejected by a `Policy`.
+///
+/// A rejected preflight request should be answered without any
+/// `Access-Control-Allow-*` headers (for example with `403 Forbidden`), which
+/// makes the user agent fail the cross-origin request. A rejected actual
+/// request may be processed as usual, but its response must not receive any
+/// `Access-Control-*` headers either.
+///
+/// Both responses must still have the headers returned by
+/// [`Rejection::headers`]: a `Vary` header, so that a shared cache doesn't
+/// serve the rejection to an allowed origin, or the other way around.
+///
+/// [`Rejection::headers`]: struct.Rejection.html#method.headers
+pub struct Rejection {
+    kind: RejectionKind,
+    headers: HeaderMap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RejectionKind {
+    OriginNotAllowed,
+    MethodNotAllowed,
+    HeadersNotAllowed,
+    InvalidPreflight,
+}
+
+/// An error resulting from an invalid CORS policy configuration.
+pub struct InvalidPolicy {
+    _priv: (),
+}
+
+impl Policy {
+    /// Creates a new `Builder` for a `Policy`.
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+
+    /// Classifies a request as preflight, CORS or non-CORS.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cors::{Kind, Policy};
+    /// # use http::Request;
+    /// let policy = Policy::builder().build().unwrap();
+    ///
+    /// let request = Request::get("/").body(()).unwrap();
+    /// assert_eq!(policy.classify(&request), Kind::NotCors);
+    ///
+    /// let request = Request::get("/").header("Origin", "https://a.example").body(()).unwrap();
+    /// assert_eq!(policy.classify(&request), Kind::Cors);
+    /// ```
+    pub fn classify<T>(&self, request: &Request<T>) -> Kind {
+        classify(request)
+    }
+
+    /// Checks a request against this policy.
+    ///
+    /// On success, the returned `Outcome` tells whether the request was a
+    /// preflight request and contains the headers to add to the response.
+    pub fn check<T>(&self, request: &Request<T>) -> Result<Outcome, Rejection> {
+        match classify(request) {
+            Kind::Preflight => self
+                .preflight(request)
+                .map(Outcome::Preflight)
+                .map_err(|kind| Rejection::new(kind, self.vary(Kind::Preflight))),
+            Kind::Cors => self
+                .cors(request)
+                .map(Outcome::Cors)
+                .map_err(|kind| Rejection::new(kind, self.vary(Kind::Cors))),
+            Kind::NotCors => Ok(Outcome::NotCors(self.vary(Kind::NotCors))),
+        }
+    }
+
+    fn preflight<T>(&self, request: &Request<T>) -> Result<HeaderMap, RejectionKind> {
+        let origin = request
+            .headers()
+            .get(header::ORIGIN)
+            .expect("classified as preflight");
+        if !self.origin.allows(origin) {
+            return Err(RejectionKind::OriginNotAllowed);
+        }
+
+        let method = request
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|v| Method::from_bytes(v.as_bytes()).ok())
+            .ok_or_else(|| RejectionKind::InvalidPreflight)?;
+        if !self.allows_method(&method) {
+            return Err(RejectionKind::MethodNotAllowed);
+        }
+
+        for value in request
+            .headers()
+            .get_all(header::ACCESS_CONTROL_REQUEST_HEADERS)
+        {
+            let value = value
+                .to_str()
+                .map_err(|_| RejectionKind::InvalidPreflight)?;
+            for name in value.split(',') {
+                let name = name.trim();
+                if name.is_empty() {
+                    continue;
+                }
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| RejectionKind::InvalidPreflight)?;
+                if !self.allows_header(&name) {
+                    return Err(RejectionKind::HeadersNotAllowed);
+                }
+            }
+        }
+
+        let mut headers = HeaderMap::new();
+        self.append_origin(origin, &mut headers);
+
+        match self.methods {
+            Any::Any => {
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_METHODS,
+                    HeaderValue::from_static("*"),
+                );
+            }
+            Any::List(ref methods) if !methods.is_empty() => {
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_METHODS,
+                    join(methods.iter().map(Method::as_str)),
+                );
+            }
+            Any::List(_) => {}
+        }
+
+        match self.headers {
+            Any::Any => {
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_HEADERS,
+                    HeaderValue::from_static("*"),
+                );
+            }
+            Any::List(ref names) if !names.is_empty() => {
+                headers.insert(
+                    header::ACCESS_CONTROL_ALLOW_HEADERS,
+                    join(names.iter().map(HeaderName::as_str)),
+                );
+            }
+            Any::List(_) => {}
+        }
+
+        if let Some(max_age) = self.max_age {
+            headers.insert(header::ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+        }
+
+        headers.extend(self.vary(Kind::Preflight));
+        Ok(headers)
+    }
+
+    fn cors<T>(&self, request: &Request<T>) -> Result<HeaderMap, RejectionKind> {
+        let origin = request
+            .headers()
+            .get(header::ORIGIN)
+            .expect("classified as cors");
+        if !self.origin.allows(origin) {
+            return Err(RejectionKind::OriginNotAllowed);
+        }
+
+        let mut headers = HeaderMap::new();
+        self.append_origin(origin, &mut headers);
+
+        match self.expose_headers {
+            Any::Any => {
+                headers.insert(
+                    header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                    HeaderValue::from_static("*"),
+                );
+            }
+            Any::List(ref names) if !names.is_empty() => {
+                headers.insert(
+                    header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                    join(names.iter().map(HeaderName::as_str)),
+                );
+            }
+            Any::List(_) => {}
+        }
+
+        headers.extend(self.vary(Kind::Cors));
+        Ok(headers)
+    }
+
+    fn append_origin(&self, origin: &HeaderValue, headers: &mut HeaderMap) {
+        let allow_origin = match self.origin.inner {
+            OriginInner::Any => HeaderValue::from_static("*"),
+            _ => origin.clone(),
+        };
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+    }
+
+    // Returns the `Vary` header of a response to a request of this kind,
+    // whether the request was allowed or rejected: caches must not serve the
+    // response to a request with other CORS headers.
+    fn vary(&self, kind: Kind) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if kind == Kind::Preflight {
+            // Preflight responses depend on all of the CORS request headers.
+            headers.insert(
+                header::VARY,
+                HeaderValue::from_static(
+                    "origin, access-control-request-method, access-control-request-headers",
+                ),
+            );
+        } else if !matches!(self.origin.inner, OriginInner::Any) {
+            // Other responses depend on the `Origin` header unless every
+            // origin gets the same `*` answer.
+            headers.insert(header::VARY, HeaderValue::from_static("origin"));
+        }
+        headers
+    }
+
+    fn allows_method(&self, method: &Method) -> bool {
+        // CORS-safelisted methods never need to be listed.
+        if *method == Method::GET || *method == Method::HEAD || *method == Method::POST {
+            return true;
+        }
+
+        match self.methods {
+            Any::Any => true,
+            Any::List(ref methods) => methods.contains(method),
+        }
+    }
+
+    fn allows_header(&self, name: &HeaderName) -> bool {
+        match self.headers {
+            Any::Any => true,
+            Any::List(ref names) => names.contains(name),
+        }
+    }
+}
+
+fn classify<T>(request: &Request<T>) -> Kind {
+    let headers = request.headers();
+
+    if !headers.contains_key(header::ORIGIN) {
+        return Kind::NotCors;
+    }
+
+    if request.method() == Method::OPTIONS
+        && headers.contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+    {
+        return Kind::Preflight;
+    }
+
+    Kind::Cors
+}
+
+fn join<'a, I: Iterator<Item = &'a str>>(iter: I) -> HeaderValue {
+    let mut buf = String::new();
+    for s in iter {
+        if !buf.is_empty() {
+            buf.push_str(", ");
+        }
+        buf.push_str(s);
+    }
+    HeaderValue::from_str(&buf).expect("methods and header names are valid header values")
+}
+
+// ===== impl Builder =====
+
+impl Builder {
+    /// Creates a new `Builder` for a policy that allows nothing.
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Sets the allowed origins.
+    pub fn allow_origin(self, origin: AllowOrigin) -> Builder {
+        self.map(|mut policy| {
+            policy.origin = origin;
+            policy
+        })
+    }
+
+    /// Sets the methods allowed in preflight requests.
+    ///
+    /// The CORS-safelisted methods `GET`, `HEAD` and `POST` are always
+    /// allowed.
+    pub fn allow_methods<I>(self, methods: I) -> Builder
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        self.map(|mut policy| {
+            policy.methods = Any::List(methods.into_iter().collect());
+            policy
+        })
+    }
+
+    /// Allows any method, answering preflight requests with
+    /// `Access-Control-Allow-Methods: *`.
+    pub fn allow_any_method(self) -> Builder {
+        self.map(|mut policy| {
+            policy.methods = Any::Any;
+            policy
+        })
+    }
+
+    /// Sets the request headers allowed in preflight requests.
+    ///
+    /// The CORS-safelisted request headers `Accept`, `Accept-Language`,
+    /// `Content-Language` and `Content-Type` must be listed too: a user agent
+    /// only asks for them in a preflight request when their value isn't
+    /// safelisted, such as `Content-Type: application/json`, and then
+    /// requires them in `Access-Control-Allow-Headers`.
+    pub fn allow_headers<I>(self, headers: I) -> Builder
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        self.map(|mut policy| {
+            policy.headers = Any::List(headers.into_iter().collect());
+            policy
+        })
+    }
+
+    /// Allows any request header, answering preflight requests with
+    /// `Access-Control-Allow-Headers: *`.
+    pub fn allow_any_header(self) -> Builder {
+        self.map(|mut policy| {
+            policy.headers = Any::Any;
+            policy
+        })
+    }
+
+    /// Sets the response headers exposed to scripts with
+    /// `Access-Control-Expose-Headers`.
+    pub fn expose_headers<I>(self, headers: I) -> Builder
+    where
+        I: IntoIterator<Item = HeaderName>,
+    {
+        self.map(|mut policy| {
+            policy.expose_headers = Any::List(headers.into_iter().collect());
+            policy
+        })
+    }
+
+    /// Exposes all response headers with `Access-Control-Expose-Headers: *`.
+    pub fn expose_any_header(self) -> Builder {
+        self.map(|mut policy| {
+            policy.expose_headers = Any::Any;
+            policy
+        })
+    }
+
+    /// Sets whether credentials (cookies, authorization headers or TLS client
+    /// certificates) are allowed, with `Access-Control-Allow-Credentials`.
+    pub fn allow_credentials(self, allow: bool) -> Builder {
+        self.map(|mut policy| {
+            policy.credentials = allow;
+            policy
+        })
+    }
+
+    /// Sets how long the results of a preflight request can be cached, with
+    /// `Access-Control-Max-Age`.
+    pub fn max_age(self, max_age: Duration) -> Builder {
+        self.map(|mut policy| {
+            policy.max_age = Some(max_age);
+            policy
+        })
+    }
+
+    /// Consumes this builder, returning the `Policy`.
+    ///
+    /// # Errors
+    ///
+    /// User agents ignore the `*` wildcard in CORS response headers for
+    /// requests with credentials. Allowing credentials together with any
+    /// origin, any method, any header or exposing any header is therefore an
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cors::{AllowOrigin, Policy};
+    /// let result = Policy::builder()
+    ///     .allow_origin(AllowOrigin::any())
+    ///     .allow_credentials(true)
+    ///     .build();
+    ///
+    /// assert!(result.is_err());
+    /// ```
+    pub fn build(self) -> Result<Policy, InvalidPolicy> {
+        let policy = self.inner?;
+
+        if policy.credentials {
+            let wildcard = matches!(policy.origin.inner, OriginInner::Any)
+                || policy.methods.is_any()
+                || policy.headers.is_any()
+                || policy.expose_headers.is_any();
+
+            if wildcard {
+                return Err(InvalidPolicy { _priv: () });
+            }
+        }
+
+        Ok(policy)
+    }
+
+    // private
+
+    fn map<F>(self, func: F) -> Builder
+    where
+        F: FnOnce(Policy) -> Policy,
+    {
+        Builder {
+            inner: self.inner.map(func),
+        }
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder {
+            inner: Ok(Policy {
+                origin: AllowOrigin::list(Vec::new()),
+                methods: Any::List(Vec::new()),
+                headers: Any::List(Vec::new()),
+                expose_headers: Any::List(Vec::new()),
+                credentials: false,
+                max_age: None,
+            }),
+        }
+    }
+}
+
+impl<T> Any<T> {
+    fn is_any(&self) -> bool {
+        matches!(*self, Any::Any)
+    }
+}
+
+// ===== impl AllowOrigin =====
+
+impl AllowOrigin {
+    /// Allows any origin, answering with `Access-Control-Allow-Origin: *`.
+    pub fn any() -> AllowOrigin {
+        AllowOrigin {
+            inner: OriginInner::Any,
+        }
+    }
+
+    /// Allows a single origin.
+    ///
+    /// Origins are compared byte for byte with the `Origin` request header,
+    /// which user agents serialize as `scheme://host[:port]` in lower case.
+    pub fn exact(origin: HeaderValue) -> AllowOrigin {
+        AllowOrigin::list(vec![origin])
+    }
+
+    /// Allows each of a list of origins.
+    pub fn list<I>(origins: I) -> AllowOrigin
+    where
+        I: IntoIterator<Item = HeaderValue>,
+    {
+        AllowOrigin {
+            inner: OriginInner::List(origins.into_iter().collect()),
+        }
+    }
+
+    /// Allows the origins for which a predicate returns `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cors::AllowOrigin;
+    /// let origin = AllowOrigin::predicate(|origin| {
+    ///     origin.as_bytes().ends_with(b".example.com")
+    /// });
+    /// ```
+    pub fn predicate<F>(f: F) -> AllowOrigin
+    where
+        F: Fn(&HeaderValue) -> bool + Send + Sync + 'static,
+    {
+        AllowOrigin {
+            inner: OriginInner::Predicate(Arc::new(f)),
+        }
+    }
+
+    fn allows(&self, origin: &HeaderValue) -> bool {
+        match self.inner {
+            OriginInner::Any => true,
+            OriginInner::List(ref list) => list.contains(origin),
+            OriginInner::Predicate(ref f) => f(origin),
+        }
+    }
+}
+
+impl fmt::Debug for AllowOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            OriginInner::Any => f.write_str("Any"),
+            OriginInner::List(ref list) => f.debug_tuple("List").field(list).finish(),
+            OriginInner::Predicate(_) => f.write_str("Predicate"),
+        }
+    }
+}
+
+// ===== impl Outcome =====
+
+impl Outcome {
+    /// Returns the headers to add to the response.
+    pub fn headers(&self) -> &HeaderMap {
+        match *self {
+            Outcome::Preflight(ref headers)
+            | Outcome::Cors(ref headers)
+            | Outcome::NotCors(ref headers) => headers,
+        }
+    }
+
+    /// Consumes the outcome, returning the headers to add to the response.
+    pub fn into_headers(self) -> HeaderMap {
+        match self {
+            Outcome::Preflight(headers) | Outcome::Cors(headers) | Outcome::NotCors(headers) => {
+                headers
+            }
+        }
+    }
+}
+
+// ===== impl Rejection =====
+
+impl Rejection {
+    fn new(kind: RejectionKind, headers: HeaderMap) -> Rejection {
+        Rejection { kind, headers }
+    }
+
+    /// Returns the headers to add to the response.
+    ///
+    /// They never contain CORS headers, but may contain a `Vary` header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::cors::{AllowOrigin, Policy};
+    /// # use http::header::{self, HeaderValue};
+    /// # use http::Request;
+    /// let policy = Policy::builder()
+    ///     .allow_origin(AllowOrigin::exact(HeaderValue::from_static("https://app.example")))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let request = Request::get("/")
+    ///     .header(header::ORIGIN, "https://evil.example")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let rejection = policy.check(&request).unwrap_err();
+    /// assert_eq!(rejection.headers()[header::VARY], "origin");
+    /// ```
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Consumes the rejection, returning the headers to add to the response.
+    pub fn into_headers(self) -> HeaderMap {
+        self.headers
+    }
+
+    /// Returns `true` if the `Origin` of the request is not allowed.
+    pub fn is_origin_not_allowed(&self) -> bool {
+        self.kind == RejectionKind::OriginNotAllowed
+    }
+
+    /// Returns `true` if the method requested by a preflight request is not
+    /// allowed.
+    pub fn is_method_not_allowed(&self) -> bool {
+        self.kind == RejectionKind::MethodNotAllowed
+    }
+
+    /// Returns `true` if one of the headers requested by a preflight request
+    /// is not allowed.
+    pub fn is_headers_not_allowed(&self) -> bool {
+        self.kind == RejectionKind::HeadersNotAllowed
+    }
+
+    /// Returns `true` if a preflight request was malformed.
+    pub fn is_invalid_preflight(&self) -> bool {
+        self.kind == RejectionKind::InvalidPreflight
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            RejectionKind::OriginNotAllowed => "origin not allowed",
+            RejectionKind::MethodNotAllowed => "method not allowed",
+            RejectionKind::HeadersNotAllowed => "headers not allowed",
+            RejectionKind::InvalidPreflight => "invalid preflight request",
+        }
+    }
+}
+
+impl fmt::Debug for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Rejection").field(&self.kind).finish()
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for Rejection {}
+
+impl fmt::Debug for InvalidPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InvalidPolicy")
+            // skip _priv noise
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("credentials can't be allowed together with a `*` wildcard")
+    }
+}
+
+impl Error for InvalidPolicy {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: &str = "https://app.example";
+
+    fn policy() -> Builder {
+        Policy::builder().allow_origin(AllowOrigin::exact(HeaderValue::from_static(ORIGIN)))
+    }
+
+    fn preflight(method: &str, headers: Option<&str>) -> Request<()> {
+        let mut builder = Request::options("/")
+            .header(header::ORIGIN, ORIGIN)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, method);
+        if let Some(headers) = headers {
+            builder = builder.header(header::ACCESS_CONTROL_REQUEST_HEADERS, headers);
+        }
+        builder.body(()).unwrap()
+    }
+
+    fn cors(origin: &str) -> Request<()> {
+        Request::get("/")
+            .header(header::ORIGIN, origin)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn classifies_requests() {
+        let policy = policy().build().unwrap();
+
+        assert_eq!(
+            policy.classify(&Request::get("/").body(()).unwrap()),
+            Kind::NotCors
+        );
+        assert_eq!(policy.classify(&cors(ORIGIN)), Kind::Cors);
+        assert_eq!(policy.classify(&preflight("PUT", None)), Kind::Preflight);
+
+        // OPTIONS without Access-Control-Request-Method is a regular request
+        let request = Request::options("/")
+            .header(header::ORIGIN, ORIGIN)
+            .body(())
+            .unwrap();
+        assert_eq!(policy.classify(&request), Kind::Cors);
+    }
+
+    #[test]
+    fn preflight_headers() {
+        let policy = policy()
+            .allow_methods(vec![Method::PUT, Method::DELETE])
+            .allow_headers(vec![
+                header::AUTHORIZATION,
+                HeaderName::from_static("x-custom"),
+            ])
+            .allow_credentials(true)
+            .max_age(Duration::from_secs(600))
+            .build()
+            .unwrap();
+
+        let headers = match policy.check(&preflight("DELETE", Some("Authorization, x-custom"))) {
+            Ok(Outcome::Preflight(headers)) => headers,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], ORIGIN);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "PUT, DELETE");
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            "authorization, x-custom"
+        );
+        assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+        assert!(headers[header::VARY]
+            .to_str()
+            .unwrap()
+            .starts_with("origin"));
+    }
+
+    #[test]
+    fn safelisted_headers_are_echoed() {
+        let policy = policy()
+            .allow_headers(vec![header::CONTENT_TYPE])
+            .build()
+            .unwrap();
+
+        // Sent by a browser for `Content-Type: application/json`.
+        let headers = match policy.check(&preflight("POST", Some("content-type"))) {
+            Ok(Outcome::Preflight(headers)) => headers,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_HEADERS],
+            "content-type"
+        );
+    }
+
+    #[test]
+    fn preflight_rejections() {
+        let policy = policy()
+            .allow_methods(vec![Method::PUT])
+            .allow_headers(vec![header::AUTHORIZATION])
+            .build()
+            .unwrap();
+
+        assert!(policy.check(&preflight("PUT", None)).is_ok());
+        assert!(policy
+            .check(&preflight("POST", Some("content-type")))
+            .unwrap_err()
+            .is_headers_not_allowed());
+        assert!(policy
+            .check(&preflight("PATCH", None))
+            .unwrap_err()
+            .is_method_not_allowed());
+        assert!(policy
+            .check(&preflight("PUT", Some("authorization, x-other")))
+            .unwrap_err()
+            .is_headers_not_allowed());
+        assert!(policy
+            .check(&preflight("", None))
+            .unwrap_err()
+            .is_invalid_preflight());
+        assert!(policy
+            .check(&preflight("PUT", Some("bad header")))
+            .unwrap_err()
+            .is_invalid_preflight());
+
+        let request = Request::options("/")
+            .header(header::ORIGIN, "https://evil.example")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .body(())
+            .unwrap();
+        assert!(policy.check(&request).unwrap_err().is_origin_not_allowed());
+    }
+
+    #[test]
+    fn wildcards() {
+        let policy = Policy::builder()
+            .allow_origin(AllowOrigin::any())
+            .allow_any_method()
+            .allow_any_header()
+            .expose_any_header()
+            .build()
+            .unwrap();
+
+        let headers = policy
+            .check(&preflight("PATCH", Some("x-anything")))
+            .unwrap()
+            .into_headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_METHODS], "*");
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS], "*");
+
+        let headers = policy
+            .check(&cors("https://any.example"))
+            .unwrap()
+            .into_headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(headers[header::ACCESS_CONTROL_EXPOSE_HEADERS], "*");
+        assert!(headers
+            .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+            .is_none());
+        assert!(headers.get(header::VARY).is_none());
+
+        let headers = policy
+            .check(&Request::get("/").body(()).unwrap())
+            .unwrap()
+            .into_headers();
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn cors_headers_vary_on_origin() {
+        let policy = policy()
+            .expose_headers(vec![header::ETAG, header::LINK])
+            .build()
+            .unwrap();
+
+        let headers = policy.check(&cors(ORIGIN)).unwrap().into_headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], ORIGIN);
+        assert_eq!(headers[header::ACCESS_CONTROL_EXPOSE_HEADERS], "etag, link");
+        assert_eq!(headers[header::VARY], "origin");
+
+        assert!(policy
+            .check(&cors("null"))
+            .unwrap_err()
+            .is_origin_not_allowed());
+
+        match policy.check(&Request::get("/").body(()).unwrap()) {
+            Ok(Outcome::NotCors(headers)) => assert_eq!(headers[header::VARY], "origin"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejections_vary() {
+        let policy = Policy::builder()
+            .allow_origin(AllowOrigin::list(vec![
+                HeaderValue::from_static(ORIGIN),
+                HeaderValue::from_static("https://other.example"),
+            ]))
+            .build()
+            .unwrap();
+
+        let rejection = policy.check(&cors("https://evil.example")).unwrap_err();
+        assert!(rejection.is_origin_not_allowed());
+        let headers = rejection.into_headers();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[header::VARY], "origin");
+
+        let rejection = policy.check(&preflight("PATCH", None)).unwrap_err();
+        assert!(rejection.is_method_not_allowed());
+        let headers = rejection.headers();
+        assert_eq!(headers.len(), 1);
+        assert!(headers[header::VARY]
+            .to_str()
+            .unwrap()
+            .starts_with("origin"));
+
+        // A rejection by a predicate depends on the origin too.
+        let policy = Policy::builder()
+            .allow_origin(AllowOrigin::predicate(|_| false))
+            .build()
+            .unwrap();
+        let rejection = policy.check(&cors(ORIGIN)).unwrap_err();
+        assert_eq!(rejection.headers()[header::VARY], "origin");
+    }
+
+    #[test]
+    fn predicate_origins() {
+        let policy = Policy::builder()
+            .allow_origin(AllowOrigin::predicate(|origin| {
+                origin.as_bytes().ends_with(b".example.com")
+            }))
+            .build()
+            .unwrap();
+
+        let headers = policy
+            .check(&cors("https://a.example.com"))
+            .unwrap()
+            .into_headers();
+        assert_eq!(
+            headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://a.example.com"
+        );
+        assert!(policy.check(&cors("https://example.org")).is_err());
+    }
+
+    #[test]
+    fn credentials_cannot_be_combined_with_wildcards() {
+        let creds = || policy().allow_credentials(true);
+
+        assert!(creds().build().is_ok());
+        assert!(creds().allow_origin(AllowOrigin::any()).build().is_err());
+        assert!(creds().allow_any_method().build().is_err());
+        assert!(creds().allow_any_header().build().is_err());
+        assert!(creds().expose_any_header().build().is_err());
+    }
+}
@@ -0,0 +1,1299 @@
+//! Forwarding headers added by proxies.
+//!
+//! This module parses and builds the headers a proxy adds to a request it
+//! forwards:
+//!
+//! - [`Forwarded`] ([RFC 7239]), a list of elements describing each hop,
+//!   with the `for`, `by`, `proto` and `host` parameters,
+//! - the de facto `X-Forwarded-For`, `X-Forwarded-Proto` and
+//!   `X-Forwarded-Host` headers, parsed together by [`XForwarded`],
+//! - [`Via`] ([RFC 9110, Section 7.6.3]), which records the protocol and
+//!   the name of each intermediary.
+//!
+//! Forwarding headers can be sent by anyone, so a server must only believe
+//! those added by proxies it trusts. [`TrustedProxies::client`] does so, and
+//! determines the address of the client and the scheme and host of its
+//! original request.
+//!
+//! # Examples
+//!
+//! A reverse proxy describing the request it received:
+//!
+//! ```
+//! use http::forwarded::Forwarded;
+//! use http::header::FORWARDED;
+//! use http::uri::Scheme;
+//! use http::Request;
+//!
+//! let mut element = Forwarded::new();
+//! element.set_for("2001:db8::1".parse::<std::net::IpAddr>().unwrap().into());
+//! element.set_proto(Scheme::HTTPS);
+//! element.set_host("example.com".parse().unwrap());
+//!
+//! let request = Request::get("/")
+//!     .header(FORWARDED, element)
+//!     .body(())
+//!     .unwrap();
+//! assert_eq!(
+//!     request.headers()[FORWARDED],
+//!     "for=\"[2001:db8::1]\";host=example.com;proto=https"
+//! );
+//! ```
+//!
+//! An application behind that proxy:
+//!
+//! ```
+//! use http::forwarded::TrustedProxies;
+//! use http::header::FORWARDED;
+//! use http::uri::Scheme;
+//! use http::Request;
+//! use std::net::{IpAddr, SocketAddr};
+//!
+//! let proxies = TrustedProxies::new().add_network("10.0.0.0".parse().unwrap(), 8);
+//!
+//! let request = Request::get("/")
+//!     .header(FORWARDED, "for=192.0.2.60;proto=https;host=example.com")
+//!     .body(())
+//!     .unwrap();
+//! let peer: SocketAddr = "10.1.2.3:51234".parse().unwrap();
+//!
+//! let client = proxies.client(&request, peer).unwrap();
+//! assert_eq!(client.addr(), Some("192.0.2.60".parse::<IpAddr>().unwrap()));
+//! assert_eq!(client.scheme(), Some(&Scheme::HTTPS));
+//! assert_eq!(client.host().unwrap(), "example.com");
+//! ```
+//!
+//! [`Forwarded`]: struct.Forwarded.html
+//! [`XForwarded`]: struct.XForwarded.html
+//! [`Via`]: struct.Via.html
+//! [`TrustedProxies::client`]: struct.TrustedProxies.html#method.client
+//! [RFC 7239]: https://www.rfc-editor.org/rfc/rfc7239
+//! [RFC 9110, Section 7.6.3]: https://www.rfc-editor.org/rfc/rfc9110#section-7.6.3
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::header::{self, HeaderMap, HeaderValue};
+use crate::token::{is_tchar, is_token, Parser};
+use crate::uri::{Authority, Scheme};
+use crate::{Request, Version};
+
+/// An element of a `Forwarded` header, describing one hop.
+///
+/// All parameters are optional. Parameters other than `for`, `by`, `proto`
+/// and `host` are ignored when parsing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Forwarded {
+    by: Option<Node>,
+    for_: Option<Node>,
+    host: Option<Authority>,
+    proto: Option<Scheme>,
+}
+
+/// A node in a `Forwarded` or `X-Forwarded-For` header: a client or a
+/// proxy, with an optional port.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Node {
+    name: NodeName,
+    port: Option<NodePort>,
+}
+
+/// The name of a `Node`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NodeName {
+    /// An IP address.
+    Ip(IpAddr),
+    /// `unknown`: the proxy doesn't know, or won't tell, the address.
+    Unknown,
+    /// An obfuscated identifier, such as `_hidden`, that starts with an
+    /// underscore.
+    Obfuscated(String),
+}
+
+/// The port of a `Node`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NodePort {
+    /// A port number.
+    Port(u16),
+    /// An obfuscated identifier, such as `_p1`, that starts with an
+    /// underscore.
+    Obfuscated(String),
+}
+
+/// The `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host`
+/// headers of a request.
+///
+/// Each header is a comma-separated list, to which every proxy may append a
+/// value, so the rightmost values were added by the nearest proxy.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct XForwarded {
+    for_: Vec<Node>,
+    proto: Vec<Scheme>,
+    host: Vec<Authority>,
+}
+
+/// An entry of a `Via` header, added by one intermediary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Via {
+    protocol_name: Option<String>,
+    protocol_version: String,
+    received_by: String,
+    comment: Option<String>,
+}
+
+/// The proxies whose forwarding headers a server trusts.
+///
+/// # Examples
+///
+/// ```
+/// # use http::forwarded::TrustedProxies;
+/// let proxies = TrustedProxies::new()
+///     .add_ip("192.0.2.1".parse().unwrap())
+///     .add_network("fd00::".parse().unwrap(), 8);
+///
+/// assert!(proxies.contains("192.0.2.1".parse().unwrap()));
+/// assert!(proxies.contains("fd12:3456::1".parse().unwrap()));
+/// assert!(!proxies.contains("192.0.2.2".parse().unwrap()));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    networks: Vec<(IpAddr, u8)>,
+}
+
+/// The client of a request and its original scheme and host, as determined
+/// by `TrustedProxies::client`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Client {
+    node: Node,
+    scheme: Option<Scheme>,
+    host: Option<Authority>,
+}
+
+/// An error resulting from parsing an invalid `Forwarded`, `X-Forwarded-*`
+/// or `Via` header.
+pub struct InvalidForwarded {
+    _priv: (),
+}
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+// ===== impl Forwarded =====
+
+impl Forwarded {
+    /// Creates an element without parameters.
+    pub fn new() -> Forwarded {
+        Forwarded::default()
+    }
+
+    /// Parses the elements of all the `Forwarded` headers in a `HeaderMap`,
+    /// from the farthest hop to the nearest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::forwarded::{Forwarded, NodeName};
+    /// # use http::header::{HeaderMap, HeaderValue, FORWARDED};
+    /// let mut headers = HeaderMap::new();
+    /// headers.append(FORWARDED, HeaderValue::from_static("for=unknown, for=_hidden"));
+    /// headers.append(FORWARDED, HeaderValue::from_static("for=\"192.0.2.43:8080\";by=_proxy"));
+    ///
+    /// let elements = Forwarded::from_headers(&headers).unwrap();
+    /// assert_eq!(elements.len(), 3);
+    /// assert_eq!(elements[0].forwarded_for().unwrap().name(), &NodeName::Unknown);
+    /// assert_eq!(elements[2].forwarded_for().unwrap().to_string(), "192.0.2.43:8080");
+    /// assert_eq!(elements[2].by().unwrap().to_string(), "_proxy");
+    /// ```
+    pub fn from_headers(
+        headers: &HeaderMap<HeaderValue>,
+    ) -> Result<Vec<Forwarded>, InvalidForwarded> {
+        let mut elements = Vec::new();
+        for value in headers.get_all(header::FORWARDED) {
+            parse_forwarded(value, &mut elements)?;
+        }
+        Ok(elements)
+    }
+
+    /// Parses the elements of a single `Forwarded` header value.
+    pub fn parse(value: &HeaderValue) -> Result<Vec<Forwarded>, InvalidForwarded> {
+        let mut elements = Vec::new();
+        parse_forwarded(value, &mut elements)?;
+        Ok(elements)
+    }
+
+    /// Returns the `by` parameter: the interface where the proxy received
+    /// the request.
+    pub fn by(&self) -> Option<&Node> {
+        self.by.as_ref()
+    }
+
+    /// Returns the `for` parameter: the node that sent the request to the
+    /// proxy.
+    pub fn forwarded_for(&self) -> Option<&Node> {
+        self.for_.as_ref()
+    }
+
+    /// Returns the `host` parameter: the `Host` of the request received by
+    /// the proxy.
+    pub fn host(&self) -> Option<&Authority> {
+        self.host.as_ref()
+    }
+
+    /// Returns the `proto` parameter: the scheme of the request received by
+    /// the proxy.
+    pub fn proto(&self) -> Option<&Scheme> {
+        self.proto.as_ref()
+    }
+
+    /// Sets the `by` parameter.
+    pub fn set_by(&mut self, node: Node) {
+        self.by = Some(node);
+    }
+
+    /// Sets the `for` parameter.
+    pub fn set_for(&mut self, node: Node) {
+        self.for_ = Some(node);
+    }
+
+    /// Sets the `host` parameter.
+    ///
+    /// The userinfo of `host`, if any, is removed.
+    pub fn set_host(&mut self, host: Authority) {
+        self.host = Some(host.without_userinfo());
+    }
+
+    /// Sets the `proto` parameter.
+    pub fn set_proto(&mut self, proto: Scheme) {
+        self.proto = Some(proto);
+    }
+
+    fn set_param(&mut self, name: &str, value: &str) -> Result<(), InvalidForwarded> {
+        let name = name.to_ascii_lowercase();
+        let duplicate = match name.as_str() {
+            "by" => self.by.replace(Node::parse(value)?).is_some(),
+            "for" => self.for_.replace(Node::parse(value)?).is_some(),
+            "host" => self.host.replace(parse_host(value)?).is_some(),
+            "proto" => self.proto.replace(parse_proto(value)?).is_some(),
+            _ => false,
+        };
+        // Each parameter must not occur more than once per element (RFC
+        // 7239, Section 4).
+        if duplicate {
+            return Err(InvalidForwarded::new());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Forwarded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        let mut pair = |f: &mut fmt::Formatter<'_>, name: &str, value: &dyn fmt::Display| {
+            let value = value.to_string();
+            let result = if is_token(&value) {
+                write!(f, "{}{}={}", sep, name, value)
+            } else {
+                write!(f, "{}{}=\"{}\"", sep, name, value)
+            };
+            sep = ";";
+            result
+        };
+        if let Some(ref by) = self.by {
+            pair(f, "by", by)?;
+        }
+        if let Some(ref for_) = self.for_ {
+            pair(f, "for", for_)?;
+        }
+        if let Some(ref host) = self.host {
+            pair(f, "host", host)?;
+        }
+        if let Some(ref proto) = self.proto {
+            pair(f, "proto", proto)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Forwarded> for HeaderValue {
+    fn from(element: Forwarded) -> HeaderValue {
+        HeaderValue::from_str(&element.to_string()).expect("Forwarded is a valid header value")
+    }
+}
+
+fn parse_forwarded(
+    value: &HeaderValue,
+    elements: &mut Vec<Forwarded>,
+) -> Result<(), InvalidForwarded> {
+    let s = value.to_str().map_err(|_| InvalidForwarded::new())?;
+    let mut p = Parser::new(s);
+    loop {
+        p.skip_empty_elements();
+        if p.is_end() {
+            return Ok(());
+        }
+
+        // forwarded-element = [ forwarded-pair ] *( ";" [ forwarded-pair ] )
+        let mut element = Forwarded::new();
+        loop {
+            p.skip_ows();
+            if p.eat(b';') {
+                continue;
+            }
+            if p.is_end() || p.peek() == Some(b',') {
+                break;
+            }
+            let name = p.token().ok_or_else(InvalidForwarded::new)?;
+            if !p.eat(b'=') {
+                return Err(InvalidForwarded::new());
+            }
+            let value = if p.peek() == Some(b'"') {
+                p.quoted_string().ok_or_else(InvalidForwarded::new)?
+            } else {
+                p.token().ok_or_else(InvalidForwarded::new)?.to_owned()
+            };
+            element.set_param(name, &value)?;
+
+            p.skip_ows();
+            if !p.is_end() && p.peek() != Some(b';') && p.peek() != Some(b',') {
+                return Err(InvalidForwarded::new());
+            }
+        }
+        elements.push(element);
+    }
+}
+
+fn parse_host(s: &str) -> Result<Authority, InvalidForwarded> {
+    match Authority::try_from(s) {
+        Ok(ref authority) if authority.username().is_none() && !s.is_empty() => {
+            Ok(authority.clone())
+        }
+        _ => Err(InvalidForwarded::new()),
+    }
+}
+
+fn parse_proto(s: &str) -> Result<Scheme, InvalidForwarded> {
+    let valid = s.bytes().next().map_or(false, |b| b.is_ascii_alphabetic())
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.');
+    if !valid {
+        return Err(InvalidForwarded::new());
+    }
+    s.to_ascii_lowercase()
+        .parse()
+        .map_err(|_| InvalidForwarded::new())
+}
+
+// ===== impl Node =====
+
+impl Node {
+    /// Creates a `Node` from a name and an optional port.
+    pub fn new(name: NodeName, port: Option<NodePort>) -> Node {
+        Node { name, port }
+    }
+
+    /// Parses a node as found in a `Forwarded` header, once unquoted.
+    ///
+    /// An IPv6 address must be in brackets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::forwarded::{Node, NodeName, NodePort};
+    /// let node = Node::parse("[2001:db8:cafe::17]:4711").unwrap();
+    /// assert_eq!(node.ip(), Some("2001:db8:cafe::17".parse().unwrap()));
+    /// assert_eq!(node.port(), Some(&NodePort::Port(4711)));
+    ///
+    /// let node = Node::parse("_SEVKISEK").unwrap();
+    /// assert_eq!(node.name(), &NodeName::Obfuscated("_SEVKISEK".into()));
+    ///
+    /// assert!(Node::parse("2001:db8:cafe::17").is_err());
+    /// ```
+    pub fn parse(s: &str) -> Result<Node, InvalidForwarded> {
+        let (name, rest) = if let Some(rest) = s.strip_prefix('[') {
+            let end = rest.find(']').ok_or_else(InvalidForwarded::new)?;
+            let ip: Ipv6Addr = rest[..end].parse().map_err(|_| InvalidForwarded::new())?;
+            (NodeName::Ip(IpAddr::V6(ip)), &rest[end + 1..])
+        } else {
+            let end = s.find(':').unwrap_or(s.len());
+            let name = &s[..end];
+            let name = if name.eq_ignore_ascii_case("unknown") {
+                NodeName::Unknown
+            } else if is_obfuscated(name) {
+                NodeName::Obfuscated(name.to_owned())
+            } else {
+                let ip: Ipv4Addr = name.parse().map_err(|_| InvalidForwarded::new())?;
+                NodeName::Ip(IpAddr::V4(ip))
+            };
+            (name, &s[end..])
+        };
+
+        let port = match rest.strip_prefix(':') {
+            None if rest.is_empty() => None,
+            None => return Err(InvalidForwarded::new()),
+            Some(port) if is_obfuscated(port) => Some(NodePort::Obfuscated(port.to_owned())),
+            Some(port)
+                if !port.is_empty()
+                    && port.len() <= 5
+                    && port.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                let port = port.parse().map_err(|_| InvalidForwarded::new())?;
+                Some(NodePort::Port(port))
+            }
+            Some(_) => return Err(InvalidForwarded::new()),
+        };
+        Ok(Node { name, port })
+    }
+
+    /// Returns the name of the node.
+    pub fn name(&self) -> &NodeName {
+        &self.name
+    }
+
+    /// Returns the port of the node.
+    pub fn port(&self) -> Option<&NodePort> {
+        self.port.as_ref()
+    }
+
+    /// Returns the IP address of the node, if it is known.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self.name {
+            NodeName::Ip(ip) => Some(ip),
+            NodeName::Unknown | NodeName::Obfuscated(_) => None,
+        }
+    }
+
+    // X-Forwarded-For also has bare IPv6 addresses.
+    fn parse_x_forwarded_for(s: &str) -> Result<Node, InvalidForwarded> {
+        match s.parse() {
+            Ok(ip) => Ok(Node::from(IpAddr::V6(ip))),
+            Err(_) => Node::parse(s),
+        }
+    }
+}
+
+impl From<IpAddr> for Node {
+    fn from(ip: IpAddr) -> Node {
+        Node {
+            name: NodeName::Ip(ip),
+            port: None,
+        }
+    }
+}
+
+impl From<SocketAddr> for Node {
+    fn from(addr: SocketAddr) -> Node {
+        Node {
+            name: NodeName::Ip(addr.ip()),
+            port: Some(NodePort::Port(addr.port())),
+        }
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            NodeName::Ip(IpAddr::V4(ip)) => write!(f, "{}", ip)?,
+            NodeName::Ip(IpAddr::V6(ip)) => write!(f, "[{}]", ip)?,
+            NodeName::Unknown => f.write_str("unknown")?,
+            NodeName::Obfuscated(ref name) => f.write_str(name)?,
+        }
+        match self.port {
+            Some(NodePort::Port(port)) => write!(f, ":{}", port),
+            Some(NodePort::Obfuscated(ref port)) => write!(f, ":{}", port),
+            None => Ok(()),
+        }
+    }
+}
+
+// obfnode = "_" 1*( ALPHA / DIGIT / "." / "_" / "-")
+fn is_obfuscated(s: &str) -> bool {
+    s.len() > 1
+        && s.starts_with('_')
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'_' || b == b'-')
+}
+
+// ===== impl XForwarded =====
+
+impl XForwarded {
+    /// Parses the `X-Forwarded-For`, `X-Forwarded-Proto` and
+    /// `X-Forwarded-Host` headers in a `HeaderMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::forwarded::XForwarded;
+    /// # use http::uri::Scheme;
+    /// # use http::header::{HeaderMap, HeaderValue};
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.195, 2001:db8::1"));
+    /// headers.insert("x-forwarded-proto", HeaderValue::from_static("https"));
+    ///
+    /// let forwarded = XForwarded::from_headers(&headers).unwrap();
+    /// assert_eq!(forwarded.forwarded_for().len(), 2);
+    /// assert_eq!(forwarded.forwarded_for()[1].to_string(), "[2001:db8::1]");
+    /// assert_eq!(forwarded.proto()[0], Scheme::HTTPS);
+    /// assert!(forwarded.host().is_empty());
+    /// ```
+    pub fn from_headers(headers: &HeaderMap<HeaderValue>) -> Result<XForwarded, InvalidForwarded> {
+        Ok(XForwarded {
+            for_: parse_list(headers, X_FORWARDED_FOR, Node::parse_x_forwarded_for)?,
+            proto: parse_list(headers, X_FORWARDED_PROTO, parse_proto)?,
+            host: parse_list(headers, X_FORWARDED_HOST, parse_host)?,
+        })
+    }
+
+    /// Returns the nodes of the `X-Forwarded-For` headers, from the farthest
+    /// to the nearest.
+    pub fn forwarded_for(&self) -> &[Node] {
+        &self.for_
+    }
+
+    /// Returns the schemes of the `X-Forwarded-Proto` headers.
+    pub fn proto(&self) -> &[Scheme] {
+        &self.proto
+    }
+
+    /// Returns the hosts of the `X-Forwarded-Host` headers.
+    pub fn host(&self) -> &[Authority] {
+        &self.host
+    }
+
+    /// Returns `true` if none of the headers were present.
+    pub fn is_empty(&self) -> bool {
+        self.for_.is_empty() && self.proto.is_empty() && self.host.is_empty()
+    }
+}
+
+fn parse_list<T, F>(
+    headers: &HeaderMap<HeaderValue>,
+    name: &str,
+    parse: F,
+) -> Result<Vec<T>, InvalidForwarded>
+where
+    F: Fn(&str) -> Result<T, InvalidForwarded>,
+{
+    let mut items = Vec::new();
+    for value in headers.get_all(name) {
+        let value = value.to_str().map_err(|_| InvalidForwarded::new())?;
+        for item in value.split(',') {
+            let item = item.trim_matches(|c| c == ' ' || c == '\t');
+            if !item.is_empty() {
+                items.push(parse(item)?);
+            }
+        }
+    }
+    Ok(items)
+}
+
+// ===== impl Via =====
+
+impl Via {
+    /// Creates an entry for an intermediary that received a request of the
+    /// given HTTP version.
+    ///
+    /// `received_by` is the host and optional port of the intermediary, or
+    /// a pseudonym.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::forwarded::Via;
+    /// # use http::Version;
+    /// let via = Via::new(Version::HTTP_11, "proxy.example:8080").unwrap();
+    /// assert_eq!(via.to_string(), "1.1 proxy.example:8080");
+    /// ```
+    pub fn new<T: Into<String>>(version: Version, received_by: T) -> Result<Via, InvalidForwarded> {
+        let received_by = received_by.into();
+        if !is_received_by(&received_by) {
+            return Err(InvalidForwarded::new());
+        }
+        let protocol_version = match version {
+            Version::HTTP_09 => "0.9",
+            Version::HTTP_10 => "1.0",
+            Version::HTTP_11 => "1.1",
+            Version::HTTP_2 => "2",
+            Version::HTTP_3 => "3",
+            _ => return Err(InvalidForwarded::new()),
+        };
+        Ok(Via {
+            protocol_name: None,
+            protocol_version: protocol_version.to_owned(),
+            received_by,
+            comment: None,
+        })
+    }
+
+    /// Parses the entries of all the `Via` headers in a `HeaderMap`, from
+    /// the farthest intermediary to the nearest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::forwarded::Via;
+    /// # use http::header::{HeaderMap, HeaderValue, VIA};
+    /// # use http::Version;
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(VIA, HeaderValue::from_static("1.0 fred, 1.1 p.example.net (Apache/1.1)"));
+    ///
+    /// let entries = Via::from_headers(&headers).unwrap();
+    /// assert_eq!(entries[0].version(), Some(Version::HTTP_10));
+    /// assert_eq!(entries[0].received_by(), "fred");
+    /// assert_eq!(entries[1].received_by(), "p.example.net");
+    /// assert_eq!(entries[1].comment(), Some("Apache/1.1"));
+    /// ```
+    pub fn from_headers(headers: &HeaderMap<HeaderValue>) -> Result<Vec<Via>, InvalidForwarded> {
+        let mut entries = Vec::new();
+        for value in headers.get_all(header::VIA) {
+            parse_via(value, &mut entries)?;
+        }
+        Ok(entries)
+    }
+
+    /// Parses the entries of a single `Via` header value.
+    pub fn parse(value: &HeaderValue) -> Result<Vec<Via>, InvalidForwarded> {
+        let mut entries = Vec::new();
+        parse_via(value, &mut entries)?;
+        Ok(entries)
+    }
+
+    /// Returns the name of the received protocol, if it isn't HTTP.
+    pub fn protocol_name(&self) -> Option<&str> {
+        self.protocol_name.as_deref()
+    }
+
+    /// Returns the version of the received protocol, such as `1.1`.
+    pub fn protocol_version(&self) -> &str {
+        &self.protocol_version
+    }
+
+    /// Returns the received protocol as an HTTP `Version`, or `None` if it
+    /// is another protocol or an unknown version of HTTP.
+    pub fn version(&self) -> Option<Version> {
+        let is_http = self
+            .protocol_name
+            .as_ref()
+            .map_or(true, |name| name.eq_ignore_ascii_case("http"));
+        if !is_http {
+            return None;
+        }
+        match self.protocol_version.as_str() {
+            "0.9" => Some(Version::HTTP_09),
+            "1.0" => Some(Version::HTTP_10),
+            "1.1" => Some(Version::HTTP_11),
+            "2" | "2.0" => Some(Version::HTTP_2),
+            "3" | "3.0" => Some(Version::HTTP_3),
+            _ => None,
+        }
+    }
+
+    /// Returns the host and optional port, or pseudonym, of the
+    /// intermediary.
+    pub fn received_by(&self) -> &str {
+        &self.received_by
+    }
+
+    /// Returns the comment, without its parentheses.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+}
+
+impl fmt::Display for Via {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref name) = self.protocol_name {
+            write!(f, "{}/", name)?;
+        }
+        write!(f, "{} {}", self.protocol_version, self.received_by)?;
+        if let Some(ref comment) = self.comment {
+            f.write_str(" (")?;
+            for c in comment.chars() {
+                if c == '(' || c == ')' || c == '\\' {
+                    f.write_str("\\")?;
+                }
+                write!(f, "{}", c)?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Via> for HeaderValue {
+    fn from(via: Via) -> HeaderValue {
+        HeaderValue::from_str(&via.to_string()).expect("Via is a valid header value")
+    }
+}
+
+fn parse_via(value: &HeaderValue, entries: &mut Vec<Via>) -> Result<(), InvalidForwarded> {
+    let s = value.to_str().map_err(|_| InvalidForwarded::new())?;
+    let mut p = Parser::new(s);
+    loop {
+        p.skip_empty_elements();
+        if p.is_end() {
+            return Ok(());
+        }
+
+        // received-protocol = [ protocol-name "/" ] protocol-version
+        let first = p.token().ok_or_else(InvalidForwarded::new)?;
+        let (protocol_name, protocol_version) = if p.eat(b'/') {
+            let version = p.token().ok_or_else(InvalidForwarded::new)?;
+            (Some(first.to_owned()), version)
+        } else {
+            (None, first)
+        };
+
+        if p.skip_ows() == 0 {
+            return Err(InvalidForwarded::new());
+        }
+        let received_by = p.take_while(|b| b > b' ' && b != b',' && b != b'(' && b != 0x7f);
+        if !is_received_by(received_by) {
+            return Err(InvalidForwarded::new());
+        }
+
+        p.skip_ows();
+        let comment = if p.peek() == Some(b'(') {
+            Some(p.comment().ok_or_else(InvalidForwarded::new)?)
+        } else {
+            None
+        };
+
+        entries.push(Via {
+            protocol_name,
+            protocol_version: protocol_version.to_owned(),
+            received_by: received_by.to_owned(),
+            comment,
+        });
+
+        p.skip_ows();
+        if !p.is_end() && p.peek() != Some(b',') {
+            return Err(InvalidForwarded::new());
+        }
+    }
+}
+
+// received-by = pseudonym [ ":" port ], where a host is a valid pseudonym
+// once percent-encoded and bracketed IPv6 addresses are accounted for.
+fn is_received_by(s: &str) -> bool {
+    let host = match s.rfind(':') {
+        Some(i) if !s.ends_with(']') => {
+            let port = &s[i + 1..];
+            if port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit()) {
+                return false;
+            }
+            &s[..i]
+        }
+        _ => s,
+    };
+    match host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        Some(ip) => ip.parse::<Ipv6Addr>().is_ok(),
+        None => host.bytes().all(|b| is_tchar(b) || b == b'%') && !host.is_empty(),
+    }
+}
+
+// ===== impl TrustedProxies =====
+
+impl TrustedProxies {
+    /// Creates an empty list, which trusts no proxy.
+    pub fn new() -> TrustedProxies {
+        TrustedProxies::default()
+    }
+
+    /// Trusts the proxy at `ip`.
+    pub fn add_ip(self, ip: IpAddr) -> TrustedProxies {
+        let prefix_len = match ip {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        self.add_network(ip, prefix_len)
+    }
+
+    /// Trusts the proxies in the network of `ip` with the given prefix
+    /// length, such as `10.0.0.0/8`.
+    ///
+    /// A prefix length longer than the address is the same as the full
+    /// length.
+    pub fn add_network(mut self, ip: IpAddr, prefix_len: u8) -> TrustedProxies {
+        let ip = canonical(ip);
+        let prefix_len = match ip {
+            IpAddr::V4(_) => prefix_len.min(32),
+            IpAddr::V6(_) => prefix_len.min(128),
+        };
+        self.networks.push((ip, prefix_len));
+        self
+    }
+
+    /// Returns `true` if the proxy at `ip` is trusted.
+    ///
+    /// IPv4-mapped IPv6 addresses, such as `::ffff:192.0.2.1`, are treated
+    /// as IPv4 addresses.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        self.networks
+            .iter()
+            .any(|&(network, prefix_len)| match (network, ip) {
+                (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                    let mask = (!0u32).checked_shl(32 - u32::from(prefix_len)).unwrap_or(0);
+                    u32::from(network) & mask == u32::from(ip) & mask
+                }
+                (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                    let mask = (!0u128)
+                        .checked_shl(128 - u32::from(prefix_len))
+                        .unwrap_or(0);
+                    u128::from(network) & mask == u128::from(ip) & mask
+                }
+                _ => false,
+            })
+    }
+
+    /// Determines the client of `request`, received from `peer`, and the
+    /// scheme and host of its original request.
+    ///
+    /// The forwarding headers are only used if `peer` is a trusted proxy.
+    /// They are then read from the nearest hop to the farthest, and the
+    /// client is the first node that isn't a trusted proxy, or the farthest
+    /// node if all are trusted. The `proto` and `host` given by the proxy
+    /// that received the request from the client are the scheme and the
+    /// host of the original request.
+    ///
+    /// `Forwarded` headers are used if present, and `X-Forwarded-*` headers
+    /// otherwise. Without forwarding headers, the client is `peer`, and the
+    /// scheme and host are those of the request URI, or its `Host` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `peer` is a trusted proxy and the forwarding
+    /// headers are invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::forwarded::TrustedProxies;
+    /// # use http::Request;
+    /// let proxies = TrustedProxies::new()
+    ///     .add_ip("198.51.100.1".parse().unwrap())
+    ///     .add_ip("198.51.100.2".parse().unwrap());
+    ///
+    /// // A client at 203.0.113.7 forging its address, through two proxies.
+    /// let request = Request::get("/")
+    ///     .header("x-forwarded-for", "10.0.0.1, 203.0.113.7, 198.51.100.1")
+    ///     .body(())
+    ///     .unwrap();
+    ///
+    /// let client = proxies.client(&request, "198.51.100.2:443".parse().unwrap()).unwrap();
+    /// assert_eq!(client.addr(), Some("203.0.113.7".parse().unwrap()));
+    /// ```
+    pub fn client<B>(
+        &self,
+        request: &Request<B>,
+        peer: SocketAddr,
+    ) -> Result<Client, InvalidForwarded> {
+        let mut client = Client {
+            node: Node::from(peer),
+            scheme: request.uri().scheme().cloned(),
+            host: request_host(request),
+        };
+        if !self.contains(peer.ip()) {
+            return Ok(client);
+        }
+
+        let headers = request.headers();
+        if headers.contains_key(header::FORWARDED) {
+            // Every element we look at was added by a trusted proxy.
+            for element in Forwarded::from_headers(headers)?.into_iter().rev() {
+                if let Some(proto) = element.proto {
+                    client.scheme = Some(proto);
+                }
+                if let Some(host) = element.host {
+                    client.host = Some(host);
+                }
+                match element.for_ {
+                    Some(node) => {
+                        let trusted = node.ip().map_or(false, |ip| self.contains(ip));
+                        client.node = node;
+                        if !trusted {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+            return Ok(client);
+        }
+
+        let forwarded = XForwarded::from_headers(headers)?;
+        let mut index = None;
+        for (i, node) in forwarded.for_.iter().enumerate().rev() {
+            client.node = node.clone();
+            index = Some(i);
+            if !node.ip().map_or(false, |ip| self.contains(ip)) {
+                break;
+            }
+        }
+        // `X-Forwarded-Proto` and `X-Forwarded-Host` usually have a single
+        // value, set by the first proxy; if every proxy appended one, use
+        // the one that goes with the client.
+        let pick = |len: usize| match index {
+            Some(i) if len == forwarded.for_.len() => Some(i),
+            _ => len.checked_sub(1),
+        };
+        if let Some(i) = pick(forwarded.proto.len()) {
+            client.scheme = Some(forwarded.proto[i].clone());
+        }
+        if let Some(i) = pick(forwarded.host.len()) {
+            client.host = Some(forwarded.host[i].clone());
+        }
+        Ok(client)
+    }
+}
+
+// IPv4-mapped IPv6 addresses as IPv4 addresses.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, hi, lo] => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(hi) << 16 | u32::from(lo)))
+            }
+            _ => ip,
+        },
+        IpAddr::V4(_) => ip,
+    }
+}
+
+fn request_host<B>(request: &Request<B>) -> Option<Authority> {
+    if let Some(authority) = request.uri().authority() {
+        return Some(authority.without_userinfo());
+    }
+    let mut values = request.headers().get_all(header::HOST).iter();
+    match (values.next(), values.next()) {
+        (Some(value), None) => value.to_str().ok().and_then(|s| parse_host(s).ok()),
+        _ => None,
+    }
+}
+
+// ===== impl Client =====
+
+impl Client {
+    /// Returns the client node.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Returns the IP address of the client, unless a proxy reported it as
+    /// `unknown` or obfuscated it.
+    pub fn addr(&self) -> Option<IpAddr> {
+        self.node.ip()
+    }
+
+    /// Returns the scheme of the original request, if known.
+    pub fn scheme(&self) -> Option<&Scheme> {
+        self.scheme.as_ref()
+    }
+
+    /// Returns the host of the original request, if known.
+    pub fn host(&self) -> Option<&Authority> {
+        self.host.as_ref()
+    }
+}
+
+// ===== impl InvalidForwarded =====
+
+impl InvalidForwarded {
+    fn new() -> InvalidForwarded {
+        InvalidForwarded { _priv: () }
+    }
+}
+
+impl fmt::Debug for InvalidForwarded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InvalidForwarded")
+            // skip _priv noise
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidForwarded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid forwarding header")
+    }
+}
+
+impl Error for InvalidForwarded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded(s: &'static str) -> Result<Vec<Forwarded>, InvalidForwarded> {
+        Forwarded::parse(&HeaderValue::from_static(s))
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_forwarded_elements() {
+        let elements = forwarded(
+            "For=\"[2001:db8:cafe::17]:4711\";proto=HTTPS;by=203.0.113.43, ;for=192.0.2.60;host=\"example.com:8443\"",
+        )
+        .unwrap();
+        assert_eq!(elements.len(), 2);
+
+        let first = &elements[0];
+        assert_eq!(
+            first.forwarded_for().unwrap().ip(),
+            Some(ip("2001:db8:cafe::17"))
+        );
+        assert_eq!(
+            first.forwarded_for().unwrap().port(),
+            Some(&NodePort::Port(4711))
+        );
+        assert_eq!(first.proto(), Some(&Scheme::HTTPS));
+        assert_eq!(first.by().unwrap().ip(), Some(ip("203.0.113.43")));
+        assert_eq!(first.host(), None);
+
+        let second = &elements[1];
+        assert_eq!(second.forwarded_for().unwrap().ip(), Some(ip("192.0.2.60")));
+        assert_eq!(second.host().unwrap(), "example.com:8443");
+
+        // Unknown parameters are ignored.
+        let elements = forwarded("for=_gazonk;secret=\"x\"").unwrap();
+        assert_eq!(
+            elements[0].forwarded_for().unwrap().name(),
+            &NodeName::Obfuscated("_gazonk".into())
+        );
+        assert!(forwarded("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_invalid_forwarded() {
+        for s in &[
+            "for",
+            "for=",
+            "for=192.0.2.60:80",
+            "for=\"2001:db8::1\"",
+            "for=\"[2001:db8::1\"",
+            "for=\"192.0.2.60:123456\"",
+            "for=\"192.0.2.60:_\"",
+            "for=192.0.2.60;for=192.0.2.61",
+            "for=host.example",
+            "for=\"unterminated",
+            "proto=\"1http\"",
+            "host=\"user@example.com\"",
+            "for=192.0.2.60 x",
+        ] {
+            assert!(
+                Forwarded::parse(&HeaderValue::from_str(s).unwrap()).is_err(),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn forwarded_roundtrip() {
+        let mut element = Forwarded::new();
+        element.set_by(Node::new(
+            NodeName::Obfuscated("_proxy".into()),
+            Some(NodePort::Obfuscated("_p1".into())),
+        ));
+        element.set_for("192.0.2.60:4711".parse::<SocketAddr>().unwrap().into());
+        element.set_host("user@example.com".parse().unwrap());
+        element.set_proto(Scheme::HTTP);
+
+        let value = HeaderValue::from(element.clone());
+        assert_eq!(
+            value,
+            "by=\"_proxy:_p1\";for=\"192.0.2.60:4711\";host=example.com;proto=http"
+        );
+        assert_eq!(Forwarded::parse(&value).unwrap(), [element]);
+    }
+
+    #[test]
+    fn parse_x_forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            X_FORWARDED_FOR,
+            HeaderValue::from_static("192.0.2.1:1234, unknown"),
+        );
+        headers.append(
+            X_FORWARDED_FOR,
+            HeaderValue::from_static(" [2001:db8::1]:80 ,::1"),
+        );
+        headers.append(X_FORWARDED_HOST, HeaderValue::from_static("example.com"));
+        let forwarded = XForwarded::from_headers(&headers).unwrap();
+
+        let nodes: Vec<_> = forwarded
+            .forwarded_for()
+            .iter()
+            .map(Node::to_string)
+            .collect();
+        assert_eq!(
+            nodes,
+            ["192.0.2.1:1234", "unknown", "[2001:db8::1]:80", "[::1]"]
+        );
+        assert!(forwarded.proto().is_empty());
+        assert_eq!(forwarded.host()[0], "example.com");
+
+        headers.insert(X_FORWARDED_PROTO, HeaderValue::from_static("ht tp"));
+        assert!(XForwarded::from_headers(&headers).is_err());
+
+        assert!(XForwarded::from_headers(&HeaderMap::new())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_via() {
+        let value = HeaderValue::from_static(
+            "1.0 fred, HTTP/2 [2001:db8::1]:8080 (nested (comment) \\) here), QUIC/1 edge:443",
+        );
+        let entries = Via::parse(&value).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].protocol_name(), None);
+        assert_eq!(entries[0].protocol_version(), "1.0");
+        assert_eq!(entries[0].received_by(), "fred");
+
+        assert_eq!(entries[1].protocol_name(), Some("HTTP"));
+        assert_eq!(entries[1].version(), Some(Version::HTTP_2));
+        assert_eq!(entries[1].received_by(), "[2001:db8::1]:8080");
+        assert_eq!(entries[1].comment(), Some("nested (comment) ) here"));
+
+        assert_eq!(entries[2].protocol_name(), Some("QUIC"));
+        assert_eq!(entries[2].version(), None);
+        assert_eq!(entries[2].received_by(), "edge:443");
+
+        for s in &[
+            "1.1",
+            "1.1 fred (unterminated",
+            "1.1 fred bar",
+            "/1.1 fred",
+            "1.1 fred:x",
+        ] {
+            assert!(
+                Via::parse(&HeaderValue::from_str(s).unwrap()).is_err(),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn via_roundtrip() {
+        let mut via = Via::new(Version::HTTP_2, "proxy").unwrap();
+        via.comment = Some("a (b) \\c".into());
+        let value = HeaderValue::from(via.clone());
+        assert_eq!(value, "2 proxy (a \\(b\\) \\\\c)");
+        assert_eq!(Via::parse(&value).unwrap(), [via]);
+
+        assert!(Via::new(Version::HTTP_11, "").is_err());
+        assert!(Via::new(Version::HTTP_11, "a b").is_err());
+    }
+
+    #[test]
+    fn trusted_networks() {
+        let proxies = TrustedProxies::new()
+            .add_network(ip("10.0.0.0"), 8)
+            .add_network(ip("2001:db8::"), 32)
+            .add_ip(ip("192.0.2.1"));
+
+        assert!(proxies.contains(ip("10.255.0.1")));
+        assert!(proxies.contains(ip("::ffff:10.0.0.1")));
+        assert!(!proxies.contains(ip("11.0.0.1")));
+        assert!(proxies.contains(ip("2001:db8:1::1")));
+        assert!(!proxies.contains(ip("2001:db9::1")));
+        assert!(proxies.contains(ip("192.0.2.1")));
+        assert!(!proxies.contains(ip("192.0.2.2")));
+
+        let everything = TrustedProxies::new().add_network(ip("0.0.0.0"), 0);
+        assert!(everything.contains(ip("203.0.113.1")));
+        assert!(!everything.contains(ip("::2")));
+    }
+
+    fn peer(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn client_from_forwarded() {
+        let proxies = TrustedProxies::new().add_network(ip("10.0.0.0"), 8);
+        let request = Request::get("/")
+            .header(header::HOST, "internal:8080")
+            .header(
+                header::FORWARDED,
+                "for=198.51.100.7, for=203.0.113.9;proto=https;host=example.com",
+            )
+            .header(header::FORWARDED, "for=10.0.0.2;proto=http;host=internal")
+            .body(())
+            .unwrap();
+
+        let client = proxies.client(&request, peer("10.0.0.1:1234")).unwrap();
+        assert_eq!(client.addr(), Some(ip("203.0.113.9")));
+        assert_eq!(client.scheme(), Some(&Scheme::HTTPS));
+        assert_eq!(client.host().unwrap(), "example.com");
+
+        // Headers from an untrusted peer are ignored.
+        let client = proxies.client(&request, peer("203.0.113.1:1234")).unwrap();
+        assert_eq!(client.node(), &Node::from(peer("203.0.113.1:1234")));
+        assert_eq!(client.scheme(), None);
+        assert_eq!(client.host().unwrap(), "internal:8080");
+
+        // An obfuscated client stops the search.
+        let request = Request::get("https://internal/")
+            .header(header::FORWARDED, "for=192.0.2.1, for=_hidden")
+            .body(())
+            .unwrap();
+        let client = proxies.client(&request, peer("10.0.0.1:1234")).unwrap();
+        assert_eq!(client.addr(), None);
+        assert_eq!(client.node().to_string(), "_hidden");
+        assert_eq!(client.scheme(), Some(&Scheme::HTTPS));
+
+        let request = Request::get("/")
+            .header(header::FORWARDED, "for=\"bad")
+            .body(())
+            .unwrap();
+        assert!(proxies.client(&request, peer("10.0.0.1:1234")).is_err());
+        assert!(proxies.client(&request, peer("192.0.2.1:1234")).is_ok());
+    }
+
+    #[test]
+    fn client_from_x_forwarded() {
+        let proxies = TrustedProxies::new()
+            .add_ip(ip("10.0.0.1"))
+            .add_ip(ip("10.0.0.2"));
+
+        let request = Request::get("/")
+            .header(X_FORWARDED_FOR, "192.0.2.1, 10.0.0.2")
+            .header(X_FORWARDED_PROTO, "https")
+            .header(X_FORWARDED_HOST, "example.com")
+            .body(())
+            .unwrap();
+        let client = proxies.client(&request, peer("10.0.0.1:80")).unwrap();
+        assert_eq!(client.addr(), Some(ip("192.0.2.1")));
+        assert_eq!(client.scheme(), Some(&Scheme::HTTPS));
+        assert_eq!(client.host().unwrap(), "example.com");
+
+        // One value per hop.
+        let request = Request::get("/")
+            .header(X_FORWARDED_FOR, "192.0.2.1, 203.0.113.5, 10.0.0.2")
+            .header(X_FORWARDED_PROTO, "http, https, http")
+            .body(())
+            .unwrap();
+        let client = proxies.client(&request, peer("10.0.0.1:80")).unwrap();
+        assert_eq!(client.addr(), Some(ip("203.0.113.5")));
+        assert_eq!(client.scheme(), Some(&Scheme::HTTPS));
+
+        // All trusted: the farthest node is the client.
+        let request = Request::get("/")
+            .header(X_FORWARDED_FOR, "10.0.0.2")
+            .body(())
+            .unwrap();
+        let client = proxies.client(&request, peer("10.0.0.1:80")).unwrap();
+        assert_eq!(client.addr(), Some(ip("10.0.0.2")));
+    }
+}
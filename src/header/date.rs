@@ -0,0 +1,610 @@
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::{self, FromStr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::header::HeaderValue;
+
+/// A timestamp as used by HTTP date header fields.
+///
+/// `HttpDate` represents the values of the `Date`, `Last-Modified`,
+/// `Expires`, `Retry-After` and `If-Modified-Since` family of headers. It has
+/// a resolution of one second and can represent dates between the years 1970
+/// and 9999 (inclusive).
+///
+/// Dates are always formatted using the preferred IMF-fixdate format:
+///
+/// ```notrust
+/// Sun, 06 Nov 1994 08:49:37 GMT
+/// ```
+///
+/// When parsing, the two obsolete formats that recipients are required to
+/// accept are also supported:
+///
+/// ```notrust
+/// Sunday, 06-Nov-94 08:49:37 GMT   ; obsolete RFC 850 format
+/// Sun Nov  6 08:49:37 1994         ; ANSI C's asctime() format
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use http::header::{HeaderValue, HttpDate};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let date: HttpDate = "Sunday, 06-Nov-94 08:49:37 GMT".parse().unwrap();
+/// assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+///
+/// let date = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+/// assert_eq!(HeaderValue::from(date), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate {
+    // Field order matters: the derived `Ord` compares chronologically.
+    year: u16,
+    mon: u8,
+    day: u8,
+    hour: u8,
+    min: u8,
+    sec: u8,
+    // 1 = Monday, 7 = Sunday
+    wday: u8,
+}
+
+/// A possible error when parsing an `HttpDate`.
+pub struct InvalidHttpDate {
+    _priv: (),
+}
+
+const WDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+const WDAYS_LONG: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Seconds between the UNIX epoch and 10000-01-01T00:00:00Z.
+const MAX_SECS: u64 = 253_402_300_800;
+
+impl HttpDate {
+    /// Returns the current time, truncated to whole seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::header::HttpDate;
+    /// let now = HttpDate::now();
+    /// assert!(now > HttpDate::UNIX_EPOCH);
+    /// ```
+    pub fn now() -> HttpDate {
+        HttpDate::from(SystemTime::now())
+    }
+
+    /// `Thu, 01 Jan 1970 00:00:00 GMT`
+    pub const UNIX_EPOCH: HttpDate = HttpDate {
+        year: 1970,
+        mon: 1,
+        day: 1,
+        hour: 0,
+        min: 0,
+        sec: 0,
+        wday: 4,
+    };
+
+    /// Returns a `HeaderValue` containing the current time, suitable for the
+    /// `Date` header.
+    ///
+    /// Servers typically stamp every response with a `Date` header. Formatting
+    /// the current time on each response is wasteful, so the formatted value
+    /// is cached per thread and only regenerated once the second it represents
+    /// has passed. Cloning the cached value does not allocate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::header::{HttpDate, DATE};
+    /// # use http::Response;
+    /// let mut response = Response::new(());
+    /// response.headers_mut().insert(DATE, HttpDate::now_header_value());
+    /// ```
+    pub fn now_header_value() -> HeaderValue {
+        thread_local! {
+            static CACHED: RefCell<Option<(u64, HeaderValue)>> = RefCell::new(None);
+        }
+
+        let now = SystemTime::now();
+        let secs = now
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        CACHED.with(|cache| {
+            let mut cache = cache.borrow_mut();
+
+            if let Some((cached_secs, ref value)) = *cache {
+                if cached_secs == secs {
+                    return value.clone();
+                }
+            }
+
+            let value = HeaderValue::from(HttpDate::from(now));
+            *cache = Some((secs, value.clone()));
+            value
+        })
+    }
+
+    // Build a date from its calendar fields, returning `None` if they don't
+    // form a valid date in the supported range.
+    pub(crate) fn from_fields(
+        year: u16,
+        mon: u8,
+        day: u8,
+        hour: u8,
+        min: u8,
+        sec: u8,
+    ) -> Option<HttpDate> {
+        if !(1970..=9999).contains(&year) || !(1..=12).contains(&mon) {
+            return None;
+        }
+        if day < 1 || day > days_in_month(year, mon) {
+            return None;
+        }
+        if hour > 23 || min > 59 || sec > 59 {
+            return None;
+        }
+
+        let mut date = HttpDate {
+            year,
+            mon,
+            day,
+            hour,
+            min,
+            sec,
+            wday: 0,
+        };
+        date.wday = HttpDate::from_secs(date.secs_since_epoch()).wday;
+        Some(date)
+    }
+
+    fn from_secs(secs: u64) -> HttpDate {
+        if secs >= MAX_SECS {
+            panic!("date must not be after year 9999");
+        }
+
+        let days = secs / 86400;
+        let secs_of_day = secs % 86400;
+
+        let (year, mon, day) = civil_from_days(days as i64);
+
+        HttpDate {
+            year: year as u16,
+            mon: mon as u8,
+            day: day as u8,
+            hour: (secs_of_day / 3600) as u8,
+            min: ((secs_of_day % 3600) / 60) as u8,
+            sec: (secs_of_day % 60) as u8,
+            // 1970-01-01 was a Thursday
+            wday: ((days + 3) % 7 + 1) as u8,
+        }
+    }
+
+    fn secs_since_epoch(&self) -> u64 {
+        let days = days_from_civil(self.year as i64, self.mon as i64, self.day as i64);
+        days as u64 * 86400 + self.hour as u64 * 3600 + self.min as u64 * 60 + self.sec as u64
+    }
+
+    fn is_valid(&self) -> bool {
+        HttpDate::from_fields(self.year, self.mon, self.day, self.hour, self.min, self.sec)
+            .map_or(false, |date| date.wday == self.wday)
+    }
+
+    // Sun, 06 Nov 1994 08:49:37 GMT
+    fn parse_imf_fixdate(s: &[u8]) -> Option<HttpDate> {
+        if s.len() != 29 || &s[25..] != b" GMT" || s[3] != b',' {
+            return None;
+        }
+
+        let date = HttpDate {
+            wday: wday(&s[..3])?,
+            day: digits(&s[5..7])? as u8,
+            mon: month(&s[8..11])?,
+            year: digits(&s[12..16])?,
+            hour: digits(&s[17..19])? as u8,
+            min: digits(&s[20..22])? as u8,
+            sec: digits(&s[23..25])? as u8,
+        };
+
+        if s[4] != b' ' || s[7] != b' ' || s[11] != b' ' || s[16] != b' ' {
+            return None;
+        }
+        if s[19] != b':' || s[22] != b':' {
+            return None;
+        }
+
+        Some(date)
+    }
+
+    // Sunday, 06-Nov-94 08:49:37 GMT
+    fn parse_rfc850_date(s: &[u8]) -> Option<HttpDate> {
+        let comma = s.iter().position(|&b| b == b',')?;
+        let wday = wday_long(&s[..comma])?;
+        let s = &s[comma..];
+
+        if s.len() != 24 || &s[20..] != b" GMT" {
+            return None;
+        }
+        if &s[..2] != b", " || s[4] != b'-' || s[8] != b'-' || s[11] != b' ' {
+            return None;
+        }
+        if s[14] != b':' || s[17] != b':' {
+            return None;
+        }
+
+        let year = two_digit_year(digits(&s[9..11])?);
+
+        Some(HttpDate {
+            wday,
+            day: digits(&s[2..4])? as u8,
+            mon: month(&s[5..8])?,
+            year,
+            hour: digits(&s[12..14])? as u8,
+            min: digits(&s[15..17])? as u8,
+            sec: digits(&s[18..20])? as u8,
+        })
+    }
+
+    // Sun Nov  6 08:49:37 1994
+    fn parse_asctime(s: &[u8]) -> Option<HttpDate> {
+        if s.len() != 24 {
+            return None;
+        }
+        if s[3] != b' ' || s[7] != b' ' || s[10] != b' ' || s[19] != b' ' {
+            return None;
+        }
+        if s[13] != b':' || s[16] != b':' {
+            return None;
+        }
+
+        let day = if s[8] == b' ' {
+            digits(&s[9..10])?
+        } else {
+            digits(&s[8..10])?
+        };
+
+        Some(HttpDate {
+            wday: wday(&s[..3])?,
+            mon: month(&s[4..7])?,
+            day: day as u8,
+            hour: digits(&s[11..13])? as u8,
+            min: digits(&s[14..16])? as u8,
+            sec: digits(&s[17..19])? as u8,
+            year: digits(&s[20..24])?,
+        })
+    }
+}
+
+impl FromStr for HttpDate {
+    type Err = InvalidHttpDate;
+
+    fn from_str(s: &str) -> Result<HttpDate, InvalidHttpDate> {
+        let s = s.as_bytes();
+
+        HttpDate::parse_imf_fixdate(s)
+            .or_else(|| HttpDate::parse_rfc850_date(s))
+            .or_else(|| HttpDate::parse_asctime(s))
+            .filter(HttpDate::is_valid)
+            .ok_or(InvalidHttpDate { _priv: () })
+    }
+}
+
+impl<'a> TryFrom<&'a str> for HttpDate {
+    type Error = InvalidHttpDate;
+
+    #[inline]
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl<'a> TryFrom<&'a HeaderValue> for HttpDate {
+    type Error = InvalidHttpDate;
+
+    fn try_from(value: &'a HeaderValue) -> Result<Self, Self::Error> {
+        value
+            .to_str()
+            .map_err(|_| InvalidHttpDate { _priv: () })?
+            .parse()
+    }
+}
+
+/// Converts a `SystemTime` to an `HttpDate`, truncating to whole seconds.
+///
+/// # Panics
+///
+/// Panics if the time is before the UNIX epoch or after the year 9999.
+impl From<SystemTime> for HttpDate {
+    fn from(time: SystemTime) -> HttpDate {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .expect("all times should be after the epoch")
+            .as_secs();
+
+        HttpDate::from_secs(secs)
+    }
+}
+
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(date.secs_since_epoch())
+    }
+}
+
+impl From<HttpDate> for HeaderValue {
+    fn from(date: HttpDate) -> HeaderValue {
+        let mut buf = String::with_capacity(29);
+        write!(buf, "{}", date).expect("writing to a String never fails");
+
+        // Safety: IMF-fixdate is always visible ASCII.
+        unsafe { HeaderValue::from_maybe_shared_unchecked(Bytes::from(buf)) }
+    }
+}
+
+impl fmt::Display for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            WDAYS[self.wday as usize - 1],
+            self.day,
+            MONTHS[self.mon as usize - 1],
+            self.year,
+            self.hour,
+            self.min,
+            self.sec,
+        )
+    }
+}
+
+impl fmt::Debug for HttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HttpDate").field(&format_args!("{}", self)).finish()
+    }
+}
+
+impl fmt::Debug for InvalidHttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InvalidHttpDate")
+            // skip _priv noise
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidHttpDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid HTTP date")
+    }
+}
+
+impl Error for InvalidHttpDate {}
+
+fn digits(s: &[u8]) -> Option<u16> {
+    let mut n = 0u16;
+    for &b in s {
+        if !b.is_ascii_digit() {
+            return None;
+        }
+        n = n * 10 + (b - b'0') as u16;
+    }
+    Some(n)
+}
+
+fn wday(s: &[u8]) -> Option<u8> {
+    WDAYS
+        .iter()
+        .position(|d| d.as_bytes() == s)
+        .map(|i| i as u8 + 1)
+}
+
+fn wday_long(s: &[u8]) -> Option<u8> {
+    WDAYS_LONG
+        .iter()
+        .position(|d| d.as_bytes() == s)
+        .map(|i| i as u8 + 1)
+}
+
+fn month(s: &[u8]) -> Option<u8> {
+    MONTHS
+        .iter()
+        .position(|m| m.as_bytes() == s)
+        .map(|i| i as u8 + 1)
+}
+
+// RFC 7231, Section 7.1.1.1:
+//
+// > Recipients of a timestamp value in rfc850-date format, which uses a
+// > two-digit year, MUST interpret a timestamp that appears to be more
+// > than 50 years in the future as representing the most recent year in
+// > the past that had the same last two digits.
+fn two_digit_year(yy: u16) -> u16 {
+    let current = HttpDate::now().year;
+    let mut year = current - current % 100 + yy;
+    if year > current + 50 {
+        year -= 100;
+    }
+    year
+}
+
+fn is_leap_year(year: u16) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u16, mon: u8) -> u8 {
+    match mon {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date.
+//
+// See http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, mon: i64, day: i64) -> i64 {
+    let year = if mon <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (mon + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let mon = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if mon <= 2 { 1 } else { 0 };
+    (year, mon, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> HttpDate {
+        s.parse().expect(&format!("parsing {}", s))
+    }
+
+    fn secs(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn formats_imf_fixdate() {
+        assert_eq!(HttpDate::from(secs(0)).to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(HttpDate::from(secs(0)), HttpDate::UNIX_EPOCH);
+        assert_eq!(
+            HttpDate::from(secs(784111777)).to_string(),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+        assert_eq!(
+            HttpDate::from(secs(951782400)).to_string(),
+            "Tue, 29 Feb 2000 00:00:00 GMT"
+        );
+        assert_eq!(
+            HttpDate::from(secs(MAX_SECS - 1)).to_string(),
+            "Fri, 31 Dec 9999 23:59:59 GMT"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "date must not be after year 9999")]
+    fn panics_after_year_9999() {
+        HttpDate::from(secs(MAX_SECS));
+    }
+
+    #[test]
+    fn parses_all_formats() {
+        let expected = HttpDate::from(secs(784111777));
+        assert_eq!(date("Sun, 06 Nov 1994 08:49:37 GMT"), expected);
+        assert_eq!(date("Sunday, 06-Nov-94 08:49:37 GMT"), expected);
+        assert_eq!(date("Sun Nov  6 08:49:37 1994"), expected);
+        assert_eq!(date("Sun Nov 06 08:49:37 1994"), expected);
+    }
+
+    #[test]
+    fn round_trips_through_system_time() {
+        for &n in &[0, 1, 68_169_600, 784_111_777, 951_782_400, 1_600_000_000] {
+            let date = HttpDate::from(secs(n));
+            assert_eq!(SystemTime::from(date), secs(n));
+            assert_eq!(date.to_string().parse::<HttpDate>().unwrap(), date);
+        }
+    }
+
+    #[test]
+    fn truncates_subsecond_precision() {
+        let time = secs(784111777) + Duration::from_millis(999);
+        assert_eq!(SystemTime::from(HttpDate::from(time)), secs(784111777));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        let invalid = [
+            "",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Mon, 06 Nov 1994 08:49:37 GMT",
+            "Sun, 31 Nov 1994 08:49:37 GMT",
+            "Thu, 29 Feb 2001 00:00:00 GMT",
+            "Sun, 06 Nov 1994 24:49:37 GMT",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1969 08:49:37 GMT",
+            "Sun, 06 Nov 1994 08:49:37 GMT ",
+            "Sun,  06 Nov 1994 08:49:37 GMT",
+            "Sun, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 94",
+            "Sonntag, 06-Nov-94 08:49:37 GMT",
+        ];
+
+        for s in &invalid {
+            assert!(s.parse::<HttpDate>().is_err(), "{:?} should be invalid", s);
+        }
+    }
+
+    #[test]
+    fn rfc850_two_digit_year_is_in_the_past() {
+        let current = HttpDate::now().year;
+        for yy in 0..100 {
+            let year = two_digit_year(yy);
+            assert_eq!(year % 100, yy);
+            assert!(year <= current + 50);
+            assert!(year > current + 50 - 100);
+        }
+    }
+
+    #[test]
+    fn orders_chronologically() {
+        assert!(date("Sun, 06 Nov 1994 08:49:37 GMT") < date("Mon, 07 Nov 1994 00:00:00 GMT"));
+        assert!(date("Sat, 31 Dec 1994 23:59:59 GMT") < date("Sun, 01 Jan 1995 00:00:00 GMT"));
+    }
+
+    #[test]
+    fn converts_to_and_from_header_value() {
+        let value = HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT");
+        let date = HttpDate::try_from(&value).unwrap();
+        assert_eq!(HeaderValue::from(date), value);
+
+        let value = HeaderValue::from_bytes(b"Sun, 06 Nov 1994 08:49:37 \xff").unwrap();
+        assert!(HttpDate::try_from(&value).is_err());
+    }
+
+    #[test]
+    fn now_header_value_is_a_valid_date() {
+        let a = HttpDate::now_header_value();
+        let b = HttpDate::now_header_value();
+        let date = HttpDate::try_from(&a).unwrap();
+
+        assert!(date <= HttpDate::try_from(&b).unwrap());
+        assert!(date <= HttpDate::now());
+    }
+}
@@ -64,16 +64,25 @@
 //! `HeaderMap` can store a maximum of 32,768 headers (header name / value
 //! pairs). Attempting to insert more will result in a panic.
 //!
+//! # `HttpDate`
+//!
+//! [`HttpDate`] is the value type shared by the `Date`, `Last-Modified`,
+//! `Expires`, `Retry-After` and `If-Modified-Since` headers. It converts to
+//! and from `SystemTime` and `HeaderValue`.
+//!
 //! [`HeaderName`]: struct.HeaderName.html
 //! [`HeaderMap`]: struct.HeaderMap.html
 //! [multimap]: https://en.wikipedia.org/wiki/Multimap
 //! [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
 //! [Robin Hood hashing]: https://en.wikipedia.org/wiki/Hash_table#Robin_Hood_hashing
+//! [`HttpDate`]: struct.HttpDate.html
 
+mod date;
 mod map;
 mod name;
 mod value;
 
+pub use self::date::{HttpDate, InvalidHttpDate};
 pub use self::map::{
     AsHeaderName, Drain, Entry, GetAll, HeaderMap, IntoHeaderName, IntoIter, Iter, IterMut, Keys,
     OccupiedEntry, VacantEntry, ValueDrain, ValueIter, ValueIterMut, Values, ValuesMut,
@@ -169,4 +178,4 @@ pub use self::name::{
 /// Generally, 64kb for a header name is WAY too much than would ever be needed
 /// in practice. Restricting it to this size enables using `u16` values to
 /// represent offsets when dealing with header names.
-const MAX_HEADER_NAME_LEN: usize = (1 << 16) - 1;
+pub(crate) const MAX_HEADER_NAME_LEN: usize = (1 << 16) - 1;
@@ -1,4 +1,5 @@
 use crate::byte_str::ByteStr;
+use crate::limits::Limits;
 use bytes::{Bytes, BytesMut};
 
 use std::borrow::Borrow;
@@ -57,7 +58,7 @@ struct MaybeLower<'a> {
 
 /// A possible error when converting a `HeaderName` from another type.
 pub struct InvalidHeaderName {
-    _priv: (),
+    too_long: bool,
 }
 
 macro_rules! standard_headers {
@@ -1514,7 +1515,7 @@ fn parse_hdr<'a>(
         len if len <= super::MAX_HEADER_NAME_LEN => {
             Ok(HdrName::custom(data, false))
         }
-        _ => Err(InvalidHeaderName::new()),
+        _ => Err(InvalidHeaderName::too_long()),
     }
 }
 
@@ -1538,11 +1539,9 @@ fn parse_hdr<'a>(
         }
     };
 
-    assert!(
-        len < super::MAX_HEADER_NAME_LEN,
-        "header name too long -- max length is {}",
-        super::MAX_HEADER_NAME_LEN
-    );
+    if len > super::MAX_HEADER_NAME_LEN {
+        return Err(InvalidHeaderName::too_long());
+    }
 
     match len {
         0 => Err(InvalidHeaderName::new()),
@@ -1682,6 +1681,34 @@ impl HeaderName {
         }
     }
 
+    /// Converts a slice of bytes to an HTTP header name, within `limits`.
+    ///
+    /// Like `from_bytes`, but a name longer than the maximum header name
+    /// length is rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::Limits;
+    /// # use http::header::*;
+    /// let limits = Limits::new().max_header_name_len(16);
+    ///
+    /// let hdr = HeaderName::from_bytes_with_limits(b"Content-Length", &limits).unwrap();
+    /// assert_eq!(CONTENT_LENGTH, hdr);
+    ///
+    /// let err = HeaderName::from_bytes_with_limits(b"X-Very-Long-Header-Name", &limits).unwrap_err();
+    /// assert!(err.is_too_long());
+    /// ```
+    pub fn from_bytes_with_limits(
+        src: &[u8],
+        limits: &Limits,
+    ) -> Result<HeaderName, InvalidHeaderName> {
+        if src.len() > limits.header_name_len {
+            return Err(InvalidHeaderName::too_long());
+        }
+        HeaderName::from_bytes(src)
+    }
+
     /// Converts a slice of bytes to an HTTP header name.
     ///
     /// This function expects the input to only contain lowercase characters.
@@ -1810,7 +1837,7 @@ impl FromStr for HeaderName {
     type Err = InvalidHeaderName;
 
     fn from_str(s: &str) -> Result<HeaderName, InvalidHeaderName> {
-        HeaderName::from_bytes(s.as_bytes()).map_err(|_| InvalidHeaderName { _priv: () })
+        HeaderName::from_bytes(s.as_bytes())
     }
 }
 
@@ -1846,7 +1873,18 @@ impl fmt::Display for HeaderName {
 
 impl InvalidHeaderName {
     fn new() -> InvalidHeaderName {
-        InvalidHeaderName { _priv: () }
+        InvalidHeaderName { too_long: false }
+    }
+
+    fn too_long() -> InvalidHeaderName {
+        InvalidHeaderName { too_long: true }
+    }
+
+    /// Returns `true` if the header name was too long.
+    ///
+    /// See `Limits`.
+    pub fn is_too_long(&self) -> bool {
+        self.too_long
     }
 }
 
@@ -2009,14 +2047,18 @@ impl<'a> PartialEq<HeaderName> for &'a str {
 impl fmt::Debug for InvalidHeaderName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("InvalidHeaderName")
-            // skip _priv noise
+            .field("too_long", &self.too_long)
             .finish()
     }
 }
 
 impl fmt::Display for InvalidHeaderName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("invalid HTTP header name")
+        if self.too_long {
+            f.write_str("HTTP header name too long")
+        } else {
+            f.write_str("invalid HTTP header name")
+        }
     }
 }
 
@@ -2185,6 +2227,22 @@ mod tests {
             HeaderName::from_bytes(long.as_slice()).is_err(),
             "longer than max header name length is an error",
         );
+        assert!(HeaderName::from_bytes(long.as_slice())
+            .unwrap_err()
+            .is_too_long());
+        assert!(!HeaderName::from_bytes(b"a b").unwrap_err().is_too_long());
+    }
+
+    #[test]
+    fn test_name_limits() {
+        let limits = Limits::new().max_header_name_len(4);
+        assert_eq!(
+            HeaderName::from_bytes_with_limits(b"Date", &limits).unwrap(),
+            DATE
+        );
+        let err = HeaderName::from_bytes_with_limits(b"Accept", &limits).unwrap_err();
+        assert!(err.is_too_long());
+        assert_eq!(err.to_string(), "HTTP header name too long");
     }
 
     #[test]
@@ -6,6 +6,7 @@ use std::str::FromStr;
 use std::{cmp, fmt, mem, str};
 
 use crate::header::name::HeaderName;
+use crate::limits::Limits;
 
 /// Represents an HTTP header field value.
 ///
@@ -25,7 +26,7 @@ pub struct HeaderValue {
 /// A possible error when converting a `HeaderValue` from a string or byte
 /// slice.
 pub struct InvalidHeaderValue {
-    _priv: (),
+    too_long: bool,
 }
 
 /// A possible error when converting a `HeaderValue` to a string representation.
@@ -186,6 +187,36 @@ impl HeaderValue {
         HeaderValue::from_bytes(src.as_ref())
     }
 
+    /// Attempt to convert a `Bytes` buffer to a `HeaderValue`, within
+    /// `limits`.
+    ///
+    /// Like `from_maybe_shared`, but a value longer than the maximum header
+    /// value length is rejected before it is copied or validated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::{HeaderValue, Limits};
+    /// let limits = Limits::new().max_header_value_len(8);
+    ///
+    /// assert!(HeaderValue::from_maybe_shared_with_limits("no-cache", &limits).is_ok());
+    ///
+    /// let err = HeaderValue::from_maybe_shared_with_limits("max-age=3600", &limits).unwrap_err();
+    /// assert!(err.is_too_long());
+    /// ```
+    pub fn from_maybe_shared_with_limits<T>(
+        src: T,
+        limits: &Limits,
+    ) -> Result<HeaderValue, InvalidHeaderValue>
+    where
+        T: AsRef<[u8]> + 'static,
+    {
+        if src.as_ref().len() > limits.header_value_len {
+            return Err(InvalidHeaderValue { too_long: true });
+        }
+        HeaderValue::from_maybe_shared(src)
+    }
+
     /// Convert a `Bytes` directly into a `HeaderValue` without validating.
     ///
     /// This function does NOT validate that illegal bytes are not contained
@@ -225,7 +256,7 @@ impl HeaderValue {
     fn try_from_generic<T: AsRef<[u8]>, F: FnOnce(T) -> Bytes>(src: T, into: F) -> Result<HeaderValue, InvalidHeaderValue> {
         for &b in src.as_ref() {
             if !is_valid(b) {
-                return Err(InvalidHeaderValue { _priv: () });
+                return Err(InvalidHeaderValue { too_long: false });
             }
         }
         Ok(HeaderValue {
@@ -588,17 +619,30 @@ fn is_valid(b: u8) -> bool {
     b >= 32 && b != 127 || b == b'\t'
 }
 
+impl InvalidHeaderValue {
+    /// Returns `true` if the header value was too long.
+    ///
+    /// See `Limits`.
+    pub fn is_too_long(&self) -> bool {
+        self.too_long
+    }
+}
+
 impl fmt::Debug for InvalidHeaderValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("InvalidHeaderValue")
-            // skip _priv noise
+            .field("too_long", &self.too_long)
             .finish()
     }
 }
 
 impl fmt::Display for InvalidHeaderValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("failed to parse header value")
+        if self.too_long {
+            f.write_str("header value too long")
+        } else {
+            f.write_str("failed to parse header value")
+        }
     }
 }
 
@@ -792,3 +836,19 @@ fn test_debug() {
     sensitive.set_sensitive(true);
     assert_eq!("Sensitive", format!("{:?}", sensitive));
 }
+
+#[test]
+fn test_limits() {
+    let limits = Limits::new().max_header_value_len(5);
+    assert_eq!(
+        HeaderValue::from_maybe_shared_with_limits(Bytes::from_static(b"hello"), &limits).unwrap(),
+        "hello"
+    );
+
+    let err = HeaderValue::from_maybe_shared_with_limits("hello!", &limits).unwrap_err();
+    assert!(err.is_too_long());
+    assert_eq!(err.to_string(), "header value too long");
+
+    let err = HeaderValue::from_maybe_shared_with_limits("\n", &limits).unwrap_err();
+    assert!(!err.is_too_long());
+}
@@ -170,20 +170,32 @@ doctest!("../README.md");
 #[macro_use]
 mod convert;
 
+pub mod auth;
+pub mod cookie;
+pub mod cors;
+pub mod forwarded;
 pub mod header;
 pub mod method;
+pub mod redirect;
 pub mod request;
 pub mod response;
+pub mod sf;
+pub mod signature;
 pub mod status;
 pub mod uri;
 pub mod version;
 
+mod base64;
 mod byte_str;
 mod error;
 mod extensions;
+mod limits;
+mod sha256;
+mod token;
 
 pub use crate::error::{Error, Result};
 pub use crate::extensions::Extensions;
+pub use crate::limits::Limits;
 #[doc(no_inline)]
 pub use crate::header::{HeaderMap, HeaderValue};
 pub use crate::method::Method;
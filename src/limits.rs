@@ -0,0 +1,122 @@
+use std::cmp;
+
+use crate::header::MAX_HEADER_NAME_LEN;
+use crate::uri::{MAX_LEN, MAX_SCHEME_LEN};
+
+/// Length limits for parsing a `Uri`, a `HeaderName` or a `HeaderValue`.
+///
+/// The default limits are the largest lengths this crate supports. A server
+/// can lower them, and pass the `Limits` to `Uri::from_maybe_shared_with_limits`,
+/// `HeaderName::from_bytes_with_limits` or
+/// `HeaderValue::from_maybe_shared_with_limits`, to reject overly long input
+/// up front. Input over a limit is rejected before it is copied and parsed,
+/// with an error whose `is_too_long` method returns `true`.
+///
+/// Limits can only be lowered: a limit larger than the default is capped at
+/// the default.
+///
+/// # Examples
+///
+/// ```
+/// use http::{HeaderValue, Limits, Uri};
+/// use http::header::HeaderName;
+///
+/// let limits = Limits::new()
+///     .max_uri_len(8 * 1024)
+///     .max_header_name_len(256)
+///     .max_header_value_len(4 * 1024);
+///
+/// let long_path = format!("/{}", "a".repeat(8 * 1024));
+/// let err = Uri::from_maybe_shared_with_limits(long_path, &limits).unwrap_err();
+/// assert!(err.is_too_long());
+///
+/// let err = HeaderName::from_bytes_with_limits(&[b'x'; 257], &limits).unwrap_err();
+/// assert!(err.is_too_long());
+///
+/// let value = HeaderValue::from_maybe_shared_with_limits("text/html", &limits).unwrap();
+/// assert_eq!(value, "text/html");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub(crate) uri_len: usize,
+    pub(crate) scheme_len: usize,
+    pub(crate) header_name_len: usize,
+    pub(crate) header_value_len: usize,
+}
+
+impl Limits {
+    /// Creates the default limits, which are the largest supported lengths.
+    pub fn new() -> Limits {
+        Limits {
+            uri_len: MAX_LEN,
+            scheme_len: MAX_SCHEME_LEN,
+            header_name_len: MAX_HEADER_NAME_LEN,
+            header_value_len: usize::MAX,
+        }
+    }
+
+    /// Sets the maximum length in bytes of a `Uri`.
+    ///
+    /// The default is 4 GiB minus 2 bytes.
+    pub fn max_uri_len(self, len: usize) -> Limits {
+        Limits {
+            uri_len: cmp::min(len, MAX_LEN),
+            ..self
+        }
+    }
+
+    /// Sets the maximum length in bytes of the scheme of a `Uri`.
+    ///
+    /// The default is 64.
+    pub fn max_scheme_len(self, len: usize) -> Limits {
+        Limits {
+            scheme_len: cmp::min(len, MAX_SCHEME_LEN),
+            ..self
+        }
+    }
+
+    /// Sets the maximum length in bytes of a `HeaderName`.
+    ///
+    /// The default is 65,535.
+    pub fn max_header_name_len(self, len: usize) -> Limits {
+        Limits {
+            header_name_len: cmp::min(len, MAX_HEADER_NAME_LEN),
+            ..self
+        }
+    }
+
+    /// Sets the maximum length in bytes of a `HeaderValue`.
+    ///
+    /// There is no limit by default.
+    pub fn max_header_value_len(self, len: usize) -> Limits {
+        Limits {
+            header_value_len: len,
+            ..self
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_can_only_be_lowered() {
+        let limits = Limits::new()
+            .max_uri_len(usize::MAX)
+            .max_scheme_len(1024)
+            .max_header_name_len(1 << 20);
+        assert_eq!(limits, Limits::default());
+
+        let limits = limits.max_scheme_len(8).max_header_value_len(10);
+        assert_eq!(limits.scheme_len, 8);
+        assert_eq!(limits.header_value_len, 10);
+        assert_eq!(limits.uri_len, MAX_LEN);
+    }
+}
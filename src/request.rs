@@ -54,10 +54,11 @@
 
 use std::any::Any;
 use std::convert::{TryFrom};
-use std::fmt;
+use std::{error, fmt, result};
 
-use crate::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::header::{self, HeaderMap, HeaderName, HeaderValue};
 use crate::method::Method;
+use crate::uri::{self, Authority, Scheme, TargetForm};
 use crate::version::Version;
 use crate::{Extensions, Result, Uri};
 
@@ -182,6 +183,20 @@ pub struct Parts {
     _priv: (),
 }
 
+/// An error resulting from a request target that doesn't fit the method or
+/// the `Host` header.
+pub struct InvalidRequestTarget {
+    kind: TargetErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetErrorKind {
+    InvalidForm,
+    HostMissing,
+    InvalidHost,
+    HostMismatch,
+}
+
 /// An HTTP request builder
 ///
 /// This type can be used to construct an instance or `Request`
@@ -693,6 +708,195 @@ impl<T> Request<T> {
             head: self.head,
         }
     }
+
+    /// Checks that the form of the request target is allowed for the
+    /// method.
+    ///
+    /// `CONNECT` requests must use the authority-form, which no other method
+    /// may use, and only `OPTIONS` requests may use the asterisk-form (RFC
+    /// 9112, Section 3.2).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::*;
+    /// let request = Request::connect("example.com:443").body(()).unwrap();
+    /// assert!(request.check_target_form().is_ok());
+    ///
+    /// let request = Request::get("*").body(()).unwrap();
+    /// assert!(request.check_target_form().is_err());
+    /// ```
+    pub fn check_target_form(&self) -> result::Result<(), InvalidRequestTarget> {
+        let is_connect = self.head.method == Method::CONNECT;
+        let allowed = match self.head.uri.target_form() {
+            TargetForm::Origin | TargetForm::Absolute => !is_connect,
+            TargetForm::Authority => is_connect,
+            TargetForm::Asterisk => self.head.method == Method::OPTIONS,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(InvalidRequestTarget::new(TargetErrorKind::InvalidForm))
+        }
+    }
+
+    /// Syncs the `Host` header with the authority of the URI.
+    ///
+    /// If the URI has an authority, the `Host` header is set to it, without
+    /// any user information, when missing. An error is returned if the
+    /// `Host` header is invalid, repeated, or names another host or port
+    /// than the URI; an omitted port is the default port of the scheme.
+    /// Nothing is done if the URI has no authority.
+    ///
+    /// A server that follows RFC 9112, Section 3.2.2, and ignores the `Host`
+    /// header of an absolute-form request should remove it before calling
+    /// this.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::*;
+    /// let mut request = Request::get("https://user@example.com/").body(()).unwrap();
+    /// request.sync_host().unwrap();
+    /// assert_eq!(request.headers()[header::HOST], "example.com");
+    ///
+    /// let mut request = Request::get("https://example.com/")
+    ///     .header(header::HOST, "example.com:443")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert!(request.sync_host().is_ok());
+    ///
+    /// let mut request = Request::get("https://example.com/")
+    ///     .header(header::HOST, "evil.example")
+    ///     .body(())
+    ///     .unwrap();
+    /// assert!(request.sync_host().is_err());
+    /// ```
+    pub fn sync_host(&mut self) -> result::Result<(), InvalidRequestTarget> {
+        let authority = match self.head.uri.authority() {
+            Some(authority) => authority.without_userinfo(),
+            None => return Ok(()),
+        };
+        match self.host_authority()? {
+            Some(host) => {
+                let default_port = self.head.uri.scheme().and_then(Scheme::default_port);
+                let port = |a: &Authority| a.port_u16().or(default_port);
+                if host.host().eq_ignore_ascii_case(authority.host())
+                    && port(&host) == port(&authority)
+                {
+                    Ok(())
+                } else {
+                    Err(InvalidRequestTarget::new(TargetErrorKind::HostMismatch))
+                }
+            }
+            None => {
+                let host = HeaderValue::from_str(authority.as_str())
+                    .expect("authority is a valid header value");
+                self.head.headers.insert(header::HOST, host);
+                Ok(())
+            }
+        }
+    }
+
+    /// Converts an absolute-form request target to the origin-form, which
+    /// is sent to origin servers.
+    ///
+    /// The `Host` header is synced with the authority first, as by
+    /// [`Request::sync_host`], and an empty path of an `OPTIONS` request
+    /// becomes `*`. Origin-form and asterisk-form targets are kept as-is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the form of the target isn't allowed for the
+    /// method, as checked by [`Request::check_target_form`], if it is the
+    /// authority-form of a `CONNECT` request, which can't be converted, or
+    /// if the `Host` header doesn't match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::*;
+    /// let mut request = Request::get("http://example.com:8080/a?b").body(()).unwrap();
+    /// request.set_origin_form().unwrap();
+    ///
+    /// assert_eq!(request.uri(), "/a?b");
+    /// assert_eq!(request.headers()[header::HOST], "example.com:8080");
+    /// ```
+    pub fn set_origin_form(&mut self) -> result::Result<(), InvalidRequestTarget> {
+        self.check_target_form()?;
+        match self.head.uri.target_form() {
+            TargetForm::Absolute => {
+                self.sync_host()?;
+                let is_options = self.head.method == Method::OPTIONS;
+                self.head.uri = uri::origin_form(&self.head.uri, is_options);
+                Ok(())
+            }
+            TargetForm::Authority => Err(InvalidRequestTarget::new(TargetErrorKind::InvalidForm)),
+            TargetForm::Origin | TargetForm::Asterisk => Ok(()),
+        }
+    }
+
+    /// Converts an origin-form or asterisk-form request target to the
+    /// absolute-form, which is sent to proxies, using the authority of the
+    /// `Host` header and `scheme`.
+    ///
+    /// `*` becomes an empty path. An absolute-form target is kept as-is, but
+    /// its `Host` header is synced, as by [`Request::sync_host`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the form of the target isn't allowed for the
+    /// method, as checked by [`Request::check_target_form`], if it is the
+    /// authority-form of a `CONNECT` request, which can't be converted, or
+    /// if the `Host` header is missing or invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::*;
+    /// # use http::uri::Scheme;
+    /// let mut request = Request::get("/a?b")
+    ///     .header(header::HOST, "example.com")
+    ///     .body(())
+    ///     .unwrap();
+    /// request.set_absolute_form(Scheme::HTTPS).unwrap();
+    ///
+    /// assert_eq!(request.uri(), "https://example.com/a?b");
+    /// ```
+    pub fn set_absolute_form(
+        &mut self,
+        scheme: Scheme,
+    ) -> result::Result<(), InvalidRequestTarget> {
+        self.check_target_form()?;
+        match self.head.uri.target_form() {
+            TargetForm::Absolute => self.sync_host(),
+            TargetForm::Authority => Err(InvalidRequestTarget::new(TargetErrorKind::InvalidForm)),
+            TargetForm::Origin | TargetForm::Asterisk => {
+                let authority = self
+                    .host_authority()?
+                    .ok_or_else(|| InvalidRequestTarget::new(TargetErrorKind::HostMissing))?;
+                self.head.uri = uri::absolute_form(&self.head.uri, scheme, authority);
+                Ok(())
+            }
+        }
+    }
+
+    // The authority of the `Host` header, which must be a single host and
+    // optional port.
+    fn host_authority(&self) -> result::Result<Option<Authority>, InvalidRequestTarget> {
+        let mut values = self.head.headers.get_all(header::HOST).iter();
+        let value = match (values.next(), values.next()) {
+            (None, _) => return Ok(None),
+            (Some(value), None) => value,
+            (Some(_), Some(_)) => {
+                return Err(InvalidRequestTarget::new(TargetErrorKind::InvalidHost))
+            }
+        };
+        match Authority::try_from(value.as_bytes()) {
+            Ok(ref authority) if authority.username().is_none() => Ok(Some(authority.clone())),
+            _ => Err(InvalidRequestTarget::new(TargetErrorKind::InvalidHost)),
+        }
+    }
 }
 
 impl<T: Default> Default for Request<T> {
@@ -1061,6 +1265,39 @@ impl Default for Builder {
     }
 }
 
+// ===== impl InvalidRequestTarget =====
+
+impl InvalidRequestTarget {
+    fn new(kind: TargetErrorKind) -> InvalidRequestTarget {
+        InvalidRequestTarget { kind }
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            TargetErrorKind::InvalidForm => "request target form not allowed for method",
+            TargetErrorKind::HostMissing => "missing host header",
+            TargetErrorKind::InvalidHost => "invalid host header",
+            TargetErrorKind::HostMismatch => "host header does not match request target",
+        }
+    }
+}
+
+impl fmt::Debug for InvalidRequestTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidRequestTarget")
+            .field(&self.kind)
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidRequestTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl error::Error for InvalidRequestTarget {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1074,4 +1311,42 @@ mod tests {
         });
         assert_eq!(mapped_request.body(), &123u32);
     }
+
+    #[test]
+    fn converts_target_forms() {
+        let mut request = Request::options("http://example.com").body(()).unwrap();
+        request.set_origin_form().unwrap();
+        assert_eq!(request.uri(), "*");
+        assert_eq!(request.headers()[header::HOST], "example.com");
+        request.set_absolute_form(Scheme::HTTP).unwrap();
+        assert_eq!(request.uri(), "http://example.com");
+
+        let mut request = Request::connect("example.com:443").body(()).unwrap();
+        assert!(request.set_origin_form().is_err());
+        assert!(request.set_absolute_form(Scheme::HTTPS).is_err());
+
+        let mut request = Request::get("/").body(()).unwrap();
+        assert!(request.set_absolute_form(Scheme::HTTP).is_err());
+
+        for &host in &["a.example, b.example", "user@a.example", ""] {
+            let mut request = Request::get("/")
+                .header(header::HOST, host)
+                .body(())
+                .unwrap();
+            assert!(request.set_absolute_form(Scheme::HTTP).is_err(), "{}", host);
+        }
+
+        let mut request = Request::get("http://a.example:8080/")
+            .header(header::HOST, "A.EXAMPLE:8080")
+            .body(())
+            .unwrap();
+        assert!(request.set_origin_form().is_ok());
+
+        let mut request = Request::get("http://a.example:8080/")
+            .header(header::HOST, "a.example")
+            .body(())
+            .unwrap();
+        assert!(request.set_origin_form().is_err());
+        assert_eq!(request.uri(), "http://a.example:8080/");
+    }
 }
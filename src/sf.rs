@@ -0,0 +1,1364 @@
+//! Structured Field Values for HTTP.
+//!
+//! Many recent header fields, such as `Priority`, `Cache-Status` or
+//! `Signature-Input`, are defined as structured fields ([RFC 9651]). Their
+//! value is one of three top-level types:
+//!
+//! - an [`Item`]: a bare item with parameters,
+//! - a [`List`]: a list of items and inner lists, each with parameters,
+//! - a [`Dictionary`]: an ordered map from keys to items and inner lists.
+//!
+//! The definition of each field tells which type it uses, so the caller
+//! picks the type to parse. A field sent on several lines is parsed from all
+//! of them at once.
+//!
+//! # Examples
+//!
+//! ```
+//! use http::sf::{BareItem, Dictionary, ListEntry};
+//! use http::{HeaderMap, HeaderValue};
+//!
+//! let mut headers = HeaderMap::new();
+//! headers.append("priority", HeaderValue::from_static("u=1"));
+//! headers.append("priority", HeaderValue::from_static("i"));
+//!
+//! let priority = Dictionary::parse(headers.get_all("priority")).unwrap();
+//! match priority.get("u") {
+//!     Some(ListEntry::Item(item)) => assert_eq!(item.bare_item, BareItem::Integer(1)),
+//!     _ => unreachable!(),
+//! }
+//! assert!(priority.get("i").is_some());
+//!
+//! assert_eq!(priority.to_header_value().unwrap(), "u=1, i");
+//! ```
+//!
+//! [RFC 9651]: https://www.rfc-editor.org/rfc/rfc9651
+//! [`Item`]: struct.Item.html
+//! [`List`]: struct.List.html
+//! [`Dictionary`]: struct.Dictionary.html
+
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::{slice, vec};
+
+use bytes::Bytes;
+
+use crate::base64;
+use crate::header::HeaderValue;
+use crate::token::is_tchar;
+
+/// A bare item: the value of an item or of a parameter.
+///
+/// The values are not checked when constructed: an error is returned when
+/// serializing a value that is out of range, or a string containing
+/// characters that its type doesn't allow.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BareItem {
+    /// An integer, between -999,999,999,999,999 and 999,999,999,999,999.
+    Integer(i64),
+    /// A decimal number, with at most 12 integer and 3 fractional digits.
+    Decimal(Decimal),
+    /// A string of printable ASCII characters.
+    String(String),
+    /// A token, such as `text/html` or `*`.
+    Token(String),
+    /// A byte sequence, serialized as base64.
+    ByteSeq(Vec<u8>),
+    /// A boolean.
+    Boolean(bool),
+    /// A date, as seconds since the UNIX epoch.
+    Date(i64),
+    /// A string of Unicode characters.
+    DisplayString(String),
+}
+
+/// A decimal number, stored exactly in thousandths.
+///
+/// # Examples
+///
+/// ```
+/// # use http::sf::Decimal;
+/// let d = Decimal::from_thousandths(-1_250);
+/// assert_eq!(d.to_string(), "-1.25");
+/// assert_eq!(f64::from(d), -1.25);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal(i64);
+
+/// An item: a bare item and its parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    /// The value of the item.
+    pub bare_item: BareItem,
+    /// The parameters of the item.
+    pub params: Parameters,
+}
+
+/// An ordered map of parameters, from keys to bare items.
+///
+/// Keys must be lowercase: they start with a lowercase letter or `*`, and
+/// contain only lowercase letters, digits, `_`, `-`, `.` and `*`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Parameters {
+    entries: Vec<(String, BareItem)>,
+}
+
+/// An inner list: a list of items, with parameters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InnerList {
+    /// The items of the list.
+    pub items: Vec<Item>,
+    /// The parameters of the list.
+    pub params: Parameters,
+}
+
+/// A member of a list or a dictionary.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ListEntry {
+    /// An item.
+    Item(Item),
+    /// An inner list.
+    InnerList(InnerList),
+}
+
+/// A list of items and inner lists.
+///
+/// # Examples
+///
+/// ```
+/// # use http::sf::{BareItem, Item, List};
+/// # use http::HeaderValue;
+/// let mut list = List::new();
+/// list.push(Item::new(BareItem::Token("sec-ch-ua-platform".into())));
+/// list.push(Item::new(BareItem::Token("sec-ch-ua-model".into())));
+///
+/// let value = list.to_header_value().unwrap();
+/// assert_eq!(value, "sec-ch-ua-platform, sec-ch-ua-model");
+/// assert_eq!(List::parse(Some(&value)).unwrap(), list);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct List {
+    members: Vec<ListEntry>,
+}
+
+/// An ordered map from keys to items and inner lists.
+///
+/// Keys follow the same rules as the keys of [`Parameters`].
+///
+/// [`Parameters`]: struct.Parameters.html
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dictionary {
+    entries: Vec<(String, ListEntry)>,
+}
+
+/// An iterator over the entries of `Parameters` or a `Dictionary`.
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+    inner: slice::Iter<'a, (String, T)>,
+}
+
+/// An error when parsing or serializing a structured field.
+pub struct InvalidStructuredField {
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    Parse,
+    Serialize,
+}
+
+/// The top-level type of a structured field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FieldType {
+    List,
+    Dictionary,
+}
+
+const MAX_INTEGER: i64 = 999_999_999_999_999;
+
+// ===== impl BareItem =====
+
+impl From<i64> for BareItem {
+    fn from(n: i64) -> BareItem {
+        BareItem::Integer(n)
+    }
+}
+
+impl From<Decimal> for BareItem {
+    fn from(d: Decimal) -> BareItem {
+        BareItem::Decimal(d)
+    }
+}
+
+impl From<bool> for BareItem {
+    fn from(b: bool) -> BareItem {
+        BareItem::Boolean(b)
+    }
+}
+
+impl From<Vec<u8>> for BareItem {
+    fn from(bytes: Vec<u8>) -> BareItem {
+        BareItem::ByteSeq(bytes)
+    }
+}
+
+// ===== impl Decimal =====
+
+impl Decimal {
+    /// Creates a decimal from a number of thousandths.
+    pub fn from_thousandths(thousandths: i64) -> Decimal {
+        Decimal(thousandths)
+    }
+
+    /// Returns the value of the decimal in thousandths.
+    pub fn thousandths(self) -> i64 {
+        self.0
+    }
+}
+
+impl From<Decimal> for f64 {
+    fn from(d: Decimal) -> f64 {
+        d.0 as f64 / 1000.0
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            f.write_char('-')?;
+        }
+        let n = i128::from(self.0).abs();
+        write!(f, "{}.", n / 1000)?;
+        let fraction = format!("{:03}", n % 1000);
+        let fraction = fraction.trim_end_matches('0');
+        f.write_str(if fraction.is_empty() { "0" } else { fraction })
+    }
+}
+
+// ===== impl Item =====
+
+impl Item {
+    /// Creates an item without parameters.
+    pub fn new(bare_item: BareItem) -> Item {
+        Item {
+            bare_item,
+            params: Parameters::new(),
+        }
+    }
+
+    /// Parses an item from the lines of a field.
+    pub fn parse<'a, I>(values: I) -> Result<Item, InvalidStructuredField>
+    where
+        I: IntoIterator<Item = &'a HeaderValue>,
+    {
+        parse(&combine(values), Parser::item)
+    }
+
+    /// Serializes the item as a header value.
+    pub fn to_header_value(&self) -> Result<HeaderValue, InvalidStructuredField> {
+        Ok(to_header_value(self.serialize()?))
+    }
+
+    pub(crate) fn serialize(&self) -> Result<String, InvalidStructuredField> {
+        serialize(|out| self.write(out))
+    }
+
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        self.bare_item.write(out)?;
+        self.params.write(out)
+    }
+}
+
+impl FromStr for Item {
+    type Err = InvalidStructuredField;
+
+    fn from_str(s: &str) -> Result<Item, InvalidStructuredField> {
+        parse(s.as_bytes(), Parser::item)
+    }
+}
+
+impl From<BareItem> for Item {
+    fn from(bare_item: BareItem) -> Item {
+        Item::new(bare_item)
+    }
+}
+
+// ===== impl Parameters =====
+
+impl Parameters {
+    /// Creates empty parameters.
+    pub fn new() -> Parameters {
+        Parameters::default()
+    }
+
+    /// Returns the value of a parameter.
+    pub fn get(&self, key: &str) -> Option<&BareItem> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Sets the value of a parameter.
+    ///
+    /// If the parameter was already present, its value is replaced but it
+    /// keeps its position, and the previous value is returned.
+    pub fn insert<K>(&mut self, key: K, value: BareItem) -> Option<BareItem>
+    where
+        K: Into<String>,
+    {
+        insert(&mut self.entries, key.into(), value)
+    }
+
+    /// Removes a parameter, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<BareItem> {
+        remove(&mut self.entries, key)
+    }
+
+    /// Returns an iterator over the parameters, in order.
+    pub fn iter(&self) -> Iter<'_, BareItem> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        for (key, value) in &self.entries {
+            out.push(';');
+            write_key(out, key)?;
+            if *value != BareItem::Boolean(true) {
+                out.push('=');
+                value.write(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, BareItem)> for Parameters {
+    fn from_iter<I>(iter: I) -> Parameters
+    where
+        I: IntoIterator<Item = (K, BareItem)>,
+    {
+        let mut params = Parameters::new();
+        for (key, value) in iter {
+            params.insert(key, value);
+        }
+        params
+    }
+}
+
+impl<'a> IntoIterator for &'a Parameters {
+    type Item = (&'a str, &'a BareItem);
+    type IntoIter = Iter<'a, BareItem>;
+
+    fn into_iter(self) -> Iter<'a, BareItem> {
+        self.iter()
+    }
+}
+
+// ===== impl InnerList =====
+
+impl InnerList {
+    /// Creates an inner list without parameters.
+    pub fn new(items: Vec<Item>) -> InnerList {
+        InnerList {
+            items,
+            params: Parameters::new(),
+        }
+    }
+
+    pub(crate) fn serialize(&self) -> Result<String, InvalidStructuredField> {
+        serialize(|out| self.write(out))
+    }
+
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        out.push('(');
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+            }
+            item.write(out)?;
+        }
+        out.push(')');
+        self.params.write(out)
+    }
+}
+
+// ===== impl ListEntry =====
+
+impl ListEntry {
+    /// Returns the parameters of the member.
+    pub fn params(&self) -> &Parameters {
+        match *self {
+            ListEntry::Item(ref item) => &item.params,
+            ListEntry::InnerList(ref list) => &list.params,
+        }
+    }
+
+    pub(crate) fn serialize(&self) -> Result<String, InvalidStructuredField> {
+        serialize(|out| self.write(out))
+    }
+
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        match *self {
+            ListEntry::Item(ref item) => item.write(out),
+            ListEntry::InnerList(ref list) => list.write(out),
+        }
+    }
+}
+
+impl From<Item> for ListEntry {
+    fn from(item: Item) -> ListEntry {
+        ListEntry::Item(item)
+    }
+}
+
+impl From<InnerList> for ListEntry {
+    fn from(list: InnerList) -> ListEntry {
+        ListEntry::InnerList(list)
+    }
+}
+
+// ===== impl List =====
+
+impl List {
+    /// Creates an empty list.
+    pub fn new() -> List {
+        List::default()
+    }
+
+    /// Parses a list from the lines of a field.
+    pub fn parse<'a, I>(values: I) -> Result<List, InvalidStructuredField>
+    where
+        I: IntoIterator<Item = &'a HeaderValue>,
+    {
+        parse(&combine(values), Parser::list)
+    }
+
+    /// Appends a member to the list.
+    pub fn push<T>(&mut self, member: T)
+    where
+        T: Into<ListEntry>,
+    {
+        self.members.push(member.into());
+    }
+
+    /// Returns the members of the list.
+    pub fn members(&self) -> &[ListEntry] {
+        &self.members
+    }
+
+    /// Returns an iterator over the members of the list.
+    pub fn iter(&self) -> slice::Iter<'_, ListEntry> {
+        self.members.iter()
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if the list has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Serializes the list as a header value.
+    ///
+    /// An empty list can't be serialized, since the field should be omitted
+    /// instead.
+    pub fn to_header_value(&self) -> Result<HeaderValue, InvalidStructuredField> {
+        if self.is_empty() {
+            return Err(InvalidStructuredField::new(ErrorKind::Serialize));
+        }
+        Ok(to_header_value(self.serialize()?))
+    }
+
+    pub(crate) fn serialize(&self) -> Result<String, InvalidStructuredField> {
+        serialize(|out| self.write(out))
+    }
+
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            member.write(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for List {
+    type Err = InvalidStructuredField;
+
+    fn from_str(s: &str) -> Result<List, InvalidStructuredField> {
+        parse(s.as_bytes(), Parser::list)
+    }
+}
+
+impl<T: Into<ListEntry>> FromIterator<T> for List {
+    fn from_iter<I>(iter: I) -> List
+    where
+        I: IntoIterator<Item = T>,
+    {
+        List {
+            members: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl IntoIterator for List {
+    type Item = ListEntry;
+    type IntoIter = vec::IntoIter<ListEntry>;
+
+    fn into_iter(self) -> vec::IntoIter<ListEntry> {
+        self.members.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a ListEntry;
+    type IntoIter = slice::Iter<'a, ListEntry>;
+
+    fn into_iter(self) -> slice::Iter<'a, ListEntry> {
+        self.iter()
+    }
+}
+
+// ===== impl Dictionary =====
+
+impl Dictionary {
+    /// Creates an empty dictionary.
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    /// Parses a dictionary from the lines of a field.
+    pub fn parse<'a, I>(values: I) -> Result<Dictionary, InvalidStructuredField>
+    where
+        I: IntoIterator<Item = &'a HeaderValue>,
+    {
+        parse(&combine(values), Parser::dictionary)
+    }
+
+    /// Returns the member with the given key.
+    pub fn get(&self, key: &str) -> Option<&ListEntry> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Sets the member with the given key.
+    ///
+    /// If the key was already present, its member is replaced but it keeps
+    /// its position, and the previous member is returned.
+    pub fn insert<K, V>(&mut self, key: K, member: V) -> Option<ListEntry>
+    where
+        K: Into<String>,
+        V: Into<ListEntry>,
+    {
+        insert(&mut self.entries, key.into(), member.into())
+    }
+
+    /// Removes the member with the given key.
+    pub fn remove(&mut self, key: &str) -> Option<ListEntry> {
+        remove(&mut self.entries, key)
+    }
+
+    /// Returns an iterator over the keys and members, in order.
+    pub fn iter(&self) -> Iter<'_, ListEntry> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the dictionary has no members.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Serializes the dictionary as a header value.
+    ///
+    /// An empty dictionary can't be serialized, since the field should be
+    /// omitted instead.
+    pub fn to_header_value(&self) -> Result<HeaderValue, InvalidStructuredField> {
+        if self.is_empty() {
+            return Err(InvalidStructuredField::new(ErrorKind::Serialize));
+        }
+        Ok(to_header_value(self.serialize()?))
+    }
+
+    pub(crate) fn serialize(&self) -> Result<String, InvalidStructuredField> {
+        serialize(|out| self.write(out))
+    }
+
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        for (i, (key, member)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            write_key(out, key)?;
+            match *member {
+                ListEntry::Item(Item {
+                    bare_item: BareItem::Boolean(true),
+                    ref params,
+                }) => params.write(out)?,
+                _ => {
+                    out.push('=');
+                    member.write(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Dictionary {
+    type Err = InvalidStructuredField;
+
+    fn from_str(s: &str) -> Result<Dictionary, InvalidStructuredField> {
+        parse(s.as_bytes(), Parser::dictionary)
+    }
+}
+
+impl<K: Into<String>, V: Into<ListEntry>> FromIterator<(K, V)> for Dictionary {
+    fn from_iter<I>(iter: I) -> Dictionary
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut dict = Dictionary::new();
+        for (key, member) in iter {
+            dict.insert(key, member);
+        }
+        dict
+    }
+}
+
+impl<'a> IntoIterator for &'a Dictionary {
+    type Item = (&'a str, &'a ListEntry);
+    type IntoIter = Iter<'a, ListEntry>;
+
+    fn into_iter(self) -> Iter<'a, ListEntry> {
+        self.iter()
+    }
+}
+
+// ===== impl Iter =====
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a str, &'a T);
+
+    fn next(&mut self) -> Option<(&'a str, &'a T)> {
+        self.inner.next().map(|(k, v)| (&k[..], v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+fn insert<T>(entries: &mut Vec<(String, T)>, key: String, value: T) -> Option<T> {
+    match entries.iter_mut().find(|(k, _)| *k == key) {
+        Some(entry) => Some(std::mem::replace(&mut entry.1, value)),
+        None => {
+            entries.push((key, value));
+            None
+        }
+    }
+}
+
+fn remove<T>(entries: &mut Vec<(String, T)>, key: &str) -> Option<T> {
+    let i = entries.iter().position(|(k, _)| k == key)?;
+    Some(entries.remove(i).1)
+}
+
+// ===== impl InvalidStructuredField =====
+
+impl InvalidStructuredField {
+    fn new(kind: ErrorKind) -> InvalidStructuredField {
+        InvalidStructuredField { kind }
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::Parse => "invalid structured field",
+            ErrorKind::Serialize => "value can't be serialized as a structured field",
+        }
+    }
+}
+
+impl fmt::Debug for InvalidStructuredField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidStructuredField")
+            .field(&self.kind)
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidStructuredField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for InvalidStructuredField {}
+
+// ===== parsing =====
+
+// The internal error of the parser and serializer, converted to an
+// `InvalidStructuredField` of the right kind at the API boundary.
+struct Invalid;
+
+pub(crate) fn parse_dictionary(input: &[u8]) -> Result<Dictionary, InvalidStructuredField> {
+    parse(input, Parser::dictionary)
+}
+
+/// Parses a field and serializes it again in its canonical form.
+pub(crate) fn reserialize(ty: FieldType, input: &[u8]) -> Result<String, InvalidStructuredField> {
+    match ty {
+        FieldType::List => parse(input, Parser::list)?.serialize(),
+        FieldType::Dictionary => parse(input, Parser::dictionary)?.serialize(),
+    }
+}
+
+// Field lines are combined as if they were a single comma-separated line.
+fn combine<'a, I>(values: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a HeaderValue>,
+{
+    let mut combined = Vec::new();
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            combined.extend_from_slice(b", ");
+        }
+        combined.extend_from_slice(value.as_bytes());
+    }
+    combined
+}
+
+fn parse<'a, T, F>(input: &'a [u8], f: F) -> Result<T, InvalidStructuredField>
+where
+    F: FnOnce(&mut Parser<'a>) -> Result<T, Invalid>,
+{
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_sp();
+    let value = f(&mut parser).and_then(|value| {
+        parser.skip_sp();
+        if parser.is_end() {
+            Ok(value)
+        } else {
+            Err(Invalid)
+        }
+    });
+    value.map_err(|_| InvalidStructuredField::new(ErrorKind::Parse))
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_sp(&mut self) {
+        while self.eat(b' ') {}
+    }
+
+    fn skip_ows(&mut self) {
+        while self.eat(b' ') || self.eat(b'\t') {}
+    }
+
+    fn is_end(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    // Parses the separator between list or dictionary members, returning
+    // `false` at the end of the input.
+    fn member_separator(&mut self) -> Result<bool, Invalid> {
+        self.skip_ows();
+        if self.is_end() {
+            return Ok(false);
+        }
+        if !self.eat(b',') {
+            return Err(Invalid);
+        }
+        self.skip_ows();
+        if self.is_end() {
+            // trailing comma
+            return Err(Invalid);
+        }
+        Ok(true)
+    }
+
+    fn list(&mut self) -> Result<List, Invalid> {
+        let mut list = List::new();
+        if self.is_end() {
+            return Ok(list);
+        }
+        loop {
+            list.push(self.item_or_inner_list()?);
+            if !self.member_separator()? {
+                return Ok(list);
+            }
+        }
+    }
+
+    fn dictionary(&mut self) -> Result<Dictionary, Invalid> {
+        let mut dict = Dictionary::new();
+        if self.is_end() {
+            return Ok(dict);
+        }
+        loop {
+            let key = self.key()?;
+            let member = if self.eat(b'=') {
+                self.item_or_inner_list()?
+            } else {
+                ListEntry::Item(Item {
+                    bare_item: BareItem::Boolean(true),
+                    params: self.parameters()?,
+                })
+            };
+            dict.insert(key, member);
+
+            if !self.member_separator()? {
+                return Ok(dict);
+            }
+        }
+    }
+
+    fn item_or_inner_list(&mut self) -> Result<ListEntry, Invalid> {
+        if self.peek() == Some(b'(') {
+            self.inner_list().map(ListEntry::InnerList)
+        } else {
+            self.item().map(ListEntry::Item)
+        }
+    }
+
+    fn inner_list(&mut self) -> Result<InnerList, Invalid> {
+        if !self.eat(b'(') {
+            return Err(Invalid);
+        }
+        let mut items = Vec::new();
+        loop {
+            self.skip_sp();
+            if self.eat(b')') {
+                let params = self.parameters()?;
+                return Ok(InnerList { items, params });
+            }
+            items.push(self.item()?);
+            match self.peek() {
+                Some(b' ') | Some(b')') => {}
+                _ => return Err(Invalid),
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Item, Invalid> {
+        let bare_item = self.bare_item()?;
+        let params = self.parameters()?;
+        Ok(Item { bare_item, params })
+    }
+
+    fn parameters(&mut self) -> Result<Parameters, Invalid> {
+        let mut params = Parameters::new();
+        while self.eat(b';') {
+            self.skip_sp();
+            let key = self.key()?;
+            let value = if self.eat(b'=') {
+                self.bare_item()?
+            } else {
+                BareItem::Boolean(true)
+            };
+            params.insert(key, value);
+        }
+        Ok(params)
+    }
+
+    fn key(&mut self) -> Result<String, Invalid> {
+        let start = self.pos;
+        match self.peek() {
+            Some(b) if b.is_ascii_lowercase() || b == b'*' => {}
+            _ => return Err(Invalid),
+        }
+        while self.peek().map_or(false, is_key_char) {
+            self.pos += 1;
+        }
+        Ok(ascii(&self.input[start..self.pos]))
+    }
+
+    fn bare_item(&mut self) -> Result<BareItem, Invalid> {
+        match self.peek() {
+            Some(b'-') => self.number(),
+            Some(b) if b.is_ascii_digit() => self.number(),
+            Some(b'"') => self.string().map(BareItem::String),
+            Some(b'*') => self.token().map(BareItem::Token),
+            Some(b) if b.is_ascii_alphabetic() => self.token().map(BareItem::Token),
+            Some(b':') => self.byte_seq().map(BareItem::ByteSeq),
+            Some(b'?') => self.boolean().map(BareItem::Boolean),
+            Some(b'@') => self.date().map(BareItem::Date),
+            Some(b'%') => self.display_string().map(BareItem::DisplayString),
+            _ => Err(Invalid),
+        }
+    }
+
+    fn number(&mut self) -> Result<BareItem, Invalid> {
+        let negative = self.eat(b'-');
+        let start = self.pos;
+        let mut dot = None;
+
+        if !self.peek().map_or(false, |b| b.is_ascii_digit()) {
+            return Err(Invalid);
+        }
+        while let Some(b) = self.peek() {
+            if b.is_ascii_digit() {
+                self.pos += 1;
+            } else if b == b'.' && dot.is_none() {
+                if self.pos - start > 12 {
+                    return Err(Invalid);
+                }
+                dot = Some(self.pos);
+                self.pos += 1;
+            } else {
+                break;
+            }
+
+            let len = self.pos - start;
+            if (dot.is_none() && len > 15) || (dot.is_some() && len > 16) {
+                return Err(Invalid);
+            }
+        }
+
+        let digits = &self.input[start..self.pos];
+        let sign = if negative { -1 } else { 1 };
+        match dot {
+            None => Ok(BareItem::Integer(sign * to_i64(digits))),
+            Some(dot) => {
+                let fraction = &digits[dot - start + 1..];
+                if fraction.is_empty() || fraction.len() > 3 {
+                    return Err(Invalid);
+                }
+                let mut thousandths = to_i64(&digits[..dot - start]) * 1000;
+                let mut scale = 100;
+                for &b in fraction {
+                    thousandths += i64::from(b - b'0') * scale;
+                    scale /= 10;
+                }
+                Ok(BareItem::Decimal(Decimal(sign * thousandths)))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Invalid> {
+        if !self.eat(b'"') {
+            return Err(Invalid);
+        }
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(b) if b == b'"' || b == b'\\' => s.push(b as char),
+                        _ => return Err(Invalid),
+                    }
+                }
+                Some(b) if (0x20..0x7f).contains(&b) => s.push(b as char),
+                _ => return Err(Invalid),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn token(&mut self) -> Result<String, Invalid> {
+        let start = self.pos;
+        match self.peek() {
+            Some(b) if b.is_ascii_alphabetic() || b == b'*' => {}
+            _ => return Err(Invalid),
+        }
+        while self.peek().map_or(false, is_token_char) {
+            self.pos += 1;
+        }
+        Ok(ascii(&self.input[start..self.pos]))
+    }
+
+    fn byte_seq(&mut self) -> Result<Vec<u8>, Invalid> {
+        if !self.eat(b':') {
+            return Err(Invalid);
+        }
+        let start = self.pos;
+        while self.peek().map_or(false, |b| b != b':') {
+            self.pos += 1;
+        }
+        let encoded = &self.input[start..self.pos];
+        if !self.eat(b':') {
+            return Err(Invalid);
+        }
+        base64::decode_lenient(encoded).ok_or(Invalid)
+    }
+
+    fn boolean(&mut self) -> Result<bool, Invalid> {
+        if !self.eat(b'?') {
+            return Err(Invalid);
+        }
+        if self.eat(b'1') {
+            Ok(true)
+        } else if self.eat(b'0') {
+            Ok(false)
+        } else {
+            Err(Invalid)
+        }
+    }
+
+    fn date(&mut self) -> Result<i64, Invalid> {
+        if !self.eat(b'@') {
+            return Err(Invalid);
+        }
+        match self.number()? {
+            BareItem::Integer(n) => Ok(n),
+            _ => Err(Invalid),
+        }
+    }
+
+    fn display_string(&mut self) -> Result<String, Invalid> {
+        if !self.eat(b'%') || !self.eat(b'"') {
+            return Err(Invalid);
+        }
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return String::from_utf8(bytes).map_err(|_| Invalid);
+                }
+                Some(b'%') => {
+                    let hex = self.input.get(self.pos + 1..self.pos + 3).ok_or(Invalid)?;
+                    bytes.push(lower_hex(hex[0])? << 4 | lower_hex(hex[1])?);
+                    self.pos += 3;
+                }
+                Some(b) if (0x20..0x7f).contains(&b) => {
+                    bytes.push(b);
+                    self.pos += 1;
+                }
+                _ => return Err(Invalid),
+            }
+        }
+    }
+}
+
+fn is_key_char(b: u8) -> bool {
+    b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'_' | b'-' | b'.' | b'*')
+}
+
+// tchar / ":" / "/"
+fn is_token_char(b: u8) -> bool {
+    is_tchar(b) || b == b':' || b == b'/'
+}
+
+fn lower_hex(b: u8) -> Result<u8, Invalid> {
+    match b {
+        b'0'..=b'9' => Ok(b - b'0'),
+        b'a'..=b'f' => Ok(b - b'a' + 10),
+        _ => Err(Invalid),
+    }
+}
+
+fn to_i64(digits: &[u8]) -> i64 {
+    digits.iter().fold(0, |n, &b| n * 10 + i64::from(b - b'0'))
+}
+
+fn ascii(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+/// Returns `true` if `s` can be serialized as a String.
+pub(crate) fn is_valid_string(s: &str) -> bool {
+    s.bytes().all(|b| (0x20..0x7f).contains(&b))
+}
+
+/// Returns `true` if `s` can be serialized as a key.
+pub(crate) fn is_valid_key(s: &str) -> bool {
+    match s.as_bytes().first() {
+        Some(&b) if b.is_ascii_lowercase() || b == b'*' => s.bytes().all(is_key_char),
+        _ => false,
+    }
+}
+
+fn is_valid_token(s: &str) -> bool {
+    match s.as_bytes().first() {
+        Some(&b) if b.is_ascii_alphabetic() || b == b'*' => s.bytes().all(is_token_char),
+        _ => false,
+    }
+}
+
+// ===== serialization =====
+
+fn serialize<F>(f: F) -> Result<String, InvalidStructuredField>
+where
+    F: FnOnce(&mut String) -> Result<(), Invalid>,
+{
+    let mut out = String::new();
+    f(&mut out).map_err(|_| InvalidStructuredField::new(ErrorKind::Serialize))?;
+    Ok(out)
+}
+
+fn to_header_value(s: String) -> HeaderValue {
+    // Safety: serialized structured fields only contain visible ASCII and
+    // spaces, and never start or end with a space.
+    unsafe { HeaderValue::from_maybe_shared_unchecked(Bytes::from(s)) }
+}
+
+fn write_key(out: &mut String, key: &str) -> Result<(), Invalid> {
+    if !is_valid_key(key) {
+        return Err(Invalid);
+    }
+    out.push_str(key);
+    Ok(())
+}
+
+fn write_integer(out: &mut String, n: i64) -> Result<(), Invalid> {
+    if !(-MAX_INTEGER..=MAX_INTEGER).contains(&n) {
+        return Err(Invalid);
+    }
+    write!(out, "{}", n).map_err(|_| Invalid)
+}
+
+impl BareItem {
+    fn write(&self, out: &mut String) -> Result<(), Invalid> {
+        match *self {
+            BareItem::Integer(n) => write_integer(out, n),
+            BareItem::Decimal(d) => {
+                // At most 12 digits before the decimal point.
+                if i128::from(d.0).abs() >= 1_000_000_000_000_000 {
+                    return Err(Invalid);
+                }
+                write!(out, "{}", d).map_err(|_| Invalid)
+            }
+            BareItem::String(ref s) => {
+                if !is_valid_string(s) {
+                    return Err(Invalid);
+                }
+                out.push('"');
+                for c in s.chars() {
+                    if c == '"' || c == '\\' {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                out.push('"');
+                Ok(())
+            }
+            BareItem::Token(ref s) => {
+                if !is_valid_token(s) {
+                    return Err(Invalid);
+                }
+                out.push_str(s);
+                Ok(())
+            }
+            BareItem::ByteSeq(ref bytes) => {
+                write!(out, ":{}:", base64::encode(bytes)).map_err(|_| Invalid)
+            }
+            BareItem::Boolean(b) => {
+                out.push_str(if b { "?1" } else { "?0" });
+                Ok(())
+            }
+            BareItem::Date(n) => {
+                out.push('@');
+                write_integer(out, n)
+            }
+            BareItem::DisplayString(ref s) => {
+                out.push_str("%\"");
+                for &b in s.as_bytes() {
+                    if b == b'%' || b == b'"' || !(0x20..0x7f).contains(&b) {
+                        write!(out, "%{:02x}", b).map_err(|_| Invalid)?;
+                    } else {
+                        out.push(b as char);
+                    }
+                }
+                out.push('"');
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(ty: FieldType, input: &str) -> String {
+        reserialize(ty, input.as_bytes()).expect(input)
+    }
+
+    fn roundtrip_item(input: &str) -> String {
+        input
+            .parse::<Item>()
+            .expect(input)
+            .serialize()
+            .expect(input)
+    }
+
+    #[test]
+    fn items() {
+        assert_eq!(roundtrip_item("42"), "42");
+        assert_eq!(roundtrip_item("-0"), "0");
+        assert_eq!(roundtrip_item("4.50"), "4.5");
+        assert_eq!(roundtrip_item("-0.25"), "-0.25");
+        assert_eq!(roundtrip_item("1.0"), "1.0");
+        assert_eq!(roundtrip_item("\"a \\\"b\\\\\""), "\"a \\\"b\\\\\"");
+        assert_eq!(roundtrip_item("foo/bar:baz"), "foo/bar:baz");
+        assert_eq!(roundtrip_item(":aGVsbG8=:"), ":aGVsbG8=:");
+        assert_eq!(roundtrip_item(":aGVsbG8:"), ":aGVsbG8=:");
+        assert_eq!(roundtrip_item("?0;a;b=?1;c=?0"), "?0;a;b;c=?0");
+        assert_eq!(roundtrip_item("  1;a=1;a=2  "), "1;a=2");
+        assert_eq!(roundtrip_item("@1659578233"), "@1659578233");
+        assert_eq!(
+            roundtrip_item("%\"f%c3%bc%c3%bc %22%25\""),
+            "%\"f%c3%bc%c3%bc %22%25\""
+        );
+    }
+
+    #[test]
+    fn bare_items() {
+        let item = |s: &str| s.parse::<Item>().unwrap().bare_item;
+        assert_eq!(item("-12"), BareItem::Integer(-12));
+        assert_eq!(item("1.5"), BareItem::Decimal(Decimal(1500)));
+        assert_eq!(item("@-1"), BareItem::Date(-1));
+        assert_eq!(
+            item("%\"f%c3%bc%c3%bc\""),
+            BareItem::DisplayString("f\u{fc}\u{fc}".into())
+        );
+        assert_eq!(item(":iZ==:"), BareItem::ByteSeq(vec![0x89]));
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(roundtrip(FieldType::List, ""), "");
+        assert_eq!(
+            roundtrip(FieldType::List, "a,\tb ,(c  \"d\");x=1, ()"),
+            "a, b, (c \"d\");x=1, ()"
+        );
+    }
+
+    #[test]
+    fn dictionaries() {
+        assert_eq!(
+            roundtrip(FieldType::Dictionary, "a=?0, b, c; foo=bar, a=1"),
+            "a=1, b, c;foo=bar"
+        );
+        assert_eq!(
+            roundtrip(
+                FieldType::Dictionary,
+                "sig=(\"@method\" \"date\");created=1"
+            ),
+            "sig=(\"@method\" \"date\");created=1"
+        );
+    }
+
+    #[test]
+    fn multiple_lines() {
+        let values = [
+            HeaderValue::from_static("a, (b c)"),
+            HeaderValue::from_static("d"),
+        ];
+        assert_eq!(List::parse(&values).unwrap().len(), 3);
+        assert!(Item::parse(&values).is_err());
+        assert!(List::parse(&[]).unwrap().is_empty());
+        assert!(Item::parse(&[]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        let invalid_items = [
+            "",
+            "1 2",
+            "1234567890123456",
+            "1234567890123.0",
+            "1.2345",
+            "1.",
+            "\"unterminated",
+            "\"\\n\"",
+            ":aGVsbG8",
+            ":aGVsb G8=:",
+            "?2",
+            "1;A=1",
+            "@1.5",
+            "%\"%C3%BC\"",
+            "%\"%c3\"",
+            "%\"\u{fc}\"",
+        ];
+        let invalid = [
+            (FieldType::List, "a,"),
+            (FieldType::List, "(a b"),
+            (FieldType::List, "(a)b"),
+            (FieldType::Dictionary, "A=1"),
+            (FieldType::Dictionary, "a=1,,b=2"),
+            (FieldType::Dictionary, "\ta=1"),
+        ];
+        for &input in &invalid_items {
+            assert!(input.parse::<Item>().is_err(), "{:?}", input);
+        }
+        for &(ty, input) in &invalid {
+            assert!(reserialize(ty, input.as_bytes()).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_unserializable() {
+        let invalid = [
+            BareItem::Integer(1_000_000_000_000_000),
+            BareItem::Decimal(Decimal(-1_000_000_000_000_000)),
+            BareItem::String("\u{e9}".into()),
+            BareItem::String("\n".into()),
+            BareItem::Token("1a".into()),
+            BareItem::Token("a b".into()),
+            BareItem::Date(-1_000_000_000_000_000),
+        ];
+        for bare_item in &invalid {
+            assert!(
+                Item::new(bare_item.clone()).to_header_value().is_err(),
+                "{:?}",
+                bare_item
+            );
+        }
+
+        let mut item = Item::new(BareItem::Boolean(true));
+        item.params.insert("A", BareItem::Integer(1));
+        assert!(item.to_header_value().is_err());
+
+        assert!(List::new().to_header_value().is_err());
+        assert!(Dictionary::new().to_header_value().is_err());
+    }
+}
@@ -0,0 +1,487 @@
+use std::fmt::{self, Write};
+
+use super::{ErrorKind, Message, SignatureError};
+use crate::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::sf::{self, BareItem, FieldType, Item, Parameters};
+use crate::uri::Scheme;
+use crate::{base64, Method};
+
+/// A component of a message covered by a signature.
+///
+/// A component is either a header field, identified by its lowercase name,
+/// or a derived component such as `@method`, identified by a name starting
+/// with `@`. Both may have parameters changing how their value is computed.
+///
+/// # Examples
+///
+/// ```
+/// # use http::signature::Component;
+/// # use http::header::CONTENT_TYPE;
+/// assert_eq!(Component::method().to_string(), "\"@method\"");
+/// assert_eq!(Component::query_param("a b").to_string(), "\"@query-param\";name=\"a%20b\"");
+/// assert_eq!(Component::field(CONTENT_TYPE).req().to_string(), "\"content-type\";req");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component {
+    name: String,
+    params: Parameters,
+}
+
+impl Component {
+    /// The `@method` derived component: the method of the request.
+    pub fn method() -> Component {
+        Component::derived("@method")
+    }
+
+    /// The `@target-uri` derived component: the full target URI of the
+    /// request.
+    ///
+    /// The URI of the request must be in absolute form, since the scheme
+    /// can't be known otherwise.
+    pub fn target_uri() -> Component {
+        Component::derived("@target-uri")
+    }
+
+    /// The `@authority` derived component: the authority of the target URI,
+    /// or the `Host` header.
+    pub fn authority() -> Component {
+        Component::derived("@authority")
+    }
+
+    /// The `@scheme` derived component: the scheme of the target URI.
+    pub fn scheme() -> Component {
+        Component::derived("@scheme")
+    }
+
+    /// The `@request-target` derived component: the request target, as it
+    /// appears in the HTTP/1.1 request line.
+    pub fn request_target() -> Component {
+        Component::derived("@request-target")
+    }
+
+    /// The `@path` derived component: the absolute path of the target URI.
+    pub fn path() -> Component {
+        Component::derived("@path")
+    }
+
+    /// The `@query` derived component: the query of the target URI,
+    /// including the leading `?`.
+    pub fn query() -> Component {
+        Component::derived("@query")
+    }
+
+    /// The `@query-param` derived component: the value of a single query
+    /// parameter.
+    ///
+    /// `name` is the decoded name of the parameter.
+    pub fn query_param(name: &str) -> Component {
+        let mut component = Component::derived("@query-param");
+        component
+            .params
+            .insert("name", BareItem::String(encode(name.as_bytes())));
+        component
+    }
+
+    /// The `@status` derived component: the status code of the response.
+    pub fn status() -> Component {
+        Component::derived("@status")
+    }
+
+    /// A header field component.
+    ///
+    /// The value of all the header lines with this name are combined.
+    pub fn field(name: HeaderName) -> Component {
+        Component {
+            name: name.as_str().to_string(),
+            params: Parameters::new(),
+        }
+    }
+
+    /// Adds the `sf` parameter: the field value is serialized again as a
+    /// structured field.
+    ///
+    /// Only fields known to be structured fields can use this parameter.
+    pub fn sf(self) -> Component {
+        self.with_flag("sf")
+    }
+
+    /// Adds the `key` parameter: the field is parsed as a structured field
+    /// dictionary, and the value is the member with this key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not a valid dictionary key.
+    pub fn key(mut self, key: &str) -> Component {
+        assert!(sf::is_valid_key(key), "invalid dictionary key");
+        self.params.insert("key", BareItem::String(key.to_string()));
+        self
+    }
+
+    /// Adds the `bs` parameter: each field line is wrapped as a byte
+    /// sequence. This allows signing fields containing non-ASCII bytes.
+    pub fn bs(self) -> Component {
+        self.with_flag("bs")
+    }
+
+    /// Adds the `req` parameter: when signing a response, the component is
+    /// taken from the request that triggered the response.
+    pub fn req(self) -> Component {
+        self.with_flag("req")
+    }
+
+    /// Returns the name of the component.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // ===== internal =====
+
+    fn derived(name: &str) -> Component {
+        Component {
+            name: name.to_string(),
+            params: Parameters::new(),
+        }
+    }
+
+    fn with_flag(mut self, flag: &str) -> Component {
+        self.params.insert(flag, BareItem::Boolean(true));
+        self
+    }
+
+    pub(super) fn from_item(item: &Item) -> Result<Component, SignatureError> {
+        match item.bare_item {
+            BareItem::String(ref name) => Ok(Component {
+                name: name.clone(),
+                params: item.params.clone(),
+            }),
+            _ => Err(SignatureError::new(ErrorKind::InvalidInput)),
+        }
+    }
+
+    pub(super) fn to_item(&self) -> Item {
+        Item {
+            bare_item: BareItem::String(self.name.clone()),
+            params: self.params.clone(),
+        }
+    }
+
+    fn flag(&self, flag: &str) -> bool {
+        self.params.get(flag) == Some(&BareItem::Boolean(true))
+    }
+
+    fn string_param(&self, name: &str) -> Option<&str> {
+        match self.params.get(name) {
+            Some(BareItem::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), SignatureError> {
+        let invalid = || SignatureError::new(ErrorKind::InvalidComponent);
+
+        if !sf::is_valid_string(&self.name) {
+            return Err(invalid());
+        }
+
+        for (key, value) in &self.params {
+            let valid = match key {
+                "sf" | "bs" | "req" => *value == BareItem::Boolean(true),
+                "key" | "name" => match *value {
+                    BareItem::String(ref s) => sf::is_valid_string(s),
+                    _ => false,
+                },
+                "tr" => return Err(SignatureError::new(ErrorKind::Unsupported)),
+                _ => false,
+            };
+            if !valid {
+                return Err(invalid());
+            }
+        }
+
+        let is_field = !self.name.starts_with('@');
+        let has_field_params = ["sf", "key", "bs"]
+            .iter()
+            .any(|&p| self.params.get(p).is_some());
+        if !is_field && has_field_params {
+            return Err(invalid());
+        }
+        if self.flag("bs") && (self.flag("sf") || self.params.get("key").is_some()) {
+            return Err(invalid());
+        }
+        if (self.name == "@query-param") != self.params.get("name").is_some() {
+            return Err(invalid());
+        }
+
+        Ok(())
+    }
+
+    /// Computes the value of this component in a message.
+    pub(super) fn value(&self, message: &Message<'_>) -> Result<String, SignatureError> {
+        self.check()?;
+
+        if !self.name.starts_with('@') {
+            let headers = if self.flag("req") {
+                &message.related_request()?.headers
+            } else {
+                message.headers()
+            };
+            return self.field_value(headers);
+        }
+
+        if self.name == "@status" {
+            if self.flag("req") {
+                return Err(SignatureError::new(ErrorKind::InvalidComponent));
+            }
+            let response = message
+                .response
+                .ok_or_else(|| SignatureError::new(ErrorKind::MissingComponent))?;
+            return Ok(response.status.as_str().to_string());
+        }
+
+        // Derived components of a response are all taken from the request.
+        let request = match (message.response.is_some(), self.flag("req")) {
+            (false, false) => message.request.expect("request message"),
+            (true, true) => message.related_request()?,
+            _ => return Err(SignatureError::new(ErrorKind::InvalidComponent)),
+        };
+        let uri = &request.uri;
+        let missing = || SignatureError::new(ErrorKind::MissingComponent);
+
+        match &self.name[..] {
+            "@method" => Ok(request.method.as_str().to_string()),
+            "@target-uri" => {
+                if uri.scheme().is_none() {
+                    return Err(missing());
+                }
+                Ok(uri.to_string())
+            }
+            "@authority" => {
+                let authority = match uri.authority() {
+                    Some(authority) => authority.as_str(),
+                    None => request
+                        .headers
+                        .get(crate::header::HOST)
+                        .and_then(|host| host.to_str().ok())
+                        .ok_or_else(missing)?,
+                };
+                let mut authority = authority.to_ascii_lowercase();
+                if let Some(port) = uri.scheme().and_then(Scheme::default_port) {
+                    let port = format!(":{}", port);
+                    if authority.ends_with(&port) {
+                        let len = authority.len() - port.len();
+                        authority.truncate(len);
+                    }
+                }
+                Ok(authority)
+            }
+            "@scheme" => uri
+                .scheme_str()
+                .map(str::to_ascii_lowercase)
+                .ok_or_else(missing),
+            "@request-target" => {
+                if request.method == Method::CONNECT {
+                    uri.authority()
+                        .map(|a| a.as_str().to_string())
+                        .ok_or_else(missing)
+                } else {
+                    Ok(uri
+                        .path_and_query()
+                        .map(|pq| pq.as_str())
+                        .unwrap_or("/")
+                        .to_string())
+                }
+            }
+            "@path" => Ok(match uri.path() {
+                "" => "/".to_string(),
+                path => path.to_string(),
+            }),
+            "@query" => Ok(format!("?{}", uri.query().unwrap_or(""))),
+            "@query-param" => {
+                let name = self.string_param("name").expect("checked");
+                let mut values = uri
+                    .query()
+                    .unwrap_or("")
+                    .split('&')
+                    .filter(|pair| !pair.is_empty())
+                    .filter_map(|pair| {
+                        let (n, v) = match pair.find('=') {
+                            Some(i) => (&pair[..i], &pair[i + 1..]),
+                            None => (pair, ""),
+                        };
+                        if encode(&form_decode(n)) == name {
+                            Some(encode(&form_decode(v)))
+                        } else {
+                            None
+                        }
+                    });
+
+                let value = values.next().ok_or_else(missing)?;
+                // A parameter occurring several times must not be signed.
+                if values.next().is_some() {
+                    return Err(SignatureError::new(ErrorKind::InvalidComponent));
+                }
+                Ok(value)
+            }
+            _ => Err(SignatureError::new(ErrorKind::InvalidComponent)),
+        }
+    }
+
+    fn field_value(&self, headers: &HeaderMap<HeaderValue>) -> Result<String, SignatureError> {
+        let name = HeaderName::from_bytes(self.name.as_bytes())
+            .ok()
+            .filter(|name| name.as_str() == self.name)
+            .ok_or_else(|| SignatureError::new(ErrorKind::InvalidComponent))?;
+
+        let lines: Vec<&[u8]> = headers
+            .get_all(&name)
+            .iter()
+            .map(|value| trim(value.as_bytes()))
+            .collect();
+        if lines.is_empty() {
+            return Err(SignatureError::new(ErrorKind::MissingComponent));
+        }
+
+        if self.flag("bs") {
+            let mut value = String::new();
+            for (i, line) in lines.iter().enumerate() {
+                if i > 0 {
+                    value.push_str(", ");
+                }
+                write!(value, ":{}:", base64::encode(line)).unwrap();
+            }
+            return Ok(value);
+        }
+
+        let combined = lines.join(&b", "[..]);
+        let invalid_field = || SignatureError::new(ErrorKind::InvalidField);
+
+        if let Some(key) = self.string_param("key") {
+            let dict = sf::parse_dictionary(&combined).map_err(|_| invalid_field())?;
+            let member = dict
+                .get(key)
+                .ok_or_else(|| SignatureError::new(ErrorKind::MissingComponent))?;
+            return member.serialize().map_err(|_| invalid_field());
+        }
+
+        if self.flag("sf") {
+            let ty = field_type(&self.name)
+                .ok_or_else(|| SignatureError::new(ErrorKind::Unsupported))?;
+            return sf::reserialize(ty, &combined).map_err(|_| invalid_field());
+        }
+
+        if !combined
+            .iter()
+            .all(|&b| b == b'\t' || (0x20..0x7f).contains(&b))
+        {
+            return Err(invalid_field());
+        }
+        Ok(combined.iter().map(|&b| b as char).collect())
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Components are checked when they are built or parsed, so they can
+        // always be serialized.
+        let item = self.to_item().serialize().map_err(|_| fmt::Error)?;
+        f.write_str(&item)
+    }
+}
+
+// The types of the registered fields that are structured fields.
+fn field_type(name: &str) -> Option<FieldType> {
+    Some(match name {
+        "accept-ch" | "cache-status" | "proxy-status" => FieldType::List,
+        "accept-signature"
+        | "cdn-cache-control"
+        | "content-digest"
+        | "priority"
+        | "repr-digest"
+        | "signature"
+        | "signature-input"
+        | "want-content-digest"
+        | "want-repr-digest" => FieldType::Dictionary,
+        _ => return None,
+    })
+}
+
+fn trim(mut s: &[u8]) -> &[u8] {
+    while let Some((&b, rest)) = s.split_first() {
+        if b != b' ' && b != b'\t' {
+            break;
+        }
+        s = rest;
+    }
+    while let Some((&b, rest)) = s.split_last() {
+        if b != b' ' && b != b'\t' {
+            break;
+        }
+        s = rest;
+    }
+    s
+}
+
+// Decodes an `application/x-www-form-urlencoded` name or value.
+fn form_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match bytes.get(i + 1..i + 3).and_then(|h| hex_pair(h[0], h[1])) {
+                Some(b) => {
+                    out.push(b);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    out
+}
+
+fn hex_pair(hi: u8, lo: u8) -> Option<u8> {
+    let hi = (hi as char).to_digit(16)?;
+    let lo = (lo as char).to_digit(16)?;
+    Some((hi * 16 + lo) as u8)
+}
+
+// Percent-encodes everything but the characters left unencoded by the
+// `application/x-www-form-urlencoded` serializer, encoding spaces as `%20`.
+fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || matches!(b, b'*' | b'-' | b'.' | b'_') {
+            s.push(b as char);
+        } else {
+            write!(s, "%{:02X}", b).unwrap();
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn form_encoding() {
+        assert_eq!(
+            encode(&form_decode("this%20is%20a%20big%0Avalue")),
+            "this%20is%20a%20big%0Avalue"
+        );
+        assert_eq!(
+            encode(&form_decode("with+plus+whitespace")),
+            "with%20plus%20whitespace"
+        );
+        assert_eq!(
+            encode(&form_decode("fa%C3%A7ade%22%3A%20")),
+            "fa%C3%A7ade%22%3A%20"
+        );
+        assert_eq!(encode(&form_decode("%zz%4")), "%25zz%254");
+        assert_eq!(encode("fa\u{e7}ade\": ".as_bytes()), "fa%C3%A7ade%22%3A%20");
+    }
+}
@@ -0,0 +1,997 @@
+//! HTTP Message Signatures.
+//!
+//! This module implements [RFC 9421]: signing selected [`Component`]s of a
+//! request or response, and verifying such signatures. The signed
+//! components and metadata are described by [`SignatureParams`] and sent in
+//! the `Signature-Input` header, while the signatures themselves are sent in
+//! the `Signature` header. Both headers are dictionaries keyed by a label, so
+//! a message may carry several signatures.
+//!
+//! The cryptography is left to implementations of the [`Signer`] and
+//! [`Verifier`] traits. Only [`HmacSha256`] is provided.
+//!
+//! # Examples
+//!
+//! ```
+//! use http::header::{CONTENT_TYPE, DATE};
+//! use http::signature::{self, Component, HmacSha256, Message, SignatureParams};
+//! use http::Request;
+//!
+//! let key = HmacSha256::new(b"a shared secret");
+//!
+//! let mut request = Request::post("https://example.com/hook")
+//!     .header(DATE, "Tue, 20 Apr 2021 02:07:55 GMT")
+//!     .header(CONTENT_TYPE, "application/json")
+//!     .body(())
+//!     .unwrap();
+//!
+//! // The sender signs the request...
+//! let mut params = SignatureParams::new(vec![
+//!     Component::method(),
+//!     Component::authority(),
+//!     Component::field(CONTENT_TYPE),
+//! ]);
+//! params.set_created(1618884473);
+//! params.set_keyid("webhooks");
+//!
+//! let (parts, body) = request.into_parts();
+//! let (input, sig) = signature::sign(&Message::request(&parts), "sig1", &params, &key).unwrap();
+//! let mut request = Request::from_parts(parts, body);
+//! request.headers_mut().append("signature-input", input);
+//! request.headers_mut().append("signature", sig);
+//!
+//! // ...and the receiver verifies it.
+//! let (parts, _) = request.into_parts();
+//! let verified = signature::verify(&Message::request(&parts), "sig1", &key).unwrap();
+//! assert_eq!(verified.keyid(), Some("webhooks"));
+//! ```
+//!
+//! [RFC 9421]: https://www.rfc-editor.org/rfc/rfc9421
+//! [`Component`]: struct.Component.html
+//! [`SignatureParams`]: struct.SignatureParams.html
+//! [`Signer`]: trait.Signer.html
+//! [`Verifier`]: trait.Verifier.html
+//! [`HmacSha256`]: struct.HmacSha256.html
+
+use std::error::Error;
+use std::fmt;
+
+use bytes::Bytes;
+
+use crate::header::{GetAll, HeaderMap, HeaderValue};
+use crate::sf::{self, BareItem, Dictionary, InnerList, ListEntry, Parameters};
+use crate::{base64, request, response, sha256};
+
+pub use self::component::Component;
+
+mod component;
+
+const SIGNATURE_INPUT: &str = "signature-input";
+const SIGNATURE: &str = "signature";
+
+/// The components and metadata covered by a signature.
+///
+/// This is the value of a member of the `Signature-Input` header.
+///
+/// # Examples
+///
+/// ```
+/// # use http::signature::{Component, SignatureParams};
+/// let mut params = SignatureParams::new(vec![Component::method(), Component::path()]);
+/// params.set_created(1618884473);
+/// params.set_keyid("test-key");
+///
+/// assert_eq!(
+///     params.to_string(),
+///     "(\"@method\" \"@path\");created=1618884473;keyid=\"test-key\""
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureParams {
+    components: Vec<Component>,
+    params: Parameters,
+}
+
+/// A message to sign or verify.
+///
+/// Since signatures of responses may cover components of the request that
+/// triggered them, a response message can refer to a request.
+#[derive(Clone, Copy, Debug)]
+pub struct Message<'a> {
+    request: Option<&'a request::Parts>,
+    response: Option<&'a response::Parts>,
+}
+
+/// Computes signatures over signature bases.
+pub trait Signer {
+    /// Signs a signature base.
+    fn sign(&self, base: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+}
+
+/// Verifies signatures over signature bases.
+pub trait Verifier {
+    /// Returns `true` if `signature` is a valid signature of `base`.
+    fn verify(&self, base: &[u8], signature: &[u8]) -> bool;
+}
+
+/// A `Signer` and `Verifier` using HMAC-SHA256 with a shared secret.
+#[derive(Clone)]
+pub struct HmacSha256 {
+    key: Vec<u8>,
+}
+
+/// An error when creating or verifying a signature.
+pub struct SignatureError {
+    kind: ErrorKind,
+    source: Option<Box<dyn Error + Send + Sync>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    InvalidLabel,
+    InvalidComponent,
+    DuplicateComponent,
+    MissingComponent,
+    MissingRequest,
+    InvalidField,
+    Unsupported,
+    InvalidInput,
+    MissingSignature,
+    Signer,
+    VerificationFailed,
+}
+
+// ===== impl SignatureParams =====
+
+impl SignatureParams {
+    /// Creates signature parameters covering the given components, without
+    /// any metadata.
+    pub fn new<I>(components: I) -> SignatureParams
+    where
+        I: IntoIterator<Item = Component>,
+    {
+        SignatureParams {
+            components: components.into_iter().collect(),
+            params: Parameters::new(),
+        }
+    }
+
+    /// Returns the covered components.
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// Sets the creation time, as a UNIX timestamp.
+    pub fn set_created(&mut self, created: u64) {
+        self.set_integer("created", created);
+    }
+
+    /// Sets the expiration time, as a UNIX timestamp.
+    pub fn set_expires(&mut self, expires: u64) {
+        self.set_integer("expires", expires);
+    }
+
+    /// Sets a nonce, to detect replayed messages.
+    pub fn set_nonce(&mut self, nonce: &str) -> Result<(), SignatureError> {
+        self.set_string("nonce", nonce)
+    }
+
+    /// Sets the signature algorithm.
+    pub fn set_alg(&mut self, alg: &str) -> Result<(), SignatureError> {
+        self.set_string("alg", alg)
+    }
+
+    /// Sets the identifier of the key.
+    pub fn set_keyid(&mut self, keyid: &str) -> Result<(), SignatureError> {
+        self.set_string("keyid", keyid)
+    }
+
+    /// Sets an application-specific tag.
+    pub fn set_tag(&mut self, tag: &str) -> Result<(), SignatureError> {
+        self.set_string("tag", tag)
+    }
+
+    /// Returns the creation time.
+    pub fn created(&self) -> Option<u64> {
+        self.integer("created")
+    }
+
+    /// Returns the expiration time.
+    pub fn expires(&self) -> Option<u64> {
+        self.integer("expires")
+    }
+
+    /// Returns the nonce.
+    pub fn nonce(&self) -> Option<&str> {
+        self.string("nonce")
+    }
+
+    /// Returns the signature algorithm.
+    pub fn alg(&self) -> Option<&str> {
+        self.string("alg")
+    }
+
+    /// Returns the identifier of the key.
+    pub fn keyid(&self) -> Option<&str> {
+        self.string("keyid")
+    }
+
+    /// Returns the application-specific tag.
+    pub fn tag(&self) -> Option<&str> {
+        self.string("tag")
+    }
+
+    fn set_integer(&mut self, name: &str, value: u64) {
+        // Structured field integers have at most 15 digits.
+        assert!(value <= 999_999_999_999_999, "timestamp out of range");
+        self.params.insert(name, BareItem::Integer(value as i64));
+    }
+
+    fn set_string(&mut self, name: &str, value: &str) -> Result<(), SignatureError> {
+        if !sf::is_valid_string(value) {
+            return Err(SignatureError::new(ErrorKind::InvalidInput));
+        }
+        self.params
+            .insert(name, BareItem::String(value.to_string()));
+        Ok(())
+    }
+
+    fn integer(&self, name: &str) -> Option<u64> {
+        match self.params.get(name) {
+            Some(&BareItem::Integer(n)) if n >= 0 => Some(n as u64),
+            _ => None,
+        }
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        match self.params.get(name) {
+            Some(BareItem::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn from_entry(entry: &ListEntry) -> Result<SignatureParams, SignatureError> {
+        let invalid = || SignatureError::new(ErrorKind::InvalidInput);
+
+        let list = match *entry {
+            ListEntry::InnerList(ref list) => list,
+            ListEntry::Item(_) => return Err(invalid()),
+        };
+
+        for (name, value) in &list.params {
+            let valid = match name {
+                "created" | "expires" => matches!(*value, BareItem::Integer(n) if n >= 0),
+                "nonce" | "alg" | "keyid" | "tag" => matches!(*value, BareItem::String(_)),
+                _ => true,
+            };
+            if !valid {
+                return Err(invalid());
+            }
+        }
+
+        Ok(SignatureParams {
+            components: list
+                .items
+                .iter()
+                .map(Component::from_item)
+                .collect::<Result<_, _>>()?,
+            params: list.params.clone(),
+        })
+    }
+
+    fn to_inner_list(&self) -> InnerList {
+        InnerList {
+            items: self.components.iter().map(Component::to_item).collect(),
+            params: self.params.clone(),
+        }
+    }
+}
+
+impl fmt::Display for SignatureParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = self.to_inner_list().serialize().map_err(|_| fmt::Error)?;
+        f.write_str(&list)
+    }
+}
+
+// ===== impl Message =====
+
+impl<'a> Message<'a> {
+    /// A request message.
+    pub fn request(request: &'a request::Parts) -> Message<'a> {
+        Message {
+            request: Some(request),
+            response: None,
+        }
+    }
+
+    /// A response message, without a related request.
+    pub fn response(response: &'a response::Parts) -> Message<'a> {
+        Message {
+            request: None,
+            response: Some(response),
+        }
+    }
+
+    /// A response message, and the request that triggered it.
+    ///
+    /// Components with the `req` parameter are taken from the request.
+    pub fn response_to(response: &'a response::Parts, request: &'a request::Parts) -> Message<'a> {
+        Message {
+            request: Some(request),
+            response: Some(response),
+        }
+    }
+
+    fn headers(&self) -> &'a HeaderMap<HeaderValue> {
+        match (self.response, self.request) {
+            (Some(response), _) => &response.headers,
+            (None, Some(request)) => &request.headers,
+            (None, None) => unreachable!("message without request nor response"),
+        }
+    }
+
+    fn related_request(&self) -> Result<&'a request::Parts, SignatureError> {
+        if self.response.is_none() {
+            // `req` is only meaningful on responses
+            return Err(SignatureError::new(ErrorKind::InvalidComponent));
+        }
+        self.request
+            .ok_or_else(|| SignatureError::new(ErrorKind::MissingRequest))
+    }
+}
+
+// ===== signing and verifying =====
+
+/// Computes the signature base of a message.
+///
+/// # Examples
+///
+/// ```
+/// # use http::signature::{signature_base, Component, Message, SignatureParams};
+/// # use http::Request;
+/// let request = Request::get("https://example.com/foo?param=Value&Pet=dog")
+///     .body(())
+///     .unwrap();
+/// let (parts, _) = request.into_parts();
+///
+/// let mut params = SignatureParams::new(vec![
+///     Component::method(),
+///     Component::query_param("Pet"),
+/// ]);
+/// params.set_created(1618884473);
+///
+/// assert_eq!(
+///     signature_base(&Message::request(&parts), &params).unwrap(),
+///     "\"@method\": GET\n\
+///      \"@query-param\";name=\"Pet\": dog\n\
+///      \"@signature-params\": (\"@method\" \"@query-param\";name=\"Pet\");created=1618884473"
+/// );
+/// ```
+pub fn signature_base(
+    message: &Message<'_>,
+    params: &SignatureParams,
+) -> Result<String, SignatureError> {
+    let mut base = String::new();
+
+    for (i, component) in params.components.iter().enumerate() {
+        if params.components[..i].contains(component) {
+            return Err(SignatureError::new(ErrorKind::DuplicateComponent));
+        }
+
+        let value = component.value(message)?;
+        base.push_str(&component.to_string());
+        base.push_str(": ");
+        base.push_str(&value);
+        base.push('\n');
+    }
+
+    base.push_str("\"@signature-params\": ");
+    base.push_str(&params.to_string());
+
+    Ok(base)
+}
+
+/// Signs a message.
+///
+/// Returns the values of the `Signature-Input` and `Signature` headers to
+/// add to the message.
+///
+/// # Errors
+///
+/// Returns an error if the label is not a valid dictionary key, if a
+/// component can't be computed, or if the signer fails.
+pub fn sign<S>(
+    message: &Message<'_>,
+    label: &str,
+    params: &SignatureParams,
+    signer: &S,
+) -> Result<(HeaderValue, HeaderValue), SignatureError>
+where
+    S: Signer + ?Sized,
+{
+    if !sf::is_valid_key(label) {
+        return Err(SignatureError::new(ErrorKind::InvalidLabel));
+    }
+
+    let base = signature_base(message, params)?;
+    let signature = signer
+        .sign(base.as_bytes())
+        .map_err(|e| SignatureError::with_source(ErrorKind::Signer, e))?;
+
+    let input = format!("{}={}", label, params);
+    let signature = format!("{}=:{}:", label, base64::encode(&signature));
+
+    // Safety: structured fields are serialized as visible ASCII.
+    unsafe {
+        Ok((
+            HeaderValue::from_maybe_shared_unchecked(Bytes::from(input)),
+            HeaderValue::from_maybe_shared_unchecked(Bytes::from(signature)),
+        ))
+    }
+}
+
+/// Verifies the signature with the given label of a message.
+///
+/// On success, the parameters of the signature are returned. Callers must
+/// check that they cover all the components they expect, and may check the
+/// `created` and `expires` times.
+///
+/// # Errors
+///
+/// Returns an error if the message has no signature with this label, if the
+/// signature headers are malformed, or if the signature is invalid.
+pub fn verify<V>(
+    message: &Message<'_>,
+    label: &str,
+    verifier: &V,
+) -> Result<SignatureParams, SignatureError>
+where
+    V: Verifier + ?Sized,
+{
+    let headers = message.headers();
+    let missing = || SignatureError::new(ErrorKind::MissingSignature);
+    let invalid = || SignatureError::new(ErrorKind::InvalidInput);
+
+    let inputs = parse_dictionary(headers.get_all(SIGNATURE_INPUT))?;
+    let params = SignatureParams::from_entry(inputs.get(label).ok_or_else(missing)?)?;
+
+    let signatures = parse_dictionary(headers.get_all(SIGNATURE))?;
+    let signature = match signatures.get(label).ok_or_else(missing)? {
+        ListEntry::Item(sf::Item {
+            bare_item: BareItem::ByteSeq(ref bytes),
+            ..
+        }) => bytes,
+        _ => return Err(invalid()),
+    };
+
+    let base = signature_base(message, &params)?;
+    if verifier.verify(base.as_bytes(), signature) {
+        Ok(params)
+    } else {
+        Err(SignatureError::new(ErrorKind::VerificationFailed))
+    }
+}
+
+/// Parses the `Signature-Input` headers of a message.
+///
+/// This can be used to select the signature to verify, and the key to use,
+/// before calling [`verify`].
+///
+/// [`verify`]: fn.verify.html
+pub fn inputs(
+    headers: &HeaderMap<HeaderValue>,
+) -> Result<Vec<(String, SignatureParams)>, SignatureError> {
+    parse_dictionary(headers.get_all(SIGNATURE_INPUT))?
+        .iter()
+        .map(|(label, entry)| Ok((label.to_string(), SignatureParams::from_entry(entry)?)))
+        .collect()
+}
+
+fn parse_dictionary(values: GetAll<'_, HeaderValue>) -> Result<Dictionary, SignatureError> {
+    Dictionary::parse(values).map_err(|_| SignatureError::new(ErrorKind::InvalidInput))
+}
+
+// ===== impl HmacSha256 =====
+
+impl HmacSha256 {
+    /// Creates a `HmacSha256` with a shared secret.
+    pub fn new(key: &[u8]) -> HmacSha256 {
+        HmacSha256 { key: key.to_vec() }
+    }
+}
+
+impl Signer for HmacSha256 {
+    fn sign(&self, base: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        Ok(sha256::hmac(&self.key, base).to_vec())
+    }
+}
+
+impl Verifier for HmacSha256 {
+    fn verify(&self, base: &[u8], signature: &[u8]) -> bool {
+        let expected = sha256::hmac(&self.key, base);
+        expected.len() == signature.len()
+            && expected
+                .iter()
+                .zip(signature)
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+impl fmt::Debug for HmacSha256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacSha256").finish()
+    }
+}
+
+// ===== impl SignatureError =====
+
+impl SignatureError {
+    fn new(kind: ErrorKind) -> SignatureError {
+        SignatureError { kind, source: None }
+    }
+
+    fn with_source(kind: ErrorKind, source: Box<dyn Error + Send + Sync>) -> SignatureError {
+        SignatureError {
+            kind,
+            source: Some(source),
+        }
+    }
+
+    /// Returns `true` if the signature didn't match the message.
+    pub fn is_verification_failed(&self) -> bool {
+        self.kind == ErrorKind::VerificationFailed
+    }
+
+    /// Returns `true` if the message has no signature with the requested
+    /// label.
+    pub fn is_missing_signature(&self) -> bool {
+        self.kind == ErrorKind::MissingSignature
+    }
+
+    /// Returns `true` if a covered component is missing from the message.
+    pub fn is_missing_component(&self) -> bool {
+        self.kind == ErrorKind::MissingComponent
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::InvalidLabel => "invalid signature label",
+            ErrorKind::InvalidComponent => "invalid component identifier",
+            ErrorKind::DuplicateComponent => "component covered more than once",
+            ErrorKind::MissingComponent => "covered component missing from the message",
+            ErrorKind::MissingRequest => "component requires the related request",
+            ErrorKind::InvalidField => "field value can't be signed",
+            ErrorKind::Unsupported => "unsupported component parameter",
+            ErrorKind::InvalidInput => "invalid signature input",
+            ErrorKind::MissingSignature => "no signature with this label",
+            ErrorKind::Signer => "signer failed",
+            ErrorKind::VerificationFailed => "signature verification failed",
+        }
+    }
+}
+
+impl fmt::Debug for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_tuple("SignatureError");
+        f.field(&self.kind);
+        if let Some(ref source) = self.source {
+            f.field(source);
+        }
+        f.finish()
+    }
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.source {
+            Some(ref source) => write!(f, "{}: {}", self.s(), source),
+            None => self.s().fmt(f),
+        }
+    }
+}
+
+impl Error for SignatureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_ref().map(|e| &**e as _)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::{HeaderName, CONTENT_TYPE, DATE};
+    use crate::{Request, Response};
+
+    // The test request and response of RFC 9421, appendix B.2.
+    fn test_request() -> request::Parts {
+        let (parts, _) = Request::post("https://example.com/foo?param=Value&Pet=dog")
+            .header("host", "example.com")
+            .header("date", "Tue, 20 Apr 2021 02:07:55 GMT")
+            .header("content-type", "application/json")
+            .header(
+                "content-digest",
+                "sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:",
+            )
+            .header("content-length", "18")
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts
+    }
+
+    fn test_response() -> response::Parts {
+        let (parts, _) = Response::builder()
+            .status(200)
+            .header("date", "Tue, 20 Apr 2021 02:07:56 GMT")
+            .header("content-type", "application/json")
+            .header(
+                "content-digest",
+                "sha-512=:mEWXIS7MaLRuGgxOBdODa3xqM1XdEvxoYhvlCFJ41QJgJc4GTsPp29l5oGX69wWdXymyU0rjJuahq4l5aGgfLQ==:",
+            )
+            .header("content-length", "23")
+            .body(())
+            .unwrap()
+            .into_parts();
+        parts
+    }
+
+    fn test_shared_secret() -> HmacSha256 {
+        let key = base64::decode(
+            b"uzvJfB4u3N0Jy4T7NZ75MDVcr8zSTInedJtkgcu46YW4XByzNJjxBdtjUkdJPBtbmHhIDi6pcl8jsasjlTMtDQ==",
+        )
+        .unwrap();
+        HmacSha256::new(&key)
+    }
+
+    fn field(name: &'static str) -> Component {
+        Component::field(HeaderName::from_static(name))
+    }
+
+    fn base(message: &Message<'_>, components: Vec<Component>) -> Result<String, SignatureError> {
+        signature_base(message, &SignatureParams::new(components))
+    }
+
+    // RFC 9421, appendix B.2.5
+    #[test]
+    fn hmac_sha256_test_vector() {
+        let request = test_request();
+        let message = Message::request(&request);
+
+        let mut params = SignatureParams::new(vec![
+            field("date"),
+            Component::authority(),
+            field("content-type"),
+        ]);
+        params.set_created(1618884473);
+        params.set_keyid("test-shared-secret").unwrap();
+
+        assert_eq!(
+            signature_base(&message, &params).unwrap(),
+            "\"date\": Tue, 20 Apr 2021 02:07:55 GMT\n\
+             \"@authority\": example.com\n\
+             \"content-type\": application/json\n\
+             \"@signature-params\": (\"date\" \"@authority\" \"content-type\")\
+             ;created=1618884473;keyid=\"test-shared-secret\""
+        );
+
+        let (input, signature) = sign(&message, "sig-b25", &params, &test_shared_secret()).unwrap();
+        assert_eq!(
+            input,
+            "sig-b25=(\"date\" \"@authority\" \"content-type\");created=1618884473;keyid=\"test-shared-secret\""
+        );
+        assert_eq!(
+            signature,
+            "sig-b25=:pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=:"
+        );
+    }
+
+    #[test]
+    fn verifies_rfc_signature() {
+        let mut request = test_request();
+        request.headers.append(
+            SIGNATURE_INPUT,
+            HeaderValue::from_static("sig-b21=();created=1618884473;keyid=\"test-key-rsa-pss\""),
+        );
+        request.headers.append(
+            SIGNATURE_INPUT,
+            HeaderValue::from_static(
+                "sig-b25=(\"date\" \"@authority\" \"content-type\");created=1618884473;keyid=\"test-shared-secret\"",
+            ),
+        );
+        request.headers.append(
+            SIGNATURE,
+            HeaderValue::from_static("sig-b25=:pxcQw6G3AjtMBQjwo8XzkZf/bws5LelbaMk5rGIGtE8=:"),
+        );
+
+        let key = test_shared_secret();
+        let params = verify(&Message::request(&request), "sig-b25", &key).unwrap();
+        assert_eq!(params.created(), Some(1618884473));
+        assert_eq!(params.keyid(), Some("test-shared-secret"));
+        assert_eq!(params.components().len(), 3);
+
+        let labels: Vec<_> = inputs(&request.headers)
+            .unwrap()
+            .into_iter()
+            .map(|(label, _)| label)
+            .collect();
+        assert_eq!(labels, ["sig-b21", "sig-b25"]);
+
+        assert!(verify(&Message::request(&request), "sig-b21", &key)
+            .unwrap_err()
+            .is_missing_signature());
+
+        request
+            .headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        assert!(verify(&Message::request(&request), "sig-b25", &key)
+            .unwrap_err()
+            .is_verification_failed());
+    }
+
+    // RFC 9421, appendix B.2.1 and B.2.2
+    #[test]
+    fn request_signature_bases() {
+        let request = test_request();
+        let message = Message::request(&request);
+
+        let mut params = SignatureParams::new(vec![]);
+        params.set_created(1618884473);
+        params.set_keyid("test-key-rsa-pss").unwrap();
+        params.set_nonce("b3k2pp5k7z-50gnwp.yemd").unwrap();
+        assert_eq!(
+            signature_base(&message, &params).unwrap(),
+            "\"@signature-params\": ();created=1618884473;keyid=\"test-key-rsa-pss\";nonce=\"b3k2pp5k7z-50gnwp.yemd\""
+        );
+
+        let mut params = SignatureParams::new(vec![
+            Component::authority(),
+            field("content-digest"),
+            Component::query_param("Pet"),
+        ]);
+        params.set_created(1618884473);
+        params.set_keyid("test-key-rsa-pss").unwrap();
+        params.set_tag("header-example").unwrap();
+        assert_eq!(
+            signature_base(&message, &params).unwrap(),
+            "\"@authority\": example.com\n\
+             \"content-digest\": sha-512=:WZDPaVn/7XgHaAy8pmojAkGWoRx2UFChF41A2svX+TaPm+AbwAgBWnrIiYllu7BNNyealdVLvRwEmTHWXvJwew==:\n\
+             \"@query-param\";name=\"Pet\": dog\n\
+             \"@signature-params\": (\"@authority\" \"content-digest\" \"@query-param\";name=\"Pet\")\
+             ;created=1618884473;keyid=\"test-key-rsa-pss\";tag=\"header-example\""
+        );
+    }
+
+    // RFC 9421, appendix B.2.4
+    #[test]
+    fn response_signature_base() {
+        let response = test_response();
+        let mut params = SignatureParams::new(vec![
+            Component::status(),
+            field("content-type"),
+            field("content-digest"),
+            field("content-length"),
+        ]);
+        params.set_created(1618884473);
+        params.set_keyid("test-key-ecc-p256").unwrap();
+
+        assert_eq!(
+            signature_base(&Message::response(&response), &params).unwrap(),
+            "\"@status\": 200\n\
+             \"content-type\": application/json\n\
+             \"content-digest\": sha-512=:mEWXIS7MaLRuGgxOBdODa3xqM1XdEvxoYhvlCFJ41QJgJc4GTsPp29l5oGX69wWdXymyU0rjJuahq4l5aGgfLQ==:\n\
+             \"content-length\": 23\n\
+             \"@signature-params\": (\"@status\" \"content-type\" \"content-digest\" \"content-length\")\
+             ;created=1618884473;keyid=\"test-key-ecc-p256\""
+        );
+    }
+
+    // RFC 9421, section 2.2
+    #[test]
+    fn derived_components() {
+        let (request, _) =
+            Request::post("https://www.example.com/path?param=value&foo=bar&baz=bat%2Dman")
+                .body(())
+                .unwrap()
+                .into_parts();
+        let message = Message::request(&request);
+        let value =
+            |c: Component| base(&message, vec![c]).map(|b| b.lines().next().unwrap().to_string());
+
+        assert_eq!(value(Component::method()).unwrap(), "\"@method\": POST");
+        assert_eq!(
+            value(Component::target_uri()).unwrap(),
+            "\"@target-uri\": https://www.example.com/path?param=value&foo=bar&baz=bat%2Dman"
+        );
+        assert_eq!(
+            value(Component::authority()).unwrap(),
+            "\"@authority\": www.example.com"
+        );
+        assert_eq!(value(Component::scheme()).unwrap(), "\"@scheme\": https");
+        assert_eq!(
+            value(Component::request_target()).unwrap(),
+            "\"@request-target\": /path?param=value&foo=bar&baz=bat%2Dman"
+        );
+        assert_eq!(value(Component::path()).unwrap(), "\"@path\": /path");
+        assert_eq!(
+            value(Component::query()).unwrap(),
+            "\"@query\": ?param=value&foo=bar&baz=bat%2Dman"
+        );
+        assert_eq!(
+            value(Component::query_param("baz")).unwrap(),
+            "\"@query-param\";name=\"baz\": bat-man"
+        );
+        assert!(value(Component::query_param("qux"))
+            .unwrap_err()
+            .is_missing_component());
+        assert!(value(Component::status())
+            .unwrap_err()
+            .is_missing_component());
+
+        let (request, _) = Request::get("/")
+            .header("host", "Example.COM:443")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let message = Message::request(&request);
+        assert_eq!(
+            base(&message, vec![Component::query()])
+                .unwrap()
+                .lines()
+                .next(),
+            Some("\"@query\": ?")
+        );
+        assert_eq!(
+            base(&message, vec![Component::authority()])
+                .unwrap()
+                .lines()
+                .next(),
+            Some("\"@authority\": example.com:443")
+        );
+        assert!(base(&message, vec![Component::target_uri()]).is_err());
+    }
+
+    // RFC 9421, section 2.2.8
+    #[test]
+    fn query_params() {
+        let (request, _) = Request::get(
+            "/parameters?var=this%20is%20a%20big%0Avalue&bar=with+plus+whitespace&fa%C3%A7ade%22%3A%20=something&dup=1&dup=2",
+        )
+        .body(())
+        .unwrap()
+        .into_parts();
+        let message = Message::request(&request);
+
+        assert_eq!(
+            base(
+                &message,
+                vec![
+                    Component::query_param("var"),
+                    Component::query_param("bar"),
+                    Component::query_param("fa\u{e7}ade\": "),
+                ]
+            )
+            .unwrap(),
+            "\"@query-param\";name=\"var\": this%20is%20a%20big%0Avalue\n\
+             \"@query-param\";name=\"bar\": with%20plus%20whitespace\n\
+             \"@query-param\";name=\"fa%C3%A7ade%22%3A%20\": something\n\
+             \"@signature-params\": (\"@query-param\";name=\"var\" \"@query-param\";name=\"bar\" \
+             \"@query-param\";name=\"fa%C3%A7ade%22%3A%20\")"
+        );
+        assert!(base(&message, vec![Component::query_param("dup")]).is_err());
+    }
+
+    // RFC 9421, section 2.1
+    #[test]
+    fn field_components() {
+        let (request, _) = Request::get("https://example.com/")
+            .header("cache-control", "   must-revalidate  ")
+            .header("cache-control", "max-age=60")
+            .header("priority", "u=1,   i")
+            .header("x-dict", "a=1, b=2;x=1;y=2, c=(a b c)")
+            .header(DATE, "Tue, 20 Apr 2021 02:07:55 GMT")
+            .header("x-obs", HeaderValue::from_bytes(b"caf\xc3\xa9").unwrap())
+            .body(())
+            .unwrap()
+            .into_parts();
+        let message = Message::request(&request);
+        let value =
+            |c: Component| base(&message, vec![c]).map(|b| b.lines().next().unwrap().to_string());
+
+        assert_eq!(
+            value(field("cache-control")).unwrap(),
+            "\"cache-control\": must-revalidate, max-age=60"
+        );
+        assert_eq!(
+            value(field("priority").sf()).unwrap(),
+            "\"priority\";sf: u=1, i"
+        );
+        assert_eq!(
+            value(field("x-dict").key("b")).unwrap(),
+            "\"x-dict\";key=\"b\": 2;x=1;y=2"
+        );
+        assert_eq!(
+            value(field("x-dict").key("c")).unwrap(),
+            "\"x-dict\";key=\"c\": (a b c)"
+        );
+        assert!(value(field("x-dict").key("d"))
+            .unwrap_err()
+            .is_missing_component());
+        assert_eq!(
+            value(field("cache-control").bs()).unwrap(),
+            "\"cache-control\";bs: :bXVzdC1yZXZhbGlkYXRl:, :bWF4LWFnZT02MA==:"
+        );
+        assert_eq!(
+            value(field("x-obs").bs()).unwrap(),
+            "\"x-obs\";bs: :Y2Fmw6k=:"
+        );
+
+        assert!(value(field("x-obs")).is_err());
+        assert!(value(field("x-dict").sf()).is_err());
+        assert!(value(field("x-missing"))
+            .unwrap_err()
+            .is_missing_component());
+        assert!(value(field("date").req()).is_err());
+        assert!(value(field("date").bs().sf()).is_err());
+        assert!(base(&message, vec![field("date"), field("date")]).is_err());
+    }
+
+    #[test]
+    fn request_components_of_responses() {
+        let request = test_request();
+        let response = test_response();
+        let components = vec![
+            Component::status(),
+            Component::method().req(),
+            Component::authority().req(),
+            field("content-type").req(),
+        ];
+
+        assert_eq!(
+            base(&Message::response_to(&response, &request), components.clone()).unwrap(),
+            "\"@status\": 200\n\
+             \"@method\";req: POST\n\
+             \"@authority\";req: example.com\n\
+             \"content-type\";req: application/json\n\
+             \"@signature-params\": (\"@status\" \"@method\";req \"@authority\";req \"content-type\";req)"
+        );
+
+        assert!(base(&Message::response(&response), components).is_err());
+        assert!(base(
+            &Message::response_to(&response, &request),
+            vec![Component::method()]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        let request = test_request();
+        let message = Message::request(&request);
+        let key = test_shared_secret();
+        let params = SignatureParams::new(vec![Component::method()]);
+
+        assert!(sign(&message, "Sig", &params, &key).is_err());
+        assert!(SignatureParams::new(vec![]).set_keyid("caf\u{e9}").is_err());
+
+        let verify_with = |input: &'static str, signature: &'static str| {
+            let mut request = test_request();
+            request
+                .headers
+                .insert(SIGNATURE_INPUT, HeaderValue::from_static(input));
+            request
+                .headers
+                .insert(SIGNATURE, HeaderValue::from_static(signature));
+            verify(&Message::request(&request), "sig", &key).map(|_| ())
+        };
+        assert!(verify_with("sig=(\"@method\"", "sig=:AAAA:").is_err());
+        assert!(verify_with("sig=\"@method\"", "sig=:AAAA:").is_err());
+        assert!(verify_with("sig=(@method)", "sig=:AAAA:").is_err());
+        assert!(verify_with("sig=(\"@method\");created=\"now\"", "sig=:AAAA:").is_err());
+        assert!(verify_with("sig=(\"@method\")", "sig=AAAA").is_err());
+        assert!(verify_with("sig=(\"@method\")", "sig=:AAAA:")
+            .unwrap_err()
+            .is_verification_failed());
+    }
+}
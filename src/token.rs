@@ -0,0 +1,198 @@
+//! The common syntax of HTTP field values (RFC 9110, Section 5.6): tokens,
+//! quoted strings and comments.
+
+// token = 1*tchar
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(is_tchar)
+}
+
+// tchar = "!" / "#" / "$" / "%" / "&" / "'" / "*" / "+" / "-" / "." / "^" /
+//         "_" / "`" / "|" / "~" / DIGIT / ALPHA
+pub(crate) fn is_tchar(b: u8) -> bool {
+    match b {
+        b'!' | b'#' | b'$' | b'%' | b'&' | b'\'' | b'*' | b'+' | b'-' | b'.' | b'^' | b'_'
+        | b'`' | b'|' | b'~' => true,
+        _ => b.is_ascii_alphanumeric(),
+    }
+}
+
+// token68 = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+fn is_token68_char(b: u8) -> bool {
+    matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/') || b.is_ascii_alphanumeric()
+}
+
+// qdtext = HTAB / SP / %x21 / %x23-5B / %x5D-7E / obs-text
+//
+// This also matches the delimiters `"` and `\`, which callers check first.
+fn is_qdtext(b: u8) -> bool {
+    b == b'\t' || (b >= 0x20 && b != 0x7f)
+}
+
+/// A cursor over a field value.
+///
+/// Errors are reported as `None`, for the caller to turn into its own error
+/// type.
+pub(crate) struct Parser<'a> {
+    s: &'a str,
+    pub(crate) pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(s: &'a str) -> Parser<'a> {
+        Parser { s, pos: 0 }
+    }
+
+    pub(crate) fn is_end(&self) -> bool {
+        self.pos == self.s.len()
+    }
+
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).cloned()
+    }
+
+    pub(crate) fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skips spaces, returning how many were skipped.
+    pub(crate) fn skip_sp(&mut self) -> usize {
+        let start = self.pos;
+        while self.eat(b' ') {}
+        self.pos - start
+    }
+
+    /// Skips spaces and tabs, returning how many were skipped.
+    pub(crate) fn skip_ows(&mut self) -> usize {
+        let start = self.pos;
+        while self.eat(b' ') || self.eat(b'\t') {}
+        self.pos - start
+    }
+
+    /// Skips the empty elements of a list, along with the whitespace around
+    /// them (RFC 9110, Section 5.6.1.2).
+    pub(crate) fn skip_empty_elements(&mut self) {
+        loop {
+            self.skip_ows();
+            if !self.eat(b',') {
+                break;
+            }
+        }
+    }
+
+    pub(crate) fn take_while<F: Fn(u8) -> bool>(&mut self, f: F) -> &'a str {
+        let start = self.pos;
+        while self.peek().map_or(false, &f) {
+            self.pos += 1;
+        }
+        &self.s[start..self.pos]
+    }
+
+    pub(crate) fn token(&mut self) -> Option<&'a str> {
+        Some(self.take_while(is_tchar)).filter(|s| !s.is_empty())
+    }
+
+    pub(crate) fn token68(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        if self.take_while(is_token68_char).is_empty() {
+            return None;
+        }
+        self.take_while(|b| b == b'=');
+        Some(&self.s[start..self.pos])
+    }
+
+    /// Parses a quoted string, returning its unescaped value.
+    pub(crate) fn quoted_string(&mut self) -> Option<String> {
+        self.delimited(b'"', b'"')
+    }
+
+    /// Parses a comment, which may contain nested comments, returning its
+    /// unescaped value without the outer parentheses.
+    pub(crate) fn comment(&mut self) -> Option<String> {
+        self.delimited(b'(', b')')
+    }
+
+    fn delimited(&mut self, open: u8, close: u8) -> Option<String> {
+        debug_assert_eq!(self.peek(), Some(open));
+        self.pos += 1;
+
+        let nested = open != close;
+        let mut value = String::new();
+        let mut depth = 0;
+        loop {
+            let start = self.pos;
+            self.take_while(|b| b != open && b != close && b != b'\\' && is_qdtext(b));
+            value.push_str(&self.s[start..self.pos]);
+
+            match self.peek() {
+                Some(b) if b == close && depth == 0 => {
+                    self.pos += 1;
+                    return Some(value);
+                }
+                Some(b) if b == close => {
+                    depth -= 1;
+                    value.push(b as char);
+                    self.pos += 1;
+                }
+                Some(b) if b == open && nested => {
+                    depth += 1;
+                    value.push(b as char);
+                    self.pos += 1;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    // Escape the next character, which may be multi-byte.
+                    let c = self.s[self.pos..]
+                        .chars()
+                        .next()
+                        .filter(|&c| c == '\t' || (c >= ' ' && c != '\x7f'))?;
+                    value.push(c);
+                    self.pos += c.len_utf8();
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        assert!(!is_token("text/html"));
+        assert!(is_token("x-custom_1.0~"));
+        assert!(!is_token(""));
+
+        let mut p = Parser::new("abc def");
+        assert_eq!(p.token(), Some("abc"));
+        assert_eq!(p.token(), None);
+        assert_eq!(p.skip_sp(), 1);
+        assert_eq!(p.token(), Some("def"));
+        assert!(p.is_end());
+
+        let mut p = Parser::new("dG9rZW4=, x");
+        assert_eq!(p.token68(), Some("dG9rZW4="));
+        assert_eq!(p.peek(), Some(b','));
+    }
+
+    #[test]
+    fn quoted_strings_and_comments() {
+        let mut p = Parser::new(r#""a \"b\" \\c"rest"#);
+        assert_eq!(p.quoted_string().as_deref(), Some(r#"a "b" \c"#));
+        assert_eq!(p.token(), Some("rest"));
+
+        let mut p = Parser::new(r"(a (nested) \) comment)");
+        assert_eq!(p.comment().as_deref(), Some("a (nested) ) comment"));
+        assert!(p.is_end());
+
+        assert_eq!(Parser::new("\"unterminated").quoted_string(), None);
+        assert_eq!(Parser::new("(a (b)").comment(), None);
+        assert_eq!(Parser::new("\"a\x7f\"").quoted_string(), None);
+    }
+}
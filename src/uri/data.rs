@@ -0,0 +1,487 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use bytes::Bytes;
+
+use super::{percent, Authority};
+use crate::base64;
+use crate::token::is_token;
+
+/// A `data:` URL, as defined in RFC 2397.
+///
+/// `data:` URLs are parsed with the "data: URL processor" of the WHATWG
+/// Fetch Standard, which is what browsers do: the media type is parsed
+/// leniently, falling back to `text/plain;charset=US-ASCII`, and the payload
+/// is percent-decoded, then base64-decoded if the media type ends with
+/// `;base64`. A `Uri` can't hold most `data:` URLs, so they are parsed from
+/// strings.
+///
+/// # Examples
+///
+/// ```
+/// # use http::uri::DataUrl;
+/// let url: DataUrl = "data:text/plain;charset=utf-8;base64,SGVsbG8sIFdvcmxkIQ==".parse().unwrap();
+///
+/// assert_eq!(url.essence(), "text/plain");
+/// assert_eq!(url.parameter("charset"), Some("utf-8"));
+/// assert!(url.is_base64());
+/// assert_eq!(url.body(), "Hello, World!");
+///
+/// let url: DataUrl = "data:,A%20brief%20note".parse().unwrap();
+///
+/// assert_eq!(url.media_type(), "text/plain;charset=US-ASCII");
+/// assert_eq!(url.body(), "A brief note");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataUrl {
+    // Serialized, with the essence first.
+    media_type: String,
+    essence_len: usize,
+    parameters: Vec<(String, String)>,
+    base64: bool,
+    body: Bytes,
+}
+
+/// An error resulting from parsing an invalid `data:` URL.
+pub struct InvalidDataUrl {
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    InvalidScheme,
+    InvalidAuthority,
+    MissingComma,
+    InvalidBase64,
+}
+
+const DEFAULT_MEDIA_TYPE: &str = "text/plain;charset=US-ASCII";
+
+// ===== impl DataUrl =====
+
+impl DataUrl {
+    /// Parses a `data:` URL.
+    ///
+    /// Leading and trailing spaces and control characters, and any tab or
+    /// newline, are removed first, and the fragment is ignored, like the
+    /// WHATWG URL parser does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the scheme isn't `data`, if there is no `,`
+    /// after the media type, or if a base64 payload is invalid.
+    pub fn parse(s: &str) -> Result<DataUrl, InvalidDataUrl> {
+        let input = serialize(s)?;
+
+        let comma = input.find(',').ok_or(ErrorKind::MissingComma)?;
+        let mut media_type = trim_ascii_whitespace(&input[..comma]);
+        let mut body = percent_decode(&input.as_bytes()[comma + 1..]);
+
+        let base64 = match strip_base64(media_type) {
+            Some(rest) => {
+                media_type = rest;
+                true
+            }
+            None => false,
+        };
+        if base64 {
+            body = base64::decode_forgiving(&body).ok_or(ErrorKind::InvalidBase64)?;
+        }
+
+        let media_type = if media_type.starts_with(';') {
+            parse_media_type(&format!("text/plain{}", media_type))
+        } else {
+            parse_media_type(media_type)
+        };
+        let (essence, parameters) = media_type
+            .or_else(|| parse_media_type(DEFAULT_MEDIA_TYPE))
+            .expect("the default media type is valid");
+
+        let mut serialized = essence.clone();
+        for (name, value) in &parameters {
+            serialized.push(';');
+            serialized.push_str(name);
+            serialized.push('=');
+            push_parameter_value(&mut serialized, value);
+        }
+
+        Ok(DataUrl {
+            media_type: serialized,
+            essence_len: essence.len(),
+            parameters,
+            base64,
+            body: Bytes::from(body),
+        })
+    }
+
+    /// Returns the media type, with its parameters, such as
+    /// `text/plain;charset=US-ASCII`.
+    ///
+    /// The type, the subtype and the parameter names are lowercased, and
+    /// parameter values are quoted if needed.
+    #[inline]
+    pub fn media_type(&self) -> &str {
+        &self.media_type
+    }
+
+    /// Returns the type and subtype of the media type, such as `text/plain`.
+    #[inline]
+    pub fn essence(&self) -> &str {
+        &self.media_type[..self.essence_len]
+    }
+
+    /// Returns the value of the media type parameter `name`, which is
+    /// case-insensitive.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters()
+            .find(|&(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns an iterator over the `(name, value)` parameters of the media
+    /// type, in order.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parameters
+            .iter()
+            .map(|(name, value)| (&name[..], &value[..]))
+    }
+
+    /// Returns `true` if the payload was base64-encoded.
+    #[inline]
+    pub fn is_base64(&self) -> bool {
+        self.base64
+    }
+
+    /// Returns the decoded payload.
+    #[inline]
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Consumes the `DataUrl`, returning the decoded payload.
+    #[inline]
+    pub fn into_body(self) -> Bytes {
+        self.body
+    }
+}
+
+impl FromStr for DataUrl {
+    type Err = InvalidDataUrl;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DataUrl::parse(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for DataUrl {
+    type Error = InvalidDataUrl;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        DataUrl::parse(s)
+    }
+}
+
+// ===== processing =====
+
+// Returns what follows `data:` in the URL serialization of `s`, without the
+// fragment.
+fn serialize(s: &str) -> Result<String, InvalidDataUrl> {
+    let s: String = s
+        .trim_matches(|c| c <= ' ')
+        .chars()
+        .filter(|&c| c != '\t' && c != '\n' && c != '\r')
+        .collect();
+    let has_scheme = s.len() >= 5 && s.as_bytes()[..5].eq_ignore_ascii_case(b"data:");
+    if !has_scheme {
+        return Err(ErrorKind::InvalidScheme.into());
+    }
+    let s = &s[5..];
+    let s = match s.find('#') {
+        Some(i) => &s[..i],
+        None => s,
+    };
+
+    if let Some(rest) = s.strip_prefix("//") {
+        let end = rest.find(&['/', '?'][..]).unwrap_or(rest.len());
+        check_authority(&rest[..end])?;
+    }
+
+    let mut serialized = String::with_capacity(s.len());
+    let mut in_query = false;
+    for c in s.chars() {
+        in_query |= c == '?';
+        let encode = !(' '..='~').contains(&c) || (in_query && matches!(c, ' ' | '"' | '<' | '>'));
+        if encode {
+            let mut buf = [0; 4];
+            serialized.push_str(&percent::encode(
+                c.encode_utf8(&mut buf),
+                percent::EncodeSet::URI,
+            ));
+        } else {
+            serialized.push(c);
+        }
+    }
+    Ok(serialized)
+}
+
+// A `data:` URL may have an authority, like any URL, which must be valid.
+fn check_authority(s: &str) -> Result<(), InvalidDataUrl> {
+    if s.is_empty() {
+        return Ok(());
+    }
+    let authority = Authority::from_str(s).map_err(|_| ErrorKind::InvalidAuthority)?;
+    if authority.port().is_none() && authority.host_port() != authority.host() {
+        return Err(ErrorKind::InvalidAuthority.into());
+    }
+    Ok(())
+}
+
+// Strips `;base64`, with optional spaces after `;`, from the end of a media
+// type.
+fn strip_base64(media_type: &str) -> Option<&str> {
+    let len = media_type.len();
+    if len < 6 || !media_type.as_bytes()[len - 6..].eq_ignore_ascii_case(b"base64") {
+        return None;
+    }
+    let rest = media_type[..len - 6].trim_end_matches(' ');
+    rest.strip_suffix(';')
+}
+
+// Decodes `%XY` escapes, keeping malformed ones as-is.
+fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        let escape = match input.get(i + 1..i + 3) {
+            Some(&[hi, lo]) if input[i] == b'%' => {
+                percent::hex(hi).and_then(|hi| percent::hex(lo).map(|lo| hi << 4 | lo))
+            }
+            _ => None,
+        };
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(input[i]);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+// ===== media types =====
+
+// Parses a media type with the WHATWG "parse a MIME type" algorithm,
+// returning its essence and its parameters.
+fn parse_media_type(s: &str) -> Option<(String, Vec<(String, String)>)> {
+    let s = s.trim_matches(is_http_whitespace);
+
+    let slash = s.find('/')?;
+    let type_ = &s[..slash];
+    let rest = &s[slash + 1..];
+    let semicolon = rest.find(';').unwrap_or(rest.len());
+    let subtype = rest[..semicolon].trim_end_matches(is_http_whitespace);
+    if !is_token(type_) || !is_token(subtype) {
+        return None;
+    }
+    let essence = format!("{}/{}", type_, subtype).to_ascii_lowercase();
+
+    let mut parameters: Vec<(String, String)> = Vec::new();
+    let mut rest = &rest[semicolon..];
+    while !rest.is_empty() {
+        // Skip the `;` and any whitespace.
+        rest = rest[1..].trim_start_matches(is_http_whitespace);
+
+        let end = rest.find(&[';', '='][..]).unwrap_or(rest.len());
+        let name = rest[..end].to_ascii_lowercase();
+        rest = &rest[end..];
+        if rest.starts_with(';') {
+            continue;
+        }
+        if rest.is_empty() {
+            break;
+        }
+        rest = &rest[1..];
+
+        let value = if rest.starts_with('"') {
+            let (value, len) = parse_quoted_string(rest);
+            rest = &rest[len..];
+            let end = rest.find(';').unwrap_or(rest.len());
+            rest = &rest[end..];
+            value
+        } else {
+            let end = rest.find(';').unwrap_or(rest.len());
+            let value = rest[..end].trim_end_matches(is_http_whitespace);
+            rest = &rest[end..];
+            if value.is_empty() {
+                continue;
+            }
+            value.to_string()
+        };
+
+        let is_valid = is_token(&name)
+            && value.chars().all(is_quoted_string_char)
+            && !parameters.iter().any(|(n, _)| *n == name);
+        if is_valid {
+            parameters.push((name, value));
+        }
+    }
+
+    Some((essence, parameters))
+}
+
+// Parses a quoted string at the start of `s`, returning its unescaped value
+// and the length it takes in `s`. An unterminated string goes to the end.
+fn parse_quoted_string(s: &str) -> (String, usize) {
+    let mut value = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, i + 1),
+            '\\' => match chars.next() {
+                Some((_, c)) => value.push(c),
+                None => {
+                    value.push('\\');
+                    break;
+                }
+            },
+            c => value.push(c),
+        }
+    }
+    (value, s.len())
+}
+
+fn push_parameter_value(out: &mut String, value: &str) {
+    if !value.is_empty() && is_token(value) {
+        out.push_str(value);
+        return;
+    }
+    out.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+fn is_http_whitespace(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | ' ')
+}
+
+fn trim_ascii_whitespace(s: &str) -> &str {
+    s.trim_matches(|c| matches!(c, '\t' | '\n' | '\x0c' | '\r' | ' '))
+}
+
+fn is_quoted_string_char(c: char) -> bool {
+    c == '\t' || (' '..='~').contains(&c) || ('\u{80}'..='\u{ff}').contains(&c)
+}
+
+// ===== impl InvalidDataUrl =====
+
+impl InvalidDataUrl {
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::InvalidScheme => "data url must start with `data:`",
+            ErrorKind::InvalidAuthority => "invalid authority in data url",
+            ErrorKind::MissingComma => "data url is missing a comma",
+            ErrorKind::InvalidBase64 => "invalid base64 in data url",
+        }
+    }
+}
+
+impl From<ErrorKind> for InvalidDataUrl {
+    fn from(kind: ErrorKind) -> InvalidDataUrl {
+        InvalidDataUrl { kind }
+    }
+}
+
+impl fmt::Debug for InvalidDataUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidDataUrl").field(&self.kind).finish()
+    }
+}
+
+impl fmt::Display for InvalidDataUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for InvalidDataUrl {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_media_types() {
+        let cases = [
+            ("text/html;charset=gbk", Some("text/html;charset=gbk")),
+            ("TEXT/HTML;CHARSET=GBK", Some("text/html;charset=GBK")),
+            ("text/html;charset=gbk(", Some("text/html;charset=\"gbk(\"")),
+            (
+                "text/html;charset=gbk;charset=windows-1255",
+                Some("text/html;charset=gbk"),
+            ),
+            ("text/html;charset=\"gbk\"", Some("text/html;charset=gbk")),
+            (
+                "text/html;charset=\"g\\\"b\\",
+                Some("text/html;charset=\"g\\\"b\\\\\""),
+            ),
+            ("text/html;charset=;x=\"\"", Some("text/html;x=\"\"")),
+            (" text/html ; a ; b=c ", Some("text/html;b=c")),
+            (
+                "text/html;\u{e9}=x;a=\u{e9}",
+                Some("text/html;a=\"\u{e9}\""),
+            ),
+            ("text", None),
+            ("text/", None),
+            ("te xt/html", None),
+            ("/html", None),
+        ];
+        for &(input, output) in &cases {
+            let parsed = parse_media_type(input).map(|(essence, parameters)| {
+                let mut s = essence;
+                for (name, value) in parameters {
+                    s.push(';');
+                    s.push_str(&name);
+                    s.push('=');
+                    push_parameter_value(&mut s, &value);
+                }
+                s
+            });
+            assert_eq!(parsed.as_ref().map(|s| &s[..]), output, "{}", input);
+        }
+    }
+
+    #[test]
+    fn strips_base64() {
+        assert_eq!(strip_base64(";base64"), Some(""));
+        assert_eq!(strip_base64("text/plain;  BASE64"), Some("text/plain"));
+        assert_eq!(strip_base64("text/plain;x=base64"), None);
+        assert_eq!(strip_base64("base64"), None);
+        assert_eq!(strip_base64("é;base64"), Some("é"));
+    }
+
+    #[test]
+    fn decodes_percent_encodings() {
+        assert_eq!(percent_decode(b"a%20b%zz%4"), b"a b%zz%4");
+        assert_eq!(percent_decode(b"%FF%"), b"\xff%");
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        assert!(DataUrl::parse("http:,x").is_err());
+        assert!(DataUrl::parse("data:text/plain").is_err());
+        assert!(DataUrl::parse("data:;base64,a").is_err());
+        assert!(DataUrl::parse("data://a:b/,x").is_err());
+        assert!(DataUrl::parse(" \tDATA:,x ").is_ok());
+    }
+}
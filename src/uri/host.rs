@@ -0,0 +1,234 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The host of an `Authority`, parsed by kind.
+///
+/// See RFC 3986, section 3.2.2 and RFC 6874.
+///
+/// # Examples
+///
+/// ```
+/// # use http::uri::{Authority, Host};
+/// # use std::net::Ipv6Addr;
+/// let authority: Authority = "[fe80::1%25eth0]:8080".parse().unwrap();
+///
+/// assert_eq!(authority.host(), "[fe80::1%25eth0]");
+/// assert_eq!(
+///     authority.host_kind(),
+///     Host::Ipv6("fe80::1".parse::<Ipv6Addr>().unwrap(), Some("eth0"))
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Host<'a> {
+    /// An IPv4 address in dotted-decimal form, such as `127.0.0.1`.
+    Ipv4(Ipv4Addr),
+    /// An IPv6 address, such as `[::1]`, and its zone identifier, such as
+    /// `eth0` in `[fe80::1%25eth0]`.
+    ///
+    /// The zone identifier is still percent-encoded.
+    Ipv6(Ipv6Addr, Option<&'a str>),
+    /// An IP address of a future version, such as `[v7.addr]`, without the
+    /// brackets.
+    IpFuture(&'a str),
+    /// A registered name, such as `example.com`.
+    ///
+    /// It may be empty, as in `file:///etc/hosts`, and is not lowercased.
+    RegName(&'a str),
+}
+
+impl<'a> Host<'a> {
+    // Parses a host that was validated with `Authority::parse`.
+    pub(super) fn parse(host: &'a str) -> Host<'a> {
+        if host.starts_with('[') {
+            let literal = &host[1..host.len() - 1];
+            if literal.starts_with(&['v', 'V'][..]) {
+                return Host::IpFuture(literal);
+            }
+
+            let (addr, zone) = split_zone(literal);
+            let addr = addr.parse().expect("IP-literal was validated");
+            return Host::Ipv6(addr, zone);
+        }
+
+        match parse_ipv4(host) {
+            Some(addr) => Host::Ipv4(addr),
+            None => Host::RegName(host),
+        }
+    }
+
+    /// Returns the IP address of this host, if it is one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::Authority;
+    /// # use std::net::{IpAddr, Ipv4Addr};
+    /// let authority: Authority = "127.0.0.1:8080".parse().unwrap();
+    /// assert_eq!(authority.host_kind().ip(), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    ///
+    /// let authority: Authority = "localhost:8080".parse().unwrap();
+    /// assert_eq!(authority.host_kind().ip(), None);
+    /// ```
+    pub fn ip(&self) -> Option<IpAddr> {
+        match *self {
+            Host::Ipv4(addr) => Some(IpAddr::V4(addr)),
+            Host::Ipv6(addr, _) => Some(IpAddr::V6(addr)),
+            Host::IpFuture(_) | Host::RegName(_) => None,
+        }
+    }
+}
+
+/// Returns `true` if `literal`, the part of an IP-literal between the
+/// brackets, is an IPv6 address with an optional zone identifier, or an
+/// IPvFuture.
+pub(super) fn is_valid_ip_literal(literal: &[u8]) -> bool {
+    match literal.first() {
+        Some(b'v') | Some(b'V') => {
+            let dot = match literal.iter().position(|&b| b == b'.') {
+                Some(dot) => dot,
+                None => return false,
+            };
+            dot > 1
+                && literal[1..dot].iter().all(u8::is_ascii_hexdigit)
+                && dot + 1 < literal.len()
+                && literal[dot + 1..]
+                    .iter()
+                    .all(|&b| b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:".contains(&b))
+        }
+        _ => {
+            let literal = match std::str::from_utf8(literal) {
+                Ok(literal) => literal,
+                Err(_) => return false,
+            };
+            let (addr, zone) = split_zone(literal);
+            if addr.contains('%') || addr.parse::<Ipv6Addr>().is_err() {
+                return false;
+            }
+            match zone {
+                Some(zone) => is_valid_zone(zone.as_bytes()),
+                None => true,
+            }
+        }
+    }
+}
+
+fn split_zone(literal: &str) -> (&str, Option<&str>) {
+    match literal.find("%25") {
+        Some(i) => (&literal[..i], Some(&literal[i + 3..])),
+        None => (literal, None),
+    }
+}
+
+// ZoneID = 1*( unreserved / pct-encoded )
+fn is_valid_zone(zone: &[u8]) -> bool {
+    let mut i = 0;
+    while i < zone.len() {
+        match zone[i] {
+            b'%' => {
+                if i + 2 >= zone.len()
+                    || !zone[i + 1].is_ascii_hexdigit()
+                    || !zone[i + 2].is_ascii_hexdigit()
+                {
+                    return false;
+                }
+                i += 3;
+            }
+            b if b.is_ascii_alphanumeric() || b"-._~".contains(&b) => i += 1,
+            _ => return false,
+        }
+    }
+    !zone.is_empty()
+}
+
+// Parses an IPv4address, whose dec-octets have no leading zeros.
+fn parse_ipv4(s: &str) -> Option<Ipv4Addr> {
+    let mut octets = [0u8; 4];
+    let mut parts = s.split('.');
+    for octet in &mut octets {
+        let part = parts.next()?;
+        let valid = !part.is_empty()
+            && part.len() <= 3
+            && part.bytes().all(|b| b.is_ascii_digit())
+            && (part.len() == 1 || !part.starts_with('0'));
+        if !valid {
+            return None;
+        }
+        *octet = part.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Ipv4Addr::from(octets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hosts() {
+        assert_eq!(Host::parse("127.0.0.1"), Host::Ipv4(Ipv4Addr::LOCALHOST));
+        assert_eq!(Host::parse("[::1]"), Host::Ipv6(Ipv6Addr::LOCALHOST, None));
+        assert_eq!(
+            Host::parse("[fe80::1%25en1%2F2]"),
+            Host::Ipv6("fe80::1".parse().unwrap(), Some("en1%2F2"))
+        );
+        assert_eq!(
+            Host::parse("[::ffff:10.0.0.1]"),
+            Host::Ipv6("::ffff:a00:1".parse().unwrap(), None)
+        );
+        assert_eq!(Host::parse("[v1F.a:b]"), Host::IpFuture("v1F.a:b"));
+        assert_eq!(Host::parse("Example.com"), Host::RegName("Example.com"));
+        assert_eq!(Host::parse(""), Host::RegName(""));
+    }
+
+    #[test]
+    fn ipv4_is_strict() {
+        for &s in &[
+            "1.2.3",
+            "1.2.3.4.5",
+            "1.2.3.256",
+            "01.2.3.4",
+            "1.2.3.",
+            "1..3.4",
+            "0x1.2.3.4",
+        ] {
+            assert_eq!(Host::parse(s), Host::RegName(s), "{}", s);
+        }
+        assert_eq!(Host::parse("0.0.0.0"), Host::Ipv4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(
+            Host::parse("255.255.255.255"),
+            Host::Ipv4(Ipv4Addr::BROADCAST)
+        );
+    }
+
+    #[test]
+    fn validates_ip_literals() {
+        for &s in &[
+            "::1",
+            "2001:db8::2:1",
+            "fe80::1%25eth0",
+            "fe80::1%25a%2Fb",
+            "v1.x",
+            "VA.a:b=c",
+        ] {
+            assert!(is_valid_ip_literal(s.as_bytes()), "{}", s);
+        }
+        for &s in &[
+            "",
+            "1.2.3.4",
+            "::1::2",
+            "fe80::1%eth0",
+            "fe80::1%25",
+            "fe80::1%25%2",
+            "fe80::1%25e/0",
+            "v.x",
+            "v1.",
+            "v1",
+            "vg.x",
+            "v1.a/b",
+            "example.com",
+        ] {
+            assert!(!is_valid_ip_literal(s.as_bytes()), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str;
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use super::{Authority, ErrorKind, InvalidUri, Uri};
+
+/// Displays a `Uri` or an `Authority` with the A-labels of its host
+/// converted back to U-labels.
+///
+/// This is meant to show a `Uri` to people, such as in a user interface;
+/// the `Display` of `Uri` itself stays the ASCII form that goes on the
+/// wire. `Debug` and `Display` print the same thing.
+///
+/// This `struct` is created by `Uri::display_unicode` and
+/// `Authority::display_unicode`.
+///
+/// # Examples
+///
+/// ```
+/// # use http::Uri;
+/// let uri: Uri = "https://bücher.example/".parse().unwrap();
+///
+/// assert_eq!(uri, "https://xn--bcher-kva.example/");
+/// assert_eq!(uri.display_unicode().to_string(), "https://bücher.example/");
+/// ```
+#[derive(Clone, Copy)]
+pub struct DisplayUnicode<'a> {
+    inner: Inner<'a>,
+}
+
+#[derive(Clone, Copy)]
+enum Inner<'a> {
+    Uri(&'a Uri),
+    Authority(&'a Authority),
+}
+
+impl<'a> DisplayUnicode<'a> {
+    pub(super) fn uri(uri: &'a Uri) -> DisplayUnicode<'a> {
+        DisplayUnicode {
+            inner: Inner::Uri(uri),
+        }
+    }
+
+    pub(super) fn authority(authority: &'a Authority) -> DisplayUnicode<'a> {
+        DisplayUnicode {
+            inner: Inner::Authority(authority),
+        }
+    }
+}
+
+impl<'a> fmt::Display for DisplayUnicode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            Inner::Uri(uri) => uri.fmt_with(f, fmt_authority),
+            Inner::Authority(authority) => fmt_authority(authority, f),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for DisplayUnicode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+fn fmt_authority(authority: &Authority, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(userinfo) = authority.userinfo() {
+        write!(f, "{}@", userinfo)?;
+    }
+    let host_port = authority.host_port();
+    let host = authority.host();
+    write!(f, "{}{}", host_to_unicode(host), &host_port[host.len()..])
+}
+
+// The UTS #46 options, with the strict rules fit for the host of a URI:
+// the result only has letters, digits, hyphens and dots.
+fn config() -> ::idna::Config {
+    ::idna::Config::default()
+        .use_std3_ascii_rules(true)
+        .verify_dns_length(true)
+        .check_hyphens(true)
+}
+
+/// Converts the host of an absolute or authority-form URI to A-labels, if
+/// it isn't ASCII.
+pub(super) fn uri_to_ascii(s: Bytes) -> Result<Bytes, InvalidUri> {
+    if s.is_ascii() || s[0] == b'/' {
+        return Ok(s);
+    }
+
+    let start = match s.iter().position(|&b| b == b':') {
+        Some(i) if s[i..].starts_with(b"://") && is_scheme(&s[..i]) => i + 3,
+        _ => 0,
+    };
+    let end = s[start..]
+        .iter()
+        .position(|&b| b == b'/' || b == b'?' || b == b'#')
+        .map_or(s.len(), |i| start + i);
+
+    if s[start..end].is_ascii() {
+        return Ok(s);
+    }
+    let authority = authority_to_ascii(&s[start..end])?;
+    let mut converted = BytesMut::with_capacity(s.len() - (end - start) + authority.len());
+    converted.put_slice(&s[..start]);
+    converted.put_slice(&authority);
+    converted.put_slice(&s[end..]);
+    Ok(converted.freeze())
+}
+
+/// Converts the host of an authority that isn't ASCII to A-labels.
+///
+/// Fails if the host is ASCII, since something else isn't.
+pub(super) fn authority_to_ascii(s: &[u8]) -> Result<Bytes, InvalidUri> {
+    let s = str::from_utf8(s).map_err(|_| ErrorKind::InvalidUriChar)?;
+    let host_start = s.rfind('@').map_or(0, |i| i + 1);
+    let host_end = match s[host_start..].rfind(':') {
+        Some(i) if s[host_start + i + 1..].bytes().all(|b| b.is_ascii_digit()) => host_start + i,
+        _ => s.len(),
+    };
+
+    let host = &s[host_start..host_end];
+    if host.is_ascii() {
+        return Err(ErrorKind::InvalidUriChar.into());
+    }
+    let host = config()
+        .to_ascii(host)
+        .map_err(|_| ErrorKind::InvalidAuthority)?;
+
+    let mut converted = String::with_capacity(s.len() + host.len());
+    converted.push_str(&s[..host_start]);
+    converted.push_str(&host);
+    converted.push_str(&s[host_end..]);
+    Ok(Bytes::from(converted))
+}
+
+/// Converts the A-labels of a host to U-labels.
+///
+/// The host is returned unchanged if it has no valid A-label.
+pub(super) fn host_to_unicode(host: &str) -> Cow<'_, str> {
+    let has_a_label = host
+        .split('.')
+        .any(|label| label.len() > 4 && label[..4].eq_ignore_ascii_case("xn--"));
+    if !has_a_label {
+        return Cow::Borrowed(host);
+    }
+
+    match config().to_unicode(host) {
+        (unicode, Ok(())) => Cow::Owned(unicode),
+        (_, Err(_)) => Cow::Borrowed(host),
+    }
+}
+
+fn is_scheme(s: &[u8]) -> bool {
+    !s.is_empty()
+        && s[0].is_ascii_alphabetic()
+        && s.iter()
+            .all(|&b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_ascii(s: &str) -> Result<String, InvalidUri> {
+        uri_to_ascii(Bytes::copy_from_slice(s.as_bytes()))
+            .map(|s| String::from_utf8(s.to_vec()).unwrap())
+    }
+
+    #[test]
+    fn converts_hosts_to_ascii() {
+        let cases = [
+            (
+                "http://bücher.example/a?b#c",
+                "http://xn--bcher-kva.example/a?b#c",
+            ),
+            (
+                "http://Bücher.example:8080",
+                "http://xn--bcher-kva.example:8080",
+            ),
+            (
+                "http://ü:ö@bücher.example/",
+                "http://ü:ö@xn--bcher-kva.example/",
+            ),
+            ("bücher.example:443", "xn--bcher-kva.example:443"),
+            ("http://faß.de", "http://xn--fa-hia.de"),
+            ("http://example.com/ü", "http://example.com/ü"),
+            ("/bücher", "/bücher"),
+        ];
+        for &(input, output) in &cases {
+            assert_eq!(to_ascii(input).unwrap(), output, "{}", input);
+        }
+
+        for &input in &["http://a\u{ff0f}b/", "http://-ü/", "http://\u{e000}/"] {
+            assert!(to_ascii(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn converts_hosts_to_unicode() {
+        assert_eq!(host_to_unicode("xn--bcher-kva.example"), "bücher.example");
+        assert_eq!(host_to_unicode("XN--BCHER-KVA.example"), "bücher.example");
+        assert_eq!(host_to_unicode("example.com"), "example.com");
+        assert_eq!(host_to_unicode("xn--"), "xn--");
+        assert_eq!(host_to_unicode("xn--a.example"), "xn--a.example");
+        assert_eq!(host_to_unicode("[::1]"), "[::1]");
+    }
+}
@@ -0,0 +1,256 @@
+use std::borrow::Cow;
+
+use bytes::Bytes;
+
+use super::percent;
+use super::scheme::default_port;
+use super::Uri;
+
+/// Normalizes a `Uri` following the syntax-based normalizations of RFC 3986,
+/// section 6.2.2, and the scheme-based normalizations of section 6.2.3.
+pub(super) fn normalize(uri: &Uri) -> Uri {
+    let mut s = String::new();
+
+    let scheme = uri.scheme_str().map(str::to_ascii_lowercase);
+    if let Some(ref scheme) = scheme {
+        s.push_str(scheme);
+        s.push_str("://");
+    }
+
+    if let Some(authority) = uri.authority() {
+        let authority = authority.as_str();
+        let host_port = match authority.rfind('@') {
+            Some(i) => {
+                s.push_str(&normalize_percent_encoding(&authority[..i]));
+                s.push('@');
+                &authority[i + 1..]
+            }
+            None => authority,
+        };
+
+        // Colons in IPv6 literals are not port separators.
+        let host_end = match host_port.rfind(']') {
+            Some(i) => i + 1,
+            None => host_port.find(':').unwrap_or(host_port.len()),
+        };
+        let host = host_port[..host_end].to_ascii_lowercase();
+        s.push_str(&normalize_percent_encoding(&host));
+
+        // Without a host, the port is kept as-is: an authority with neither
+        // isn't valid.
+        let port = host_port[host_end..].trim_start_matches(':');
+        let default = scheme.as_ref().and_then(|scheme| default_port(scheme));
+        if host.is_empty() {
+            s.push_str(&host_port[host_end..]);
+        } else if !port.is_empty() && (default.is_none() || port.parse().ok() != default) {
+            s.push(':');
+            s.push_str(port);
+        }
+    }
+
+    if uri.path_and_query().is_some() {
+        let p_and_q = &uri.path_and_query;
+        let path = remove_dot_segments(&normalize_percent_encoding(p_and_q.raw_path()));
+        if path.is_empty()
+            && (scheme.as_deref() == Some("http") || scheme.as_deref() == Some("https"))
+        {
+            s.push('/');
+        }
+        s.push_str(&path);
+
+        if let Some(query) = p_and_q.query() {
+            s.push('?');
+            s.push_str(&normalize_percent_encoding(query));
+        }
+
+        if let Some(fragment) = p_and_q.fragment() {
+            s.push('#');
+            s.push_str(&normalize_percent_encoding(fragment));
+        }
+    }
+
+    // The normalizations above keep the `Uri` valid, but an unusual one that
+    // doesn't survive them is better left as it is than a panic.
+    Uri::from_shared_with_fragment(Bytes::from(s)).unwrap_or_else(|_| uri.clone())
+}
+
+/// Uppercases the hex digits of percent-encodings, and decodes those of
+/// unreserved characters.
+fn normalize_percent_encoding(input: &str) -> Cow<'_, str> {
+    if !input.contains('%') {
+        return Cow::Borrowed(input);
+    }
+
+    let bytes = input.as_bytes();
+    let mut normalized = String::with_capacity(input.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = if bytes[i] == b'%' && i + 2 < bytes.len() {
+            percent::hex(bytes[i + 1])
+                .and_then(|hi| percent::hex(bytes[i + 2]).map(|lo| hi << 4 | lo))
+        } else {
+            None
+        };
+        match escape {
+            Some(b) if !percent::EncodeSet::UNRESERVED.encodes(b) => {
+                normalized.push(b as char);
+                i += 3;
+            }
+            Some(_) => {
+                normalized.push('%');
+                normalized.push(bytes[i + 1].to_ascii_uppercase() as char);
+                normalized.push(bytes[i + 2].to_ascii_uppercase() as char);
+                i += 3;
+            }
+            None => {
+                normalized.push(bytes[i] as char);
+                i += 1;
+            }
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+/// Removes the `.` and `..` segments of a path, as described in RFC 3986,
+/// section 5.2.4.
+pub(super) fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    let mut output = String::with_capacity(path.len());
+
+    while !input.is_empty() {
+        if input.starts_with("../") {
+            input = &input[3..];
+        } else if input.starts_with("./") || input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") {
+            input = &input[3..];
+            pop_segment(&mut output);
+        } else if input == "/.." {
+            input = "/";
+            pop_segment(&mut output);
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = if input.starts_with('/') { 1 } else { 0 };
+            let end = input[start..].find('/').map_or(input.len(), |i| i + start);
+            output.push_str(&input[..end]);
+            input = &input[end..];
+        }
+    }
+
+    output
+}
+
+fn pop_segment(output: &mut String) {
+    let last = output.rfind('/').unwrap_or(0);
+    output.truncate(last);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(s: &str) -> String {
+        s.parse::<Uri>().unwrap().normalize().to_string()
+    }
+
+    #[test]
+    fn rfc3986_examples() {
+        assert_eq!(
+            normalized("HTTP://www.Example.com/"),
+            "http://www.example.com/"
+        );
+        assert_eq!(
+            normalized("eXAMPLE://a/./b/../b/%63/%7bfoo%7d"),
+            "example://a/b/c/%7Bfoo%7D"
+        );
+        for &uri in &[
+            "http://example.com",
+            "http://example.com/",
+            "http://example.com:/",
+            "http://example.com:80/",
+        ] {
+            assert_eq!(normalized(uri), "http://example.com/", "{}", uri);
+        }
+    }
+
+    #[test]
+    fn authority() {
+        assert_eq!(
+            normalized("https://User%3a%41@Example.COM:443/"),
+            "https://User%3AA@example.com/"
+        );
+        assert_eq!(
+            normalized("https://Example.com:80/"),
+            "https://example.com:80/"
+        );
+        assert_eq!(normalized("http://[::FFFF:1]:80/"), "http://[::ffff:1]/");
+        assert_eq!(normalized("http://[::1]:8080/"), "http://[::1]:8080/");
+        assert_eq!(normalized("foo://Example.com:80"), "foo://example.com:80/");
+        assert_eq!(normalized("Example.com:443"), "example.com:443");
+    }
+
+    #[test]
+    fn empty_host() {
+        let cases = [
+            ("http://:/x", "http://:/x"),
+            ("http://x@:80/", "http://x@:80/"),
+            ("HTTP://X%3a@:/", "http://X%3A@:/"),
+            (":", ":"),
+            ("x@:", "x@:"),
+            ("mailto:x@:", "mailto:x@:"),
+        ];
+        for &(input, output) in &cases {
+            let uri: Uri = input.parse().unwrap();
+            assert_eq!(uri.normalize(), output, "{}", input);
+            assert!(uri.equivalent(&uri), "{}", input);
+        }
+    }
+
+    #[test]
+    fn path_and_query() {
+        assert_eq!(
+            normalized("/a/%2e%2E/b/%7euser/%2f?q=%7e%2a"),
+            "/b/~user/%2F?q=~%2A"
+        );
+        assert_eq!(normalized("/a/./b/../../../c?x=/../y"), "/c?x=/../y");
+        assert_eq!(normalized("/100%/%zz"), "/100%/%zz");
+        assert_eq!(normalized("*"), "*");
+        assert_eq!(normalized("https://a?q"), "https://a/?q");
+
+        let uri = Uri::parse_with_fragment("HTTP://A/./b#%7e%2f").unwrap();
+        assert_eq!(uri.normalize().to_string(), "http://a/b#~%2F");
+    }
+
+    #[test]
+    fn equivalence() {
+        let a: Uri = "HTTP://Example.com:80/a/../%7Eb?%3f".parse().unwrap();
+        let b: Uri = "http://example.com/~b?%3F".parse().unwrap();
+        let c: Uri = "http://example.com/~b?%3f&".parse().unwrap();
+        assert!(a.equivalent(&b));
+        assert!(b.equivalent(&a));
+        assert!(!a.equivalent(&c));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn dot_segments() {
+        let cases = [
+            ("/a/b/c/./../../g", "/a/g"),
+            ("mid/content=5/../6", "mid/6"),
+            ("/..", "/"),
+            ("/../a", "/a"),
+            ("/a/..", "/"),
+            ("/a/.", "/a/"),
+            ("/a//../b", "/a/b"),
+            ("../../a", "a"),
+            (".", ""),
+            ("/a/.b/..c", "/a/.b/..c"),
+        ];
+        for &(input, output) in &cases {
+            assert_eq!(remove_dot_segments(input), output, "{}", input);
+        }
+    }
+}
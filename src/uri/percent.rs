@@ -0,0 +1,362 @@
+//! Percent-encoding and decoding of URI components.
+//!
+//! The components of a `Uri` may only contain a limited set of characters,
+//! and each component reserves some of them as delimiters. Arbitrary input is
+//! made safe to use in a component by percent-encoding every other byte with
+//! [`encode`], using the [`EncodeSet`] for that component. [`decode`] reverses
+//! the encoding.
+//!
+//! # Examples
+//!
+//! ```
+//! use http::uri::percent::{self, EncodeSet};
+//!
+//! let segment = percent::encode("a/b c", EncodeSet::PATH_SEGMENT);
+//! assert_eq!(segment, "a%2Fb%20c");
+//!
+//! assert_eq!(percent::decode(&segment).unwrap(), "a/b c");
+//! ```
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::str;
+
+/// The set of bytes that are left as-is when percent-encoding a component.
+///
+/// Every byte outside of the set, including all non-ASCII bytes, is encoded
+/// as `%XY`. The predefined sets leave the characters that are valid in a
+/// component, minus that component's delimiters, unencoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeSet {
+    // Bit `b` is set if ASCII byte `b` is left unencoded.
+    allowed: u128,
+}
+
+/// An error resulting from decoding a malformed percent-encoded string.
+pub struct InvalidPercentEncoding {
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    InvalidEscape,
+    InvalidUtf8,
+}
+
+const UNRESERVED: u128 =
+    ascii(b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-._~");
+const SUB_DELIMS: u128 = ascii(b"!$&'()*+,;=");
+
+const fn ascii(chars: &[u8]) -> u128 {
+    let mut set = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        set |= 1 << chars[i];
+        i += 1;
+    }
+    set
+}
+
+// ===== impl EncodeSet =====
+
+impl EncodeSet {
+    /// Leaves only unreserved characters (`A-Z a-z 0-9 - . _ ~`) unencoded.
+    ///
+    /// Data encoded with this set can be used in any component.
+    pub const UNRESERVED: EncodeSet = EncodeSet {
+        allowed: UNRESERVED,
+    };
+
+    /// The set for a single segment of a path.
+    ///
+    /// Encodes `/`, so the segment can't be split into several.
+    pub const PATH_SEGMENT: EncodeSet = EncodeSet {
+        allowed: UNRESERVED | SUB_DELIMS | ascii(b":@"),
+    };
+
+    /// The set for a key or a value in a `key=value&...` query.
+    ///
+    /// Encodes `&`, `=` and `+`, which would otherwise be read as delimiters
+    /// or, in the case of `+`, as a space.
+    pub const QUERY_PAIR: EncodeSet = EncodeSet {
+        allowed: (UNRESERVED | SUB_DELIMS | ascii(b":@/?")) & !ascii(b"&=+"),
+    };
+
+    /// The set for the user name or password of an authority's userinfo.
+    ///
+    /// Encodes `:`, which separates the user name from the password.
+    pub const USERINFO: EncodeSet = EncodeSet {
+        allowed: UNRESERVED | SUB_DELIMS,
+    };
+
+    /// The set for a fragment.
+    pub const FRAGMENT: EncodeSet = EncodeSet {
+        allowed: UNRESERVED | SUB_DELIMS | ascii(b":@/?"),
+    };
+
+    // Leaves every unreserved and reserved character unencoded, for input
+    // whose delimiters are meant as such.
+    pub(super) const URI: EncodeSet = EncodeSet {
+        allowed: UNRESERVED | SUB_DELIMS | ascii(b":/?#[]@"),
+    };
+
+    /// Returns a copy of this set that also encodes `byte`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use http::uri::percent::{self, EncodeSet};
+    ///
+    /// let set = EncodeSet::PATH_SEGMENT.add(b'@');
+    /// assert_eq!(percent::encode("me@host", set), "me%40host");
+    /// ```
+    pub const fn add(self, byte: u8) -> EncodeSet {
+        if byte < 128 {
+            EncodeSet {
+                allowed: self.allowed & !(1 << byte),
+            }
+        } else {
+            self
+        }
+    }
+
+    /// Returns `true` if `byte` is percent-encoded by this set.
+    pub fn encodes(&self, byte: u8) -> bool {
+        byte >= 128 || self.allowed & (1 << byte) == 0
+    }
+}
+
+// ===== encoding =====
+
+/// Percent-encodes every byte of `input` that is not allowed by `set`.
+///
+/// The input is returned as-is if there is nothing to encode.
+///
+/// # Examples
+///
+/// ```
+/// use http::uri::percent::{self, EncodeSet};
+///
+/// assert_eq!(percent::encode("café & crème", EncodeSet::QUERY_PAIR), "caf%C3%A9%20%26%20cr%C3%A8me");
+/// assert_eq!(percent::encode("plain", EncodeSet::QUERY_PAIR), "plain");
+/// ```
+pub fn encode(input: &str, set: EncodeSet) -> Cow<'_, str> {
+    encode_bytes(input.as_bytes(), set)
+}
+
+// Percent-encodes a single path segment. The dot-segments `.` and `..` are
+// encoded too, so that they aren't removed, along with the segment before
+// `..`, when a reference is resolved (RFC 3986, Section 5.2.4).
+pub(crate) fn encode_segment(segment: &str) -> Cow<'_, str> {
+    match segment {
+        "." => Cow::Borrowed("%2E"),
+        ".." => Cow::Borrowed("%2E%2E"),
+        _ => encode(segment, EncodeSet::PATH_SEGMENT),
+    }
+}
+
+/// Percent-encodes every byte of `input` that is not allowed by `set`.
+///
+/// Unlike [`encode`], the input doesn't need to be UTF-8.
+pub fn encode_bytes(input: &[u8], set: EncodeSet) -> Cow<'_, str> {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let first = match input.iter().position(|&b| set.encodes(b)) {
+        Some(i) => i,
+        None => {
+            // Only bytes in the set, which are all ASCII, are left.
+            return Cow::Borrowed(str::from_utf8(input).expect("ASCII is valid UTF-8"));
+        }
+    };
+
+    let mut encoded = String::with_capacity(input.len() + 8);
+    encoded.push_str(str::from_utf8(&input[..first]).expect("ASCII is valid UTF-8"));
+    for &b in &input[first..] {
+        if set.encodes(b) {
+            encoded.push('%');
+            encoded.push(HEX[usize::from(b >> 4)] as char);
+            encoded.push(HEX[usize::from(b & 0xf)] as char);
+        } else {
+            encoded.push(b as char);
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+// ===== decoding =====
+
+/// Decodes every `%XY` escape in `input`, requiring the result to be UTF-8.
+///
+/// The input is returned as-is if there is nothing to decode.
+///
+/// # Errors
+///
+/// Returns an error if a `%` isn't followed by two hex digits, or if the
+/// decoded bytes aren't valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use http::uri::percent;
+///
+/// assert_eq!(percent::decode("caf%C3%A9").unwrap(), "café");
+/// assert!(percent::decode("100%").is_err());
+/// assert!(percent::decode("%FF").is_err());
+/// ```
+pub fn decode(input: &str) -> Result<Cow<'_, str>, InvalidPercentEncoding> {
+    match decode_bytes(input.as_bytes())? {
+        Cow::Borrowed(_) => Ok(Cow::Borrowed(input)),
+        Cow::Owned(bytes) => String::from_utf8(bytes)
+            .map(Cow::Owned)
+            .map_err(|_| InvalidPercentEncoding::new(ErrorKind::InvalidUtf8)),
+    }
+}
+
+/// Decodes every `%XY` escape in `input`.
+///
+/// The input is returned as-is if there is nothing to decode.
+///
+/// # Errors
+///
+/// Returns an error if a `%` isn't followed by two hex digits.
+pub fn decode_bytes(input: &[u8]) -> Result<Cow<'_, [u8]>, InvalidPercentEncoding> {
+    let first = match input.iter().position(|&b| b == b'%') {
+        Some(i) => i,
+        None => return Ok(Cow::Borrowed(input)),
+    };
+
+    let mut decoded = Vec::with_capacity(input.len());
+    decoded.extend_from_slice(&input[..first]);
+    let mut rest = &input[first..];
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let escape = match tail {
+                [hi, lo, ..] => hex(*hi).and_then(|hi| hex(*lo).map(|lo| hi << 4 | lo)),
+                _ => None,
+            };
+            match escape {
+                Some(byte) => decoded.push(byte),
+                None => return Err(InvalidPercentEncoding::new(ErrorKind::InvalidEscape)),
+            }
+            rest = &tail[2..];
+        } else {
+            decoded.push(b);
+            rest = tail;
+        }
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+pub(super) fn hex(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        _ => None,
+    }
+}
+
+// ===== impl InvalidPercentEncoding =====
+
+impl InvalidPercentEncoding {
+    fn new(kind: ErrorKind) -> InvalidPercentEncoding {
+        InvalidPercentEncoding { kind }
+    }
+
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::InvalidEscape => "invalid percent-encoded escape",
+            ErrorKind::InvalidUtf8 => "percent-decoded data is not valid UTF-8",
+        }
+    }
+}
+
+impl fmt::Debug for InvalidPercentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidPercentEncoding")
+            .field(&self.kind)
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidPercentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for InvalidPercentEncoding {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_sets() {
+        let all: String = (0x20u8..0x7f).map(char::from).collect();
+        assert_eq!(
+            encode(&all, EncodeSet::PATH_SEGMENT),
+            "%20!%22%23$%25&'()*+,-.%2F0123456789:;%3C=%3E%3F@ABCDEFGHIJKLMNOPQRSTUVWXYZ%5B%5C%5D%5E_%60abcdefghijklmnopqrstuvwxyz%7B%7C%7D~"
+        );
+        assert_eq!(
+            encode(&all, EncodeSet::QUERY_PAIR),
+            "%20!%22%23$%25%26'()*%2B,-./0123456789:;%3C%3D%3E?@ABCDEFGHIJKLMNOPQRSTUVWXYZ%5B%5C%5D%5E_%60abcdefghijklmnopqrstuvwxyz%7B%7C%7D~"
+        );
+        assert_eq!(encode("user:pass@", EncodeSet::USERINFO), "user%3Apass%40");
+        assert_eq!(encode("a/b?c#d", EncodeSet::FRAGMENT), "a/b?c%23d");
+        assert_eq!(encode("a/b", EncodeSet::UNRESERVED), "a%2Fb");
+        assert_eq!(
+            encode("\u{0}\u{7f}é", EncodeSet::UNRESERVED),
+            "%00%7F%C3%A9"
+        );
+        assert_eq!(encode_bytes(b"\xff", EncodeSet::UNRESERVED), "%FF");
+    }
+
+    #[test]
+    fn encoded_components_are_valid() {
+        use crate::uri::PathAndQuery;
+        use std::convert::TryFrom;
+
+        let all: String = (0u8..0x80).map(char::from).collect();
+        let path = format!(
+            "/{}?{}={}",
+            encode(&all, EncodeSet::PATH_SEGMENT),
+            encode(&all, EncodeSet::QUERY_PAIR),
+            encode(&all, EncodeSet::QUERY_PAIR)
+        );
+        let path = PathAndQuery::try_from(path.as_str()).unwrap();
+        let mut parts = path.query().unwrap().split('=');
+        assert_eq!(decode(parts.next().unwrap()).unwrap(), all);
+        assert_eq!(decode(parts.next().unwrap()).unwrap(), all);
+        assert_eq!(parts.next(), None);
+    }
+
+    #[test]
+    fn borrows_when_unchanged() {
+        assert!(matches!(
+            encode("abc", EncodeSet::UNRESERVED),
+            Cow::Borrowed("abc")
+        ));
+        assert!(matches!(decode("abc"), Ok(Cow::Borrowed("abc"))));
+        assert!(matches!(decode_bytes(b"abc"), Ok(Cow::Borrowed(b"abc"))));
+    }
+
+    #[test]
+    fn decodes() {
+        assert_eq!(decode("%41%62c").unwrap(), "Abc");
+        assert_eq!(decode("caf%c3%a9").unwrap(), "café");
+        assert_eq!(decode("a+b").unwrap(), "a+b");
+        assert_eq!(decode_bytes(b"%ff%00").unwrap(), &b"\xff\x00"[..]);
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for &input in &["%", "%4", "%4g", "%%41", "a%zz", "%C3"] {
+            assert!(decode(input).is_err(), "{:?}", input);
+        }
+        assert_eq!(decode("%").unwrap_err().kind, ErrorKind::InvalidEscape);
+        assert_eq!(decode("%C3").unwrap_err().kind, ErrorKind::InvalidUtf8);
+        assert!(decode_bytes(b"%C3").is_ok());
+    }
+}
@@ -0,0 +1,303 @@
+use std::borrow::Cow;
+use std::str;
+
+use super::percent::{self, EncodeSet};
+
+/// An iterator over the decoded `(key, value)` pairs of a query.
+///
+/// Pairs are decoded following the `application/x-www-form-urlencoded`
+/// rules: `+` is a space, malformed percent-encodings are kept as-is, and
+/// invalid UTF-8 is replaced with `U+FFFD`. Empty pairs are skipped, and a
+/// pair without `=` has an empty value.
+///
+/// This `struct` is created by `Uri::query_pairs` and
+/// `PathAndQuery::query_pairs`.
+///
+/// # Examples
+///
+/// ```
+/// # use http::Uri;
+/// let uri: Uri = "/search?q=rust+http&lang=en&lang=fr&flag".parse().unwrap();
+/// let pairs: Vec<_> = uri.query_pairs().collect();
+///
+/// assert_eq!(pairs, [
+///     ("q".into(), "rust http".into()),
+///     ("lang".into(), "en".into()),
+///     ("lang".into(), "fr".into()),
+///     ("flag".into(), "".into()),
+/// ]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct QueryPairs<'a> {
+    segments: Option<str::Split<'a, char>>,
+}
+
+/// A builder for `key=value&...` query strings.
+///
+/// Keys and values are percent-encoded, so any string can be used.
+///
+/// # Examples
+///
+/// ```
+/// # use http::uri::QueryBuilder;
+/// let query = QueryBuilder::new()
+///     .pair("q", "rust & http")
+///     .pair("page", "2")
+///     .build();
+///
+/// assert_eq!(query, "q=rust%20%26%20http&page=2");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct QueryBuilder {
+    query: String,
+}
+
+// ===== impl QueryPairs =====
+
+impl<'a> QueryPairs<'a> {
+    pub(super) fn new(query: Option<&'a str>) -> QueryPairs<'a> {
+        QueryPairs {
+            segments: query.map(|query| query.split('&')),
+        }
+    }
+}
+
+impl<'a> Iterator for QueryPairs<'a> {
+    type Item = (Cow<'a, str>, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let segments = self.segments.as_mut()?;
+        loop {
+            let segment = segments.next()?;
+            if !segment.is_empty() {
+                let (key, value) = split_pair(segment);
+                return Some((decode(key), decode(value)));
+            }
+        }
+    }
+}
+
+// ===== impl QueryBuilder =====
+
+impl QueryBuilder {
+    /// Creates a new, empty `QueryBuilder`.
+    #[inline]
+    pub fn new() -> QueryBuilder {
+        QueryBuilder::default()
+    }
+
+    /// Appends a `key=value` pair, percent-encoding the key and the value.
+    pub fn pair<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        push_pair(&mut self.query, key.as_ref(), value.as_ref());
+        self
+    }
+
+    /// Consumes this builder, returning the query string without the
+    /// leading `?`.
+    pub fn build(self) -> String {
+        self.query
+    }
+}
+
+// ===== editing =====
+
+/// Appends an encoded `key=value` pair to a query.
+pub(super) fn push_pair(query: &mut String, key: &str, value: &str) {
+    if !query.is_empty() && !query.ends_with('&') {
+        query.push('&');
+    }
+    query.push_str(&percent::encode(key, EncodeSet::QUERY_PAIR));
+    query.push('=');
+    query.push_str(&percent::encode(value, EncodeSet::QUERY_PAIR));
+}
+
+/// Replaces the first pair with `key` in a query, and removes the others.
+///
+/// The pair is appended if there is none with `key`.
+pub(super) fn replace_pair(query: Option<&str>, key: &str, value: &str) -> String {
+    let mut replaced = String::new();
+    let mut found = false;
+    for (i, segment) in query.into_iter().flat_map(|q| q.split('&')).enumerate() {
+        if has_key(segment, key) {
+            if found {
+                continue;
+            }
+            found = true;
+            if i > 0 {
+                replaced.push('&');
+            }
+            push_pair(&mut replaced, key, value);
+        } else {
+            if i > 0 {
+                replaced.push('&');
+            }
+            replaced.push_str(segment);
+        }
+    }
+    if !found {
+        push_pair(&mut replaced, key, value);
+    }
+    replaced
+}
+
+/// Removes every pair with `key` from a query.
+///
+/// Returns `None` if nothing is left of the query.
+pub(super) fn remove_pairs<'a>(query: Option<&'a str>, key: &str) -> Option<Cow<'a, str>> {
+    let query = query?;
+    if !query.split('&').any(|segment| has_key(segment, key)) {
+        return Some(Cow::Borrowed(query));
+    }
+
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|segment| !has_key(segment, key))
+        .collect();
+    if kept.is_empty() {
+        None
+    } else {
+        Some(Cow::Owned(kept.join("&")))
+    }
+}
+
+fn has_key(segment: &str, key: &str) -> bool {
+    !segment.is_empty() && decode(split_pair(segment).0) == key
+}
+
+fn split_pair(segment: &str) -> (&str, &str) {
+    match segment.find('=') {
+        Some(i) => (&segment[..i], &segment[i + 1..]),
+        None => (segment, ""),
+    }
+}
+
+// Decodes leniently, as browsers do for `application/x-www-form-urlencoded`.
+fn decode(input: &str) -> Cow<'_, str> {
+    if !input.contains(&['+', '%'][..]) {
+        return Cow::Borrowed(input);
+    }
+
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match (percent::hex(bytes[i + 1]), percent::hex(bytes[i + 2])) {
+                    (Some(hi), Some(lo)) => {
+                        decoded.push(hi << 4 | lo);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uri::{PathAndQuery, Uri};
+
+    fn pairs(query: &str) -> Vec<(String, String)> {
+        QueryPairs::new(Some(query))
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect()
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn decodes_pairs() {
+        assert_eq!(pairs(""), []);
+        assert_eq!(pairs("&&"), []);
+        assert_eq!(pairs("a=1&b"), [pair("a", "1"), pair("b", "")]);
+        assert_eq!(pairs("a=b=c"), [pair("a", "b=c")]);
+        assert_eq!(pairs("=x"), [pair("", "x")]);
+        assert_eq!(pairs("k+1=a+b%2Bc"), [pair("k 1", "a b+c")]);
+        assert_eq!(pairs("q=caf%C3%A9"), [pair("q", "café")]);
+        assert_eq!(
+            pairs("q=100%&r=%zz%4"),
+            [pair("q", "100%"), pair("r", "%zz%4")]
+        );
+        assert_eq!(pairs("q=%FF"), [pair("q", "\u{fffd}")]);
+        assert!(QueryPairs::new(None).next().is_none());
+    }
+
+    #[test]
+    fn builds_queries() {
+        let query = QueryBuilder::new()
+            .pair("a b", "c&d=e+f")
+            .pair("", "")
+            .pair("é", "/?")
+            .build();
+        assert_eq!(query, "a%20b=c%26d%3De%2Bf&=&%C3%A9=/?");
+        assert_eq!(
+            pairs(&query),
+            [pair("a b", "c&d=e+f"), pair("", ""), pair("é", "/?")]
+        );
+        assert_eq!(QueryBuilder::new().build(), "");
+    }
+
+    #[test]
+    fn appends() {
+        let p = PathAndQuery::from_static("/p");
+        assert_eq!(p.append_query_pair("a", "1 2"), "/p?a=1%202");
+
+        let p = PathAndQuery::from_static("/p?");
+        assert_eq!(p.append_query_pair("a", "1"), "/p?a=1");
+
+        let p = PathAndQuery::from_static("/p?x=%7e&");
+        assert_eq!(p.append_query_pair("a", "1"), "/p?x=%7e&a=1");
+
+        let uri: Uri = "http://example.com".parse().unwrap();
+        assert_eq!(uri.append_query_pair("a", "1"), "http://example.com/?a=1");
+    }
+
+    #[test]
+    fn replaces() {
+        let p = PathAndQuery::from_static("/p?x=%7e&a=1&y&a=2&z=+");
+        assert_eq!(p.replace_query_pair("a", "3"), "/p?x=%7e&a=3&y&z=+");
+        assert_eq!(p.replace_query_pair("b", "3"), "/p?x=%7e&a=1&y&a=2&z=+&b=3");
+        assert_eq!(p.replace_query_pair("z", ""), "/p?x=%7e&a=1&y&a=2&z=");
+
+        let p = PathAndQuery::from_static("/p?%61=1&&b");
+        assert_eq!(p.replace_query_pair("a", "2"), "/p?a=2&&b");
+
+        let p = PathAndQuery::from_static("/p");
+        assert_eq!(p.replace_query_pair("a", "1"), "/p?a=1");
+    }
+
+    #[test]
+    fn removes() {
+        let p = PathAndQuery::from_static("/p?x=%7e&a=1&y&a+b=2&a");
+        assert_eq!(p.remove_query_pair("a"), "/p?x=%7e&y&a+b=2");
+        assert_eq!(p.remove_query_pair("a b"), "/p?x=%7e&a=1&y&a");
+        assert_eq!(p.remove_query_pair("b"), p);
+
+        let p = PathAndQuery::from_static("/p?a=1&a=2");
+        assert_eq!(p.remove_query_pair("a"), "/p");
+        assert_eq!(p.remove_query_pair("a").query(), None);
+
+        let p = PathAndQuery::from_static("/p?");
+        assert_eq!(p.remove_query_pair("a").query(), Some(""));
+    }
+
+    #[test]
+    fn leaves_authority_form_unchanged() {
+        let uri: Uri = "example.com:443".parse().unwrap();
+        assert_eq!(uri.append_query_pair("a", "1"), uri);
+        assert_eq!(uri.query_pairs().count(), 0);
+    }
+}
@@ -0,0 +1,468 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::{fmt, str};
+
+use bytes::Bytes;
+
+use super::normalize::remove_dot_segments;
+use super::{Authority, ErrorKind, InvalidUri, Uri};
+use crate::byte_str::ByteStr;
+
+/// A URI-reference, as defined in RFC 3986, section 4.1.
+///
+/// Unlike a `Uri`, a `UriRef` may be a relative reference, such as `../a?b`,
+/// `//example.com/x`, `?q` or `#frag`, and keeps its fragment. Relative
+/// references are resolved against a base `Uri` with [`Uri::join`].
+///
+/// # Examples
+///
+/// ```
+/// # use http::uri::UriRef;
+/// let reference: UriRef = "../a?b#c".parse().unwrap();
+///
+/// assert_eq!(reference.scheme_str(), None);
+/// assert_eq!(reference.path(), "../a");
+/// assert_eq!(reference.query(), Some("b"));
+/// assert_eq!(reference.fragment(), Some("c"));
+/// ```
+#[derive(Clone)]
+pub struct UriRef {
+    data: ByteStr,
+    // Index of the `:` ending the scheme.
+    scheme_end: Option<usize>,
+    // Index of the end of the authority, which starts after `//`.
+    authority_end: Option<usize>,
+    // Index of the `?` starting the query.
+    query_start: Option<usize>,
+    // Index of the `#` starting the fragment.
+    fragment_start: Option<usize>,
+}
+
+// The components of a URI-reference, as used by the resolution algorithm.
+struct Components<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl UriRef {
+    /// Convert a `UriRef` from a static string.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the argument is an invalid URI-reference.
+    pub fn from_static(src: &'static str) -> Self {
+        UriRef::from_shared(Bytes::from_static(src.as_bytes())).unwrap()
+    }
+
+    fn from_shared(src: Bytes) -> Result<Self, InvalidUri> {
+        let scheme_end = parse_scheme(&src)?;
+        let mut pos = scheme_end.map_or(0, |i| i + 1);
+
+        let authority_end = if src[pos..].starts_with(b"//") {
+            pos += 2 + Authority::parse(&src[pos + 2..])?;
+            Some(pos)
+        } else {
+            None
+        };
+
+        let mut query_start = None;
+        let mut fragment_start = None;
+        for (i, &b) in src.iter().enumerate().skip(pos) {
+            match b {
+                b'?' if query_start.is_none() && fragment_start.is_none() => {
+                    query_start = Some(i);
+                }
+                b'#' if fragment_start.is_none() => {
+                    fragment_start = Some(i);
+                }
+                // The bytes allowed in a `PathAndQuery`.
+                0x21 | 0x22 | 0x24..=0x3B | 0x3D | 0x3F..=0x7E => {}
+                _ => return Err(ErrorKind::InvalidUriChar.into()),
+            }
+        }
+
+        Ok(UriRef {
+            // Every byte was checked to be ASCII.
+            data: unsafe { ByteStr::from_utf8_unchecked(src) },
+            scheme_end,
+            authority_end,
+            query_start,
+            fragment_start,
+        })
+    }
+
+    /// Get the scheme of this `UriRef`, if it has one.
+    ///
+    /// A `UriRef` with a scheme is an absolute URI, and is not resolved
+    /// against a base.
+    pub fn scheme_str(&self) -> Option<&str> {
+        self.scheme_end.map(|end| &self.data[..end])
+    }
+
+    /// Get the authority of this `UriRef`, if it has one.
+    ///
+    /// The authority may be empty, as in `file:///etc/hosts`.
+    pub fn authority(&self) -> Option<&str> {
+        self.authority_end
+            .map(|end| &self.data[self.authority_start()..end])
+    }
+
+    /// Get the path of this `UriRef`.
+    ///
+    /// The path may be empty or relative.
+    pub fn path(&self) -> &str {
+        let end = self
+            .query_start
+            .or(self.fragment_start)
+            .unwrap_or_else(|| self.data.len());
+        &self.data[self.path_start()..end]
+    }
+
+    /// Get the query of this `UriRef`, starting after the `?`.
+    pub fn query(&self) -> Option<&str> {
+        let end = self.fragment_start.unwrap_or_else(|| self.data.len());
+        self.query_start.map(|start| &self.data[start + 1..end])
+    }
+
+    /// Get the fragment of this `UriRef`, starting after the `#`.
+    pub fn fragment(&self) -> Option<&str> {
+        self.fragment_start.map(|start| &self.data[start + 1..])
+    }
+
+    /// Returns the `UriRef` as a string.
+    pub fn as_str(&self) -> &str {
+        &self.data[..]
+    }
+
+    fn authority_start(&self) -> usize {
+        self.scheme_end.map_or(0, |i| i + 1) + 2
+    }
+
+    fn path_start(&self) -> usize {
+        match self.authority_end {
+            Some(end) => end,
+            None => self.scheme_end.map_or(0, |i| i + 1),
+        }
+    }
+
+    fn components(&self) -> Components<'_> {
+        Components {
+            scheme: self.scheme_str(),
+            authority: self.authority(),
+            path: self.path(),
+            query: self.query(),
+            fragment: self.fragment(),
+        }
+    }
+}
+
+// Returns the index of the `:` ending the scheme, if there is one.
+fn parse_scheme(s: &[u8]) -> Result<Option<usize>, InvalidUri> {
+    let end = match s
+        .iter()
+        .position(|&b| matches!(b, b':' | b'/' | b'?' | b'#'))
+    {
+        Some(i) if s[i] == b':' => i,
+        _ => return Ok(None),
+    };
+
+    let valid = end > 0
+        && s[0].is_ascii_alphabetic()
+        && s[..end]
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'));
+    if valid {
+        Ok(Some(end))
+    } else {
+        // The first segment of a relative path can't contain a `:`.
+        Err(ErrorKind::InvalidScheme.into())
+    }
+}
+
+/// Resolves `reference` against `base`, as described in RFC 3986, section
+/// 5.2.
+pub(super) fn join(base: &Uri, reference: &UriRef) -> Result<Uri, InvalidUri> {
+    let base = Components {
+        scheme: base.scheme_str(),
+        authority: base.authority().map(Authority::as_str),
+        path: base.path(),
+        query: base.query(),
+        fragment: None,
+    };
+    let reference = reference.components();
+
+    // A `Uri` has either both a scheme and an authority, or neither.
+    let scheme = reference.scheme.or(base.scheme);
+    let authority = if reference.scheme.is_some() || reference.authority.is_some() {
+        reference.authority
+    } else {
+        base.authority
+    };
+    match (scheme, authority) {
+        (Some(_), None) => return Err(ErrorKind::AuthorityMissing.into()),
+        (None, Some(_)) => return Err(ErrorKind::SchemeMissing.into()),
+        _ => {}
+    }
+
+    Uri::from_shared_with_fragment(Bytes::from(resolve(&base, &reference)))
+}
+
+fn resolve(base: &Components<'_>, reference: &Components<'_>) -> String {
+    let scheme;
+    let authority;
+    let path;
+    let query;
+
+    if reference.scheme.is_some() {
+        scheme = reference.scheme;
+        authority = reference.authority;
+        path = remove_dot_segments(reference.path);
+        query = reference.query;
+    } else {
+        scheme = base.scheme;
+        if reference.authority.is_some() {
+            authority = reference.authority;
+            path = remove_dot_segments(reference.path);
+            query = reference.query;
+        } else {
+            authority = base.authority;
+            if reference.path.is_empty() {
+                path = base.path.to_string();
+                query = reference.query.or(base.query);
+            } else {
+                if reference.path.starts_with('/') {
+                    path = remove_dot_segments(reference.path);
+                } else {
+                    path = remove_dot_segments(&merge(base, reference.path));
+                }
+                query = reference.query;
+            }
+        }
+    }
+
+    // Recompose the components, as described in section 5.3.
+    let mut s = String::new();
+    if let Some(scheme) = scheme {
+        s.push_str(scheme);
+        s.push(':');
+    }
+    if let Some(authority) = authority {
+        s.push_str("//");
+        s.push_str(authority);
+    }
+    s.push_str(&path);
+    if let Some(query) = query {
+        s.push('?');
+        s.push_str(query);
+    }
+    if let Some(fragment) = reference.fragment {
+        s.push('#');
+        s.push_str(fragment);
+    }
+    s
+}
+
+// Merges a relative path with the path of the base, as described in section
+// 5.2.3.
+fn merge(base: &Components<'_>, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{}", path);
+    }
+    match base.path.rfind('/') {
+        Some(i) => format!("{}{}", &base.path[..=i], path),
+        None => path.to_string(),
+    }
+}
+
+impl FromStr for UriRef {
+    type Err = InvalidUri;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, InvalidUri> {
+        UriRef::try_from(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for UriRef {
+    type Error = InvalidUri;
+
+    #[inline]
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        UriRef::from_shared(Bytes::copy_from_slice(s.as_bytes()))
+    }
+}
+
+impl TryFrom<String> for UriRef {
+    type Error = InvalidUri;
+
+    #[inline]
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        UriRef::from_shared(Bytes::from(s))
+    }
+}
+
+impl PartialEq for UriRef {
+    fn eq(&self, other: &UriRef) -> bool {
+        self.data == other.data
+    }
+}
+
+impl Eq for UriRef {}
+
+impl PartialEq<str> for UriRef {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for UriRef {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Debug for UriRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for UriRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "http://a/b/c/d;p?q";
+
+    fn resolved(reference: &str) -> String {
+        let base = UriRef::from_static(BASE);
+        let reference: UriRef = reference.parse().unwrap();
+        resolve(&base.components(), &reference.components())
+    }
+
+    #[test]
+    fn rfc3986_normal_examples() {
+        let cases = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+        ];
+        for &(reference, target) in &cases {
+            assert_eq!(resolved(reference), target, "{}", reference);
+        }
+    }
+
+    #[test]
+    fn rfc3986_abnormal_examples() {
+        let cases = [
+            ("../../../g", "http://a/g"),
+            ("../../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            ("g?y/./x", "http://a/b/c/g?y/./x"),
+            ("g?y/../x", "http://a/b/c/g?y/../x"),
+            ("g#s/./x", "http://a/b/c/g#s/./x"),
+            ("g#s/../x", "http://a/b/c/g#s/../x"),
+            ("http:g", "http:g"),
+        ];
+        for &(reference, target) in &cases {
+            assert_eq!(resolved(reference), target, "{}", reference);
+        }
+    }
+
+    #[test]
+    fn components() {
+        let r = UriRef::from_static("foo://user@example.com:8042/over/there?name=ferret#nose");
+        assert_eq!(r.scheme_str(), Some("foo"));
+        assert_eq!(r.authority(), Some("user@example.com:8042"));
+        assert_eq!(r.path(), "/over/there");
+        assert_eq!(r.query(), Some("name=ferret"));
+        assert_eq!(r.fragment(), Some("nose"));
+
+        let r = UriRef::from_static("file:///etc/hosts");
+        assert_eq!(r.authority(), Some(""));
+        assert_eq!(r.path(), "/etc/hosts");
+
+        let r = UriRef::from_static("urn:example:animal?x#a?b");
+        assert_eq!(r.authority(), None);
+        assert_eq!(r.path(), "example:animal");
+        assert_eq!(r.query(), Some("x"));
+        assert_eq!(r.fragment(), Some("a?b"));
+
+        let r = UriRef::from_static("");
+        assert_eq!(r.scheme_str(), None);
+        assert_eq!(r.path(), "");
+        assert_eq!(r.query(), None);
+        assert_eq!(r.fragment(), None);
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for &s in &["a b", "1a:b", ":b", "//a:b:c/", "/\u{e9}", "<a>"] {
+            assert!(s.parse::<UriRef>().is_err(), "{}", s);
+        }
+        assert!("./1a:b".parse::<UriRef>().is_ok());
+    }
+
+    #[test]
+    fn join() {
+        let base: Uri = BASE.parse().unwrap();
+        let join = |r: &str| base.join(&r.parse().unwrap()).unwrap();
+        assert_eq!(join("../g?y#s"), "http://a/b/g?y#s");
+        assert_eq!(join("../g?y#s").fragment(), Some("s"));
+        assert_eq!(join("//g/x"), "http://g/x");
+        assert_eq!(join("https://b/"), "https://b/");
+        assert!(base.join(&"g:h".parse().unwrap()).is_err());
+        assert!(base
+            .join(&"mailto:user@example.com".parse().unwrap())
+            .is_err());
+
+        let base: Uri = "https://example.com".parse().unwrap();
+        assert_eq!(
+            base.join(&"a/b".parse().unwrap()).unwrap(),
+            "https://example.com/a/b"
+        );
+
+        let base: Uri = "/a/b?c".parse().unwrap();
+        assert_eq!(base.join(&"?d".parse().unwrap()).unwrap(), "/a/b?d");
+        assert!(base.join(&"//g/x".parse().unwrap()).is_err());
+    }
+}
@@ -5,7 +5,7 @@ use std::str::FromStr;
 
 use bytes::Bytes;
 
-use super::{ErrorKind, InvalidUri};
+use super::{ErrorKind, InvalidUri, Uri};
 use crate::byte_str::ByteStr;
 
 /// Represents the scheme component of a URI
@@ -25,6 +25,42 @@ pub(super) enum Scheme2<T = Box<ByteStr>> {
 pub(super) enum Protocol {
     Http,
     Https,
+    Ws,
+    Wss,
+}
+
+/// A table of the default ports of schemes, and of whether they are secure.
+///
+/// `Scheme::default_port` and `Scheme::is_secure` only know of well-known
+/// schemes. A `SchemeRegistry` starts with those, and applications can
+/// register their own.
+///
+/// Schemes are compared case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// # use http::Uri;
+/// # use http::uri::{Scheme, SchemeRegistry};
+/// let mut registry = SchemeRegistry::new();
+/// registry.register("gopher", 70).register_secure("gemini", 1965);
+///
+/// let uri: Uri = "gemini://example.org/".parse().unwrap();
+/// assert_eq!(registry.port_or_default(&uri), Some(1965));
+/// assert!(registry.is_secure(uri.scheme().unwrap()));
+///
+/// assert_eq!(registry.default_port(&Scheme::HTTPS), Some(443));
+/// ```
+#[derive(Clone, Debug)]
+pub struct SchemeRegistry {
+    entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    scheme: String,
+    port: u16,
+    secure: bool,
 }
 
 impl Scheme {
@@ -38,12 +74,67 @@ impl Scheme {
         inner: Scheme2::Standard(Protocol::Https),
     };
 
+    /// WebSocket protocol scheme
+    pub const WS: Scheme = Scheme {
+        inner: Scheme2::Standard(Protocol::Ws),
+    };
+
+    /// WebSocket protocol over TLS.
+    pub const WSS: Scheme = Scheme {
+        inner: Scheme2::Standard(Protocol::Wss),
+    };
+
     pub(super) fn empty() -> Self {
         Scheme {
             inner: Scheme2::None,
         }
     }
 
+    /// Returns the port used when a URI with this scheme has none.
+    ///
+    /// Only the well-known schemes `http`, `https`, `ws`, `wss` and `ftp`
+    /// have one; use a [`SchemeRegistry`] for others.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::*;
+    /// assert_eq!(Scheme::HTTPS.default_port(), Some(443));
+    /// assert_eq!(Scheme::WS.default_port(), Some(80));
+    ///
+    /// let scheme: Scheme = "FTP".parse().unwrap();
+    /// assert_eq!(scheme.default_port(), Some(21));
+    ///
+    /// let scheme: Scheme = "gopher".parse().unwrap();
+    /// assert_eq!(scheme.default_port(), None);
+    /// ```
+    pub fn default_port(&self) -> Option<u16> {
+        match self.inner {
+            Scheme2::Standard(Protocol::Http) | Scheme2::Standard(Protocol::Ws) => Some(80),
+            Scheme2::Standard(Protocol::Https) | Scheme2::Standard(Protocol::Wss) => Some(443),
+            _ => default_port(self.as_str()),
+        }
+    }
+
+    /// Returns `true` if this is a well-known scheme that runs over TLS,
+    /// `https` or `wss`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::*;
+    /// assert!(Scheme::HTTPS.is_secure());
+    /// assert!(Scheme::WSS.is_secure());
+    /// assert!(!Scheme::HTTP.is_secure());
+    /// ```
+    pub fn is_secure(&self) -> bool {
+        match self.inner {
+            Scheme2::Standard(Protocol::Https) | Scheme2::Standard(Protocol::Wss) => true,
+            Scheme2::Standard(_) => false,
+            _ => is_secure(self.as_str()),
+        }
+    }
+
     /// Return a str representation of the scheme
     ///
     /// # Examples
@@ -61,6 +152,8 @@ impl Scheme {
         match self.inner {
             Standard(Http) => "http",
             Standard(Https) => "https",
+            Standard(Ws) => "ws",
+            Standard(Wss) => "wss",
             Other(ref v) => &v[..],
             None => unreachable!(),
         }
@@ -132,6 +225,8 @@ impl PartialEq for Scheme {
         match (&self.inner, &other.inner) {
             (&Standard(Http), &Standard(Http)) => true,
             (&Standard(Https), &Standard(Https)) => true,
+            (&Standard(Ws), &Standard(Ws)) => true,
+            (&Standard(Wss), &Standard(Wss)) => true,
             (&Other(ref a), &Other(ref b)) => a.eq_ignore_ascii_case(b),
             (&None, _) | (_, &None) => unreachable!(),
             _ => false,
@@ -173,6 +268,8 @@ impl Hash for Scheme {
             Scheme2::None => (),
             Scheme2::Standard(Protocol::Http) => state.write_u8(1),
             Scheme2::Standard(Protocol::Https) => state.write_u8(2),
+            Scheme2::Standard(Protocol::Ws) => state.write_u8(3),
+            Scheme2::Standard(Protocol::Wss) => state.write_u8(4),
             Scheme2::Other(ref other) => {
                 other.len().hash(state);
                 for &b in other.as_bytes() {
@@ -192,9 +289,103 @@ impl<T> Scheme2<T> {
     }
 }
 
+// ===== impl SchemeRegistry =====
+
+impl SchemeRegistry {
+    /// Creates a registry of the well-known schemes, as known by
+    /// `Scheme::default_port` and `Scheme::is_secure`.
+    pub fn new() -> SchemeRegistry {
+        let mut registry = SchemeRegistry {
+            entries: Vec::new(),
+        };
+        for &(scheme, port) in WELL_KNOWN {
+            registry.insert(scheme, port, is_secure(scheme));
+        }
+        registry
+    }
+
+    /// Registers a scheme and its default port.
+    ///
+    /// This replaces any previous registration of the scheme.
+    pub fn register(&mut self, scheme: &str, default_port: u16) -> &mut Self {
+        self.insert(scheme, default_port, false);
+        self
+    }
+
+    /// Registers a secure scheme, such as one that runs over TLS, and its
+    /// default port.
+    ///
+    /// This replaces any previous registration of the scheme.
+    pub fn register_secure(&mut self, scheme: &str, default_port: u16) -> &mut Self {
+        self.insert(scheme, default_port, true);
+        self
+    }
+
+    /// Returns the default port of a scheme, if it is registered.
+    pub fn default_port(&self, scheme: &Scheme) -> Option<u16> {
+        self.get(scheme.as_str()).map(|entry| entry.port)
+    }
+
+    /// Returns `true` if a scheme is registered as secure.
+    pub fn is_secure(&self, scheme: &Scheme) -> bool {
+        self.get(scheme.as_str())
+            .map_or(false, |entry| entry.secure)
+    }
+
+    /// Returns the port of a `Uri`, or the default port of its scheme if it
+    /// has none.
+    pub fn port_or_default(&self, uri: &Uri) -> Option<u16> {
+        uri.port_u16().or_else(|| self.default_port(uri.scheme()?))
+    }
+
+    fn insert(&mut self, scheme: &str, port: u16, secure: bool) {
+        let entry = Entry {
+            scheme: scheme.to_ascii_lowercase(),
+            port,
+            secure,
+        };
+        match self.entries.iter().position(|e| e.scheme == entry.scheme) {
+            Some(i) => self.entries[i] = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    fn get(&self, scheme: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.scheme.eq_ignore_ascii_case(scheme))
+    }
+}
+
+impl Default for SchemeRegistry {
+    fn default() -> SchemeRegistry {
+        SchemeRegistry::new()
+    }
+}
+
+const WELL_KNOWN: &[(&str, u16)] = &[
+    ("http", 80),
+    ("https", 443),
+    ("ws", 80),
+    ("wss", 443),
+    ("ftp", 21),
+];
+
+// The default port of a well-known scheme, compared case-insensitively.
+pub(super) fn default_port(scheme: &str) -> Option<u16> {
+    WELL_KNOWN
+        .iter()
+        .find(|&&(name, _)| name.eq_ignore_ascii_case(scheme))
+        .map(|&(_, port)| port)
+}
+
+fn is_secure(scheme: &str) -> bool {
+    scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("wss")
+}
+
 // Require the scheme to not be too long in order to enable further
 // optimizations later.
-const MAX_SCHEME_LEN: usize = 64;
+pub(crate) const MAX_SCHEME_LEN: usize = 64;
 
 // scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
 //
@@ -240,6 +431,8 @@ impl Scheme2<usize> {
         match s {
             b"http" => Ok(Protocol::Http.into()),
             b"https" => Ok(Protocol::Https.into()),
+            b"ws" => Ok(Protocol::Ws.into()),
+            b"wss" => Ok(Protocol::Wss.into()),
             _ => {
                 if s.len() > MAX_SCHEME_LEN {
                     return Err(ErrorKind::SchemeTooLong.into());
@@ -281,6 +474,20 @@ impl Scheme2<usize> {
             }
         }
 
+        if s.len() >= 5 {
+            // Check for WS
+            if s[..5].eq_ignore_ascii_case(b"ws://") {
+                return Ok(Protocol::Ws.into());
+            }
+        }
+
+        if s.len() >= 6 {
+            // Check for WSs
+            if s[..6].eq_ignore_ascii_case(b"wss://") {
+                return Ok(Protocol::Wss.into());
+            }
+        }
+
         if s.len() > 3 {
             for i in 0..s.len() {
                 let b = s[i];
@@ -320,6 +527,8 @@ impl Protocol {
         match *self {
             Protocol::Http => 4,
             Protocol::Https => 5,
+            Protocol::Ws => 2,
+            Protocol::Wss => 3,
         }
     }
 }
@@ -357,6 +566,68 @@ mod test {
         Scheme::try_from([0xC0].as_ref()).expect_err("Unexpectly valid Scheme");
     }
 
+    #[test]
+    fn websocket_schemes() {
+        let uri: Uri = "WSS://example.com/chat".parse().unwrap();
+        assert_eq!(uri.scheme(), Some(&Scheme::WSS));
+        assert_eq!(uri.scheme_str(), Some("wss"));
+        assert_eq!(uri.host(), Some("example.com"));
+        assert_eq!(uri, "wss://example.com/chat");
+
+        let uri: Uri = "ws://example.com".parse().unwrap();
+        assert_eq!(uri.scheme(), Some(&Scheme::WS));
+        assert_eq!(scheme("ws"), Scheme::WS);
+        assert_eq!(scheme("wss"), Scheme::WSS);
+        assert_ne!(Scheme::WS, Scheme::WSS);
+        assert_ne!(Scheme::WS, Scheme::HTTP);
+        assert_eq!(&scheme("wsx"), "wsx");
+    }
+
+    #[test]
+    fn default_ports() {
+        let cases = [
+            ("http", Some(80)),
+            ("HTTPS", Some(443)),
+            ("ws", Some(80)),
+            ("Wss", Some(443)),
+            ("ftp", Some(21)),
+            ("gopher", None),
+        ];
+        for &(s, port) in &cases {
+            assert_eq!(scheme(s).default_port(), port, "{}", s);
+        }
+
+        assert!(scheme("HTTPS").is_secure());
+        assert!(scheme("wss").is_secure());
+        assert!(!scheme("ws").is_secure());
+        assert!(!scheme("ftp").is_secure());
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = SchemeRegistry::new();
+        assert_eq!(registry.default_port(&scheme("FTP")), Some(21));
+        assert_eq!(registry.default_port(&scheme("gemini")), None);
+
+        registry
+            .register_secure("Gemini", 1965)
+            .register("ftp", 2121)
+            .register("http", 8080);
+        assert_eq!(registry.default_port(&scheme("gemini")), Some(1965));
+        assert!(registry.is_secure(&scheme("GEMINI")));
+        assert_eq!(registry.default_port(&scheme("ftp")), Some(2121));
+        assert_eq!(registry.default_port(&Scheme::HTTP), Some(8080));
+        assert!(!registry.is_secure(&Scheme::HTTP));
+        assert!(registry.is_secure(&Scheme::WSS));
+
+        let uri: Uri = "http://example.com:81/".parse().unwrap();
+        assert_eq!(registry.port_or_default(&uri), Some(81));
+        let uri: Uri = "http://example.com/".parse().unwrap();
+        assert_eq!(registry.port_or_default(&uri), Some(8080));
+        let uri: Uri = "/path".parse().unwrap();
+        assert_eq!(registry.port_or_default(&uri), None);
+    }
+
     fn scheme(s: &str) -> Scheme {
         s.parse().expect(&format!("Invalid scheme: {}", s))
     }
@@ -0,0 +1,162 @@
+use std::borrow::Cow;
+use std::str;
+
+use super::percent;
+
+/// An iterator over the segments of a path, as they appear in the URI.
+///
+/// The leading `/` is skipped, so `/a/b` has the segments `a` and `b`. A
+/// trailing `/` ends the path with an empty segment, and the path `/` has
+/// no segments.
+///
+/// This `struct` is created by `Uri::raw_segments` and
+/// `PathAndQuery::raw_segments`.
+///
+/// # Examples
+///
+/// ```
+/// # use http::Uri;
+/// let uri: Uri = "/files/a%2Fb/?download".parse().unwrap();
+/// let segments: Vec<_> = uri.raw_segments().collect();
+///
+/// assert_eq!(segments, ["files", "a%2Fb", ""]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RawSegments<'a> {
+    segments: Option<str::Split<'a, char>>,
+}
+
+/// An iterator over the decoded segments of a path.
+///
+/// Segments are split like [`RawSegments`], then percent-decoded:
+/// malformed percent-encodings are kept as-is, and invalid UTF-8 is
+/// replaced with `U+FFFD`. Since `%2F` is decoded after splitting, a
+/// decoded segment may contain `/`.
+///
+/// This `struct` is created by `Uri::segments` and
+/// `PathAndQuery::segments`.
+///
+/// # Examples
+///
+/// ```
+/// # use http::Uri;
+/// let uri: Uri = "/files/a%2Fb/caf%C3%A9".parse().unwrap();
+/// let segments: Vec<_> = uri.segments().collect();
+///
+/// assert_eq!(segments, ["files", "a/b", "café"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Segments<'a> {
+    raw: RawSegments<'a>,
+}
+
+// ===== impl RawSegments =====
+
+impl<'a> RawSegments<'a> {
+    pub(super) fn new(path: &'a str) -> RawSegments<'a> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        RawSegments {
+            segments: if path.is_empty() {
+                None
+            } else {
+                Some(path.split('/'))
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for RawSegments<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.segments.as_mut()?.next()
+    }
+}
+
+impl<'a> DoubleEndedIterator for RawSegments<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.segments.as_mut()?.next_back()
+    }
+}
+
+// ===== impl Segments =====
+
+impl<'a> Segments<'a> {
+    pub(super) fn new(path: &'a str) -> Segments<'a> {
+        Segments {
+            raw: RawSegments::new(path),
+        }
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Cow<'a, str>> {
+        self.raw.next().map(decode)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Segments<'a> {
+    fn next_back(&mut self) -> Option<Cow<'a, str>> {
+        self.raw.next_back().map(decode)
+    }
+}
+
+fn decode(segment: &str) -> Cow<'_, str> {
+    match percent::decode_bytes(segment.as_bytes()) {
+        Ok(Cow::Borrowed(_)) | Err(_) => Cow::Borrowed(segment),
+        Ok(Cow::Owned(bytes)) => Cow::Owned(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+// Whether `segment` is `.` or `..`, possibly with percent-encoded dots.
+pub(super) fn is_dot_segment(segment: &str) -> bool {
+    let segment = segment.to_ascii_lowercase().replace("%2e", ".");
+    segment == "." || segment == ".."
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_segments() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("/", &[]),
+            ("*", &["*"]),
+            ("/a", &["a"]),
+            ("/a/", &["a", ""]),
+            ("/a//b", &["a", "", "b"]),
+            ("//", &["", ""]),
+        ];
+        for &(path, segments) in cases {
+            assert_eq!(
+                RawSegments::new(path).collect::<Vec<_>>(),
+                segments,
+                "{}",
+                path
+            );
+        }
+
+        let reversed: Vec<_> = RawSegments::new("/a/b/c").rev().collect();
+        assert_eq!(reversed, ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn decodes_segments() {
+        let segments: Vec<_> = Segments::new("/a%20b/100%/%FF/%2e%2E").collect();
+        assert_eq!(segments, ["a b", "100%", "\u{fffd}", ".."]);
+    }
+
+    #[test]
+    fn dot_segments() {
+        for &segment in &[".", "..", "%2e", "%2E.", ".%2e", "%2e%2E"] {
+            assert!(is_dot_segment(segment), "{}", segment);
+        }
+        for &segment in &["", "...", "a.", "%2e%2e%2e", "%2f"] {
+            assert!(!is_dot_segment(segment), "{}", segment);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bytes::Bytes;
+
+use super::scheme::Scheme2;
+use super::{Authority, PathAndQuery, Scheme, Uri};
+
+/// The form of a request target, as defined in RFC 9112, Section 3.2.
+///
+/// This is returned by `Uri::target_form`.
+///
+/// # Examples
+///
+/// ```
+/// # use http::uri::{TargetForm, Uri};
+/// let forms = [
+///     ("/where?q=now", TargetForm::Origin),
+///     ("http://www.example.org/pub/WWW/", TargetForm::Absolute),
+///     ("www.example.com:80", TargetForm::Authority),
+///     ("*", TargetForm::Asterisk),
+/// ];
+/// for &(target, form) in &forms {
+///     assert_eq!(target.parse::<Uri>().unwrap().target_form(), form);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TargetForm {
+    /// An absolute path and a query, such as `/where?q=now`, sent to an
+    /// origin server along with a `Host` header.
+    Origin,
+    /// An absolute URI, such as `http://www.example.org/pub/WWW/`, sent to a
+    /// proxy.
+    Absolute,
+    /// Only an authority, such as `www.example.com:80`, for `CONNECT`
+    /// requests.
+    Authority,
+    /// `*`, for server-wide `OPTIONS` requests.
+    Asterisk,
+}
+
+impl TargetForm {
+    pub(super) fn of(uri: &Uri) -> TargetForm {
+        if uri.scheme().is_some() {
+            TargetForm::Absolute
+        } else if uri.authority().is_some() {
+            TargetForm::Authority
+        } else if uri.path_and_query.as_str() == "*" {
+            TargetForm::Asterisk
+        } else {
+            TargetForm::Origin
+        }
+    }
+}
+
+/// Returns the origin-form of an absolute-form `uri`.
+///
+/// With `asterisk_if_empty`, for `OPTIONS` requests, an empty path and no
+/// query become `*` (RFC 9112, Section 3.2.4). The fragment is dropped.
+pub(crate) fn origin_form(uri: &Uri, asterisk_if_empty: bool) -> Uri {
+    let p_and_q = &uri.path_and_query;
+    let path_and_query =
+        if asterisk_if_empty && p_and_q.raw_path().is_empty() && p_and_q.query().is_none() {
+            PathAndQuery::star()
+        } else {
+            PathAndQuery::from_shared(Bytes::copy_from_slice(p_and_q.as_str().as_bytes()))
+                .expect("path and query are valid")
+        };
+    Uri {
+        scheme: Scheme {
+            inner: Scheme2::None,
+        },
+        authority: Authority::empty(),
+        path_and_query,
+    }
+}
+
+/// Returns the absolute-form of an origin-form or asterisk-form `uri`, for
+/// a request to `authority`.
+///
+/// `*` becomes an empty path. The fragment is dropped.
+pub(crate) fn absolute_form(uri: &Uri, scheme: Scheme, authority: Authority) -> Uri {
+    let path_and_query = match TargetForm::of(uri) {
+        TargetForm::Asterisk => PathAndQuery::empty(),
+        _ => PathAndQuery::from_shared(Bytes::copy_from_slice(
+            uri.path_and_query.as_str().as_bytes(),
+        ))
+        .expect("path and query are valid"),
+    };
+    Uri {
+        scheme,
+        authority,
+        path_and_query,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(s: &str) -> Uri {
+        Uri::parse_with_fragment(s).unwrap()
+    }
+
+    #[test]
+    fn converts_to_origin_form() {
+        let cases = [
+            ("http://a.example/b?c#d", false, "/b?c"),
+            ("http://a.example", false, "/"),
+            ("http://a.example", true, "*"),
+            ("http://a.example/", true, "/"),
+            ("http://a.example?b", true, "/?b"),
+        ];
+        for &(input, asterisk, output) in &cases {
+            let converted = origin_form(&uri(input), asterisk);
+            assert_eq!(converted, output, "{}", input);
+            assert_eq!(converted.fragment(), None, "{}", input);
+            assert_ne!(converted.target_form(), TargetForm::Absolute, "{}", input);
+        }
+    }
+
+    #[test]
+    fn converts_to_absolute_form() {
+        let authority = Authority::from_static("a.example:8080");
+        let cases = [
+            ("/b?c#d", "http://a.example:8080/b?c"),
+            ("/", "http://a.example:8080/"),
+            ("*", "http://a.example:8080"),
+        ];
+        for &(input, output) in &cases {
+            let converted = absolute_form(&uri(input), Scheme::HTTP, authority.clone());
+            assert_eq!(converted, output, "{}", input);
+            assert_eq!(converted.target_form(), TargetForm::Absolute, "{}", input);
+        }
+    }
+}
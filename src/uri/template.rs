@@ -0,0 +1,799 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::str::FromStr;
+
+use bytes::Bytes;
+
+use super::percent::{self, EncodeSet};
+use super::{InvalidUri, PathAndQuery, Uri};
+
+/// A URI Template, as defined in RFC 6570.
+///
+/// A template is a URI with `{...}` expressions, which are replaced by the
+/// values of their variables. All four levels of the RFC are supported: the
+/// operators `+ # . / ; ? &`, and the prefix (`:n`) and explode (`*`)
+/// modifiers.
+///
+/// # Examples
+///
+/// ```
+/// # use http::uri::{TemplateValue, UriTemplate};
+/// # use std::collections::HashMap;
+/// let template: UriTemplate = "/repos/{owner}/{repo}/issues{?state,labels*}".parse().unwrap();
+///
+/// let mut vars = HashMap::new();
+/// vars.insert("owner", TemplateValue::from("hyperium"));
+/// vars.insert("repo", "http".into());
+/// vars.insert("labels", vec!["bug", "help wanted"].into());
+///
+/// let path = template.expand_path_and_query(&vars).unwrap();
+/// assert_eq!(path, "/repos/hyperium/http/issues?labels=bug&labels=help%20wanted");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct UriTemplate {
+    src: String,
+    parts: Vec<Part>,
+}
+
+/// The value of a variable of a `UriTemplate`.
+///
+/// Empty lists and associative arrays are undefined, like a missing
+/// variable, while an empty string is defined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateValue {
+    /// A string.
+    String(String),
+    /// A list of strings.
+    List(Vec<String>),
+    /// An associative array of `(key, value)` pairs, in order.
+    Assoc(Vec<(String, String)>),
+}
+
+/// The variables to expand a `UriTemplate` with.
+///
+/// This is implemented for `HashMap`s and `BTreeMap`s of `TemplateValue`s
+/// keyed by strings.
+pub trait TemplateVars {
+    /// Returns the value of the variable `name`, or `None` if it is
+    /// undefined.
+    fn value(&self, name: &str) -> Option<&TemplateValue>;
+}
+
+/// An error resulting from parsing an invalid `UriTemplate`, or from
+/// expanding one with [`UriTemplate::try_expand`].
+pub struct InvalidUriTemplate {
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    InvalidLiteral,
+    UnclosedExpression,
+    InvalidOperator,
+    InvalidVarName,
+    InvalidPrefix,
+    PrefixOfComposite,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    // Already encoded.
+    Literal(String),
+    Expression(Operator, Vec<VarSpec>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operator {
+    Simple,
+    Reserved,
+    Fragment,
+    Label,
+    PathSegment,
+    PathParam,
+    Query,
+    QueryContinuation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct VarSpec {
+    name: String,
+    modifier: Modifier,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Modifier {
+    None,
+    Prefix(usize),
+    Explode,
+}
+
+// ===== impl UriTemplate =====
+
+impl UriTemplate {
+    /// Parses a template.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an expression isn't closed, has an invalid
+    /// operator, variable name or prefix, or if a literal has a character
+    /// that can't be in a URI even when percent-encoded, such as a space.
+    pub fn parse(src: &str) -> Result<UriTemplate, InvalidUriTemplate> {
+        let mut parts = Vec::new();
+        let mut rest = src;
+        while !rest.is_empty() {
+            if rest.starts_with('{') {
+                let end = rest.find('}').ok_or(ErrorKind::UnclosedExpression)?;
+                parts.push(parse_expression(&rest[1..end])?);
+                rest = &rest[end + 1..];
+            } else {
+                let end = rest.find('{').unwrap_or(rest.len());
+                parts.push(Part::Literal(parse_literal(&rest[..end])?));
+                rest = &rest[end..];
+            }
+        }
+        Ok(UriTemplate {
+            src: src.to_string(),
+            parts,
+        })
+    }
+
+    /// Returns the names of the variables of this template, in order of
+    /// appearance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::UriTemplate;
+    /// let template: UriTemplate = "{/path*}{?q,page}".parse().unwrap();
+    ///
+    /// assert_eq!(template.variables().collect::<Vec<_>>(), ["path", "q", "page"]);
+    /// ```
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts
+            .iter()
+            .flat_map(|part| match *part {
+                Part::Literal(_) => &[][..],
+                Part::Expression(_, ref specs) => &specs[..],
+            })
+            .map(|spec| &spec.name[..])
+    }
+
+    /// Expands this template to a string.
+    ///
+    /// The result is percent-encoded, but only a URI reference if the
+    /// template is one. Use [`UriTemplate::expand_uri`] or
+    /// [`UriTemplate::expand_path_and_query`] to validate it.
+    ///
+    /// A prefix modifier, such as in `{list:3}`, doesn't apply to a list or
+    /// an associative array, and is ignored. Use [`UriTemplate::try_expand`]
+    /// to treat it as an error instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::{TemplateValue, UriTemplate};
+    /// # use std::collections::BTreeMap;
+    /// let template: UriTemplate = "{x,y}{;list}{#keys*}".parse().unwrap();
+    ///
+    /// let mut vars = BTreeMap::new();
+    /// vars.insert("x", TemplateValue::from("a b"));
+    /// vars.insert("list", vec!["c", "d"].into());
+    /// vars.insert("keys", vec![("e", "/"), ("f", "")].into());
+    ///
+    /// assert_eq!(template.expand(&vars), "a%20b;list=c,d#e=/,f=");
+    /// ```
+    pub fn expand<V>(&self, vars: &V) -> String
+    where
+        V: TemplateVars + ?Sized,
+    {
+        let mut expanded = String::with_capacity(self.src.len());
+        for part in &self.parts {
+            match *part {
+                Part::Literal(ref literal) => expanded.push_str(literal),
+                Part::Expression(op, ref specs) => {
+                    expand_expression(&mut expanded, op, specs, vars)
+                }
+            }
+        }
+        expanded
+    }
+
+    /// Expands this template to a string, failing if a prefix modifier is
+    /// applied to a list or an associative array.
+    ///
+    /// RFC 6570 doesn't define the prefix of such values, and asks for an
+    /// error to be reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::{TemplateValue, UriTemplate};
+    /// # use std::collections::HashMap;
+    /// let template: UriTemplate = "{var:3}".parse().unwrap();
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("var", TemplateValue::from("value"));
+    /// assert_eq!(template.try_expand(&vars).unwrap(), "val");
+    ///
+    /// vars.insert("var", vec!["a", "b"].into());
+    /// assert!(template.try_expand(&vars).is_err());
+    /// assert_eq!(template.expand(&vars), "a,b");
+    /// ```
+    pub fn try_expand<V>(&self, vars: &V) -> Result<String, InvalidUriTemplate>
+    where
+        V: TemplateVars + ?Sized,
+    {
+        for part in &self.parts {
+            let specs = match *part {
+                Part::Literal(_) => continue,
+                Part::Expression(_, ref specs) => specs,
+            };
+            for spec in specs {
+                if let Modifier::Prefix(_) = spec.modifier {
+                    // Undefined variables are skipped, like in `expand`.
+                    let composite = match vars.value(&spec.name) {
+                        Some(&TemplateValue::String(_)) | None => false,
+                        Some(value) => is_defined(value),
+                    };
+                    if composite {
+                        return Err(ErrorKind::PrefixOfComposite.into());
+                    }
+                }
+            }
+        }
+        Ok(self.expand(vars))
+    }
+
+    /// Expands this template to a `Uri`.
+    ///
+    /// A fragment, such as one from a `{#var}` expression, is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expansion isn't a valid `Uri`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use http::uri::{TemplateValue, UriTemplate};
+    /// # use std::collections::HashMap;
+    /// let template: UriTemplate = "https://{host}/search{?q}".parse().unwrap();
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("host", TemplateValue::from("example.com"));
+    /// vars.insert("q", "caf\u{e9}".into());
+    ///
+    /// let uri = template.expand_uri(&vars).unwrap();
+    /// assert_eq!(uri, "https://example.com/search?q=caf%C3%A9");
+    /// ```
+    pub fn expand_uri<V>(&self, vars: &V) -> Result<Uri, InvalidUri>
+    where
+        V: TemplateVars + ?Sized,
+    {
+        Uri::from_shared_with_fragment(Bytes::from(self.expand(vars)))
+    }
+
+    /// Expands this template to a `PathAndQuery`.
+    ///
+    /// A fragment, such as one from a `{#var}` expression, is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expansion isn't a valid `PathAndQuery`.
+    pub fn expand_path_and_query<V>(&self, vars: &V) -> Result<PathAndQuery, InvalidUri>
+    where
+        V: TemplateVars + ?Sized,
+    {
+        PathAndQuery::from_shared_with_fragment(Bytes::from(self.expand(vars)))
+    }
+
+    /// Returns the source of this template.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.src
+    }
+}
+
+impl FromStr for UriTemplate {
+    type Err = InvalidUriTemplate;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UriTemplate::parse(s)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for UriTemplate {
+    type Error = InvalidUriTemplate;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        UriTemplate::parse(s)
+    }
+}
+
+impl fmt::Debug for UriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.src, f)
+    }
+}
+
+impl fmt::Display for UriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.src)
+    }
+}
+
+// ===== impl TemplateValue =====
+
+impl<'a> From<&'a str> for TemplateValue {
+    fn from(s: &'a str) -> Self {
+        TemplateValue::String(s.to_string())
+    }
+}
+
+impl From<String> for TemplateValue {
+    fn from(s: String) -> Self {
+        TemplateValue::String(s)
+    }
+}
+
+impl<'a> From<Vec<&'a str>> for TemplateValue {
+    fn from(list: Vec<&'a str>) -> Self {
+        TemplateValue::List(list.into_iter().map(String::from).collect())
+    }
+}
+
+impl From<Vec<String>> for TemplateValue {
+    fn from(list: Vec<String>) -> Self {
+        TemplateValue::List(list)
+    }
+}
+
+impl<'a> From<Vec<(&'a str, &'a str)>> for TemplateValue {
+    fn from(pairs: Vec<(&'a str, &'a str)>) -> Self {
+        TemplateValue::Assoc(
+            pairs
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl From<Vec<(String, String)>> for TemplateValue {
+    fn from(pairs: Vec<(String, String)>) -> Self {
+        TemplateValue::Assoc(pairs)
+    }
+}
+
+// ===== impl TemplateVars =====
+
+impl<K, S> TemplateVars for HashMap<K, TemplateValue, S>
+where
+    K: Borrow<str> + Hash + Eq,
+    S: BuildHasher,
+{
+    fn value(&self, name: &str) -> Option<&TemplateValue> {
+        self.get(name)
+    }
+}
+
+impl<K> TemplateVars for BTreeMap<K, TemplateValue>
+where
+    K: Borrow<str> + Ord,
+{
+    fn value(&self, name: &str) -> Option<&TemplateValue> {
+        self.get(name)
+    }
+}
+
+// ===== parsing =====
+
+fn parse_literal(literal: &str) -> Result<String, InvalidUriTemplate> {
+    let bytes = literal.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                if !is_pct_encoded(&bytes[i..]) {
+                    return Err(ErrorKind::InvalidLiteral.into());
+                }
+                i += 3;
+            }
+            b'\x00'..=b' '
+            | b'"'
+            | b'\''
+            | b'<'
+            | b'>'
+            | b'\\'
+            | b'^'
+            | b'`'
+            | b'|'
+            | b'}'
+            | b'\x7f' => return Err(ErrorKind::InvalidLiteral.into()),
+            _ => i += 1,
+        }
+    }
+    Ok(encode_reserved(literal))
+}
+
+fn parse_expression(expression: &str) -> Result<Part, InvalidUriTemplate> {
+    let op = match expression.as_bytes().first() {
+        Some(b'+') => Operator::Reserved,
+        Some(b'#') => Operator::Fragment,
+        Some(b'.') => Operator::Label,
+        Some(b'/') => Operator::PathSegment,
+        Some(b';') => Operator::PathParam,
+        Some(b'?') => Operator::Query,
+        Some(b'&') => Operator::QueryContinuation,
+        // Reserved for future extensions.
+        Some(b'=') | Some(b',') | Some(b'!') | Some(b'@') | Some(b'|') => {
+            return Err(ErrorKind::InvalidOperator.into())
+        }
+        _ => Operator::Simple,
+    };
+    let list = match op {
+        Operator::Simple => expression,
+        _ => &expression[1..],
+    };
+
+    let specs = list
+        .split(',')
+        .map(parse_varspec)
+        .collect::<Result<_, _>>()?;
+    Ok(Part::Expression(op, specs))
+}
+
+fn parse_varspec(spec: &str) -> Result<VarSpec, InvalidUriTemplate> {
+    let (name, modifier) = if let Some(name) = spec.strip_suffix('*') {
+        (name, Modifier::Explode)
+    } else if let Some(i) = spec.find(':') {
+        let max_len = &spec[i + 1..];
+        let valid = !max_len.is_empty()
+            && max_len.len() <= 4
+            && max_len.bytes().all(|b| b.is_ascii_digit())
+            && !max_len.starts_with('0');
+        if !valid {
+            return Err(ErrorKind::InvalidPrefix.into());
+        }
+        let max_len = max_len.parse().expect("prefix is 1 to 4 digits");
+        (&spec[..i], Modifier::Prefix(max_len))
+    } else {
+        (spec, Modifier::None)
+    };
+
+    // varname = varchar *( ["."] varchar )
+    let bytes = name.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if is_pct_encoded(&bytes[i..]) => i += 3,
+            b'.' if i > 0 && i + 1 < bytes.len() && bytes[i + 1] != b'.' => i += 1,
+            b if b.is_ascii_alphanumeric() || b == b'_' => i += 1,
+            _ => return Err(ErrorKind::InvalidVarName.into()),
+        }
+    }
+    if name.is_empty() {
+        return Err(ErrorKind::InvalidVarName.into());
+    }
+
+    Ok(VarSpec {
+        name: name.to_string(),
+        modifier,
+    })
+}
+
+fn is_pct_encoded(bytes: &[u8]) -> bool {
+    bytes.len() >= 3 && bytes[1].is_ascii_hexdigit() && bytes[2].is_ascii_hexdigit()
+}
+
+// ===== expansion =====
+
+impl Operator {
+    // The string before the first defined variable.
+    fn first(self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved => "",
+            Operator::Fragment => "#",
+            Operator::Label => ".",
+            Operator::PathSegment => "/",
+            Operator::PathParam => ";",
+            Operator::Query => "?",
+            Operator::QueryContinuation => "&",
+        }
+    }
+
+    // The separator between variables, and between exploded items.
+    fn separator(self) -> &'static str {
+        match self {
+            Operator::Simple | Operator::Reserved | Operator::Fragment => ",",
+            Operator::Label => ".",
+            Operator::PathSegment => "/",
+            Operator::PathParam => ";",
+            Operator::Query | Operator::QueryContinuation => "&",
+        }
+    }
+
+    // Whether values are `name=value` pairs.
+    fn is_named(self) -> bool {
+        matches!(
+            self,
+            Operator::PathParam | Operator::Query | Operator::QueryContinuation
+        )
+    }
+
+    // The string after the name of an empty value.
+    fn if_empty(self) -> &'static str {
+        match self {
+            Operator::Query | Operator::QueryContinuation => "=",
+            _ => "",
+        }
+    }
+
+    // Whether reserved characters and percent-encodings are kept as-is.
+    fn allows_reserved(self) -> bool {
+        matches!(self, Operator::Reserved | Operator::Fragment)
+    }
+
+    fn encode(self, value: &str) -> String {
+        if self.allows_reserved() {
+            encode_reserved(value)
+        } else {
+            percent::encode(value, EncodeSet::UNRESERVED).into_owned()
+        }
+    }
+}
+
+fn expand_expression<V>(out: &mut String, op: Operator, specs: &[VarSpec], vars: &V)
+where
+    V: TemplateVars + ?Sized,
+{
+    let mut first = true;
+    for spec in specs {
+        let value = match vars.value(&spec.name) {
+            Some(value) if is_defined(value) => value,
+            _ => continue,
+        };
+        out.push_str(if first { op.first() } else { op.separator() });
+        first = false;
+
+        match *value {
+            TemplateValue::String(ref s) => {
+                let s = match spec.modifier {
+                    Modifier::Prefix(max_len) => prefix(s, max_len),
+                    _ => s,
+                };
+                push_named(out, op, &spec.name, &op.encode(s));
+            }
+            TemplateValue::List(ref list) => {
+                if spec.modifier == Modifier::Explode {
+                    for (i, item) in list.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(op.separator());
+                        }
+                        push_named(out, op, &spec.name, &op.encode(item));
+                    }
+                } else {
+                    let items: Vec<String> = list.iter().map(|item| op.encode(item)).collect();
+                    push_named(out, op, &spec.name, &items.join(","));
+                }
+            }
+            TemplateValue::Assoc(ref pairs) => {
+                if spec.modifier == Modifier::Explode {
+                    for (i, (key, value)) in pairs.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(op.separator());
+                        }
+                        let key = op.encode(key);
+                        if op.is_named() {
+                            push_named(out, op, &key, &op.encode(value));
+                        } else {
+                            out.push_str(&key);
+                            out.push('=');
+                            out.push_str(&op.encode(value));
+                        }
+                    }
+                } else {
+                    let items: Vec<String> = pairs
+                        .iter()
+                        .map(|(key, value)| format!("{},{}", op.encode(key), op.encode(value)))
+                        .collect();
+                    push_named(out, op, &spec.name, &items.join(","));
+                }
+            }
+        }
+    }
+}
+
+fn is_defined(value: &TemplateValue) -> bool {
+    match *value {
+        TemplateValue::String(_) => true,
+        TemplateValue::List(ref list) => !list.is_empty(),
+        TemplateValue::Assoc(ref pairs) => !pairs.is_empty(),
+    }
+}
+
+// Pushes `name=value` for named operators, and only `value` otherwise.
+fn push_named(out: &mut String, op: Operator, name: &str, value: &str) {
+    if op.is_named() {
+        out.push_str(name);
+        if value.is_empty() {
+            out.push_str(op.if_empty());
+            return;
+        }
+        out.push('=');
+    }
+    out.push_str(value);
+}
+
+// The first `max_len` characters of `s`.
+fn prefix(s: &str, max_len: usize) -> &str {
+    match s.char_indices().nth(max_len) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
+// Encodes everything but unreserved and reserved characters, and
+// percent-encodings.
+fn encode_reserved(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut encoded = String::with_capacity(s.len());
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && is_pct_encoded(&bytes[i..]) {
+            encoded.push_str(&percent::encode(&s[start..i], EncodeSet::URI));
+            encoded.push_str(&s[i..i + 3]);
+            i += 3;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    encoded.push_str(&percent::encode(&s[start..], EncodeSet::URI));
+    encoded
+}
+
+// ===== impl InvalidUriTemplate =====
+
+impl InvalidUriTemplate {
+    fn s(&self) -> &str {
+        match self.kind {
+            ErrorKind::InvalidLiteral => "invalid literal in uri template",
+            ErrorKind::UnclosedExpression => "unclosed expression in uri template",
+            ErrorKind::InvalidOperator => "invalid operator in uri template",
+            ErrorKind::InvalidVarName => "invalid variable name in uri template",
+            ErrorKind::InvalidPrefix => "invalid prefix modifier in uri template",
+            ErrorKind::PrefixOfComposite => "prefix modifier of a composite value in uri template",
+        }
+    }
+}
+
+impl From<ErrorKind> for InvalidUriTemplate {
+    fn from(kind: ErrorKind) -> InvalidUriTemplate {
+        InvalidUriTemplate { kind }
+    }
+}
+
+impl fmt::Debug for InvalidUriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InvalidUriTemplate")
+            .field(&self.kind)
+            .finish()
+    }
+}
+
+impl fmt::Display for InvalidUriTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.s().fmt(f)
+    }
+}
+
+impl Error for InvalidUriTemplate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(template: &str, vars: &[(&str, TemplateValue)]) -> String {
+        let vars: HashMap<&str, TemplateValue> = vars.iter().cloned().collect();
+        UriTemplate::parse(template).unwrap().expand(&vars)
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for &template in &[
+            "{",
+            "{var",
+            "}",
+            "a b",
+            "100%",
+            "%zz",
+            "{}",
+            "{=var}",
+            "{|var}",
+            "{var,}",
+            "{.var.}",
+            "{a..b}",
+            "{a b}",
+            "{var:0}",
+            "{var:10000}",
+            "{var:}",
+            "{var:x}",
+            "{var*:3}",
+            "{%2}",
+        ] {
+            assert!(UriTemplate::parse(template).is_err(), "{}", template);
+        }
+
+        for &template in &["", "/a%2Fb?c=d#e", "{a.b,c_1,%C3%A9}", "{var:9999}", "/é"] {
+            assert!(UriTemplate::parse(template).is_ok(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn encodes_literals() {
+        assert_eq!(expand("/é{x}", &[]), "/%C3%A9");
+        assert_eq!(expand("/a%2fb[c]!", &[]), "/a%2fb[c]!");
+    }
+
+    #[test]
+    fn expands_prefixes() {
+        let vars = [("var", TemplateValue::from("d\u{e9}j\u{e0}"))];
+        assert_eq!(expand("{var:2}", &vars), "d%C3%A9");
+        assert_eq!(expand("{?var:3}", &vars), "?var=d%C3%A9j");
+        assert_eq!(expand("{+var:9}", &vars), "d%C3%A9j%C3%A0");
+
+        // The prefix doesn't apply to lists.
+        let vars = [("list", TemplateValue::from(vec!["abc", "def"]))];
+        assert_eq!(expand("{list:1}", &vars), "abc,def");
+    }
+
+    #[test]
+    fn try_expand_rejects_prefixes_of_composite_values() {
+        let vars: HashMap<&str, TemplateValue> = vec![
+            ("var", TemplateValue::from("abc")),
+            ("list", vec!["abc", "def"].into()),
+            ("keys", vec![("a", "b")].into()),
+            ("empty", Vec::<&str>::new().into()),
+        ]
+        .into_iter()
+        .collect();
+        let try_expand = |template| UriTemplate::parse(template).unwrap().try_expand(&vars);
+
+        assert_eq!(try_expand("{var:1}{/list*}").unwrap(), "a/abc/def");
+        assert!(try_expand("{list:1}").is_err());
+        assert!(try_expand("{+keys:1}").is_err());
+
+        // Undefined variables are skipped, whatever their modifier.
+        assert_eq!(try_expand("{empty:1}{undefined:1}").unwrap(), "");
+    }
+
+    #[test]
+    fn expands_uris() {
+        let template = UriTemplate::parse("http://example.com{/path*}{?q}{#frag}").unwrap();
+        let vars: BTreeMap<String, TemplateValue> = vec![
+            ("path".to_string(), vec!["a", "b c"].into()),
+            ("q".to_string(), "1+1".into()),
+            ("frag".to_string(), "top".into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let uri = template.expand_uri(&vars).unwrap();
+        assert_eq!(uri.path(), "/a/b%20c");
+        assert_eq!(uri.query(), Some("q=1%2B1"));
+        assert_eq!(uri.fragment(), Some("top"));
+
+        let template = UriTemplate::parse("{/path*}{?q}").unwrap();
+        let p = template.expand_path_and_query(&vars).unwrap();
+        assert_eq!(p, "/a/b%20c?q=1%2B1");
+
+        let template = UriTemplate::parse("http://{path}/").unwrap();
+        assert!(template.expand_uri(&vars).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use std::str::FromStr;
 
-use super::{ErrorKind, InvalidUri, Port, Uri, URI_CHARS};
+use bytes::Bytes;
+
+use super::{ErrorKind, InvalidUri, PathAndQuery, Port, Uri, URI_CHARS};
+use crate::Limits;
 
 #[test]
 fn test_char_table() {
@@ -441,15 +444,50 @@ fn test_uri_parse_error() {
 }
 
 #[test]
-fn test_max_uri_len() {
-    let mut uri = vec![];
-    uri.extend(b"http://localhost/");
-    uri.extend(vec![b'a'; 70 * 1024]);
+fn test_long_uri() {
+    let path = "a".repeat(70 * 1024);
+    let query = "b".repeat(70 * 1024);
+    let uri = format!("http://localhost/{}?{}#c", path, query);
 
-    let uri = String::from_utf8(uri).unwrap();
-    let res: Result<Uri, InvalidUri> = uri.parse();
+    let res: Uri = uri.parse().unwrap();
+    assert_eq!(res.path().len(), path.len() + 1);
+    assert_eq!(res.query(), Some(&query[..]));
+
+    let res = Uri::parse_with_fragment(&uri).unwrap();
+    assert_eq!(res.query(), Some(&query[..]));
+    assert_eq!(res.fragment(), Some("c"));
+    assert_eq!(res.to_string(), uri);
+}
+
+#[test]
+fn test_uri_size() {
+    // The `u32` offsets of a `PathAndQuery` fit in the padding after its
+    // bytes, so it is no larger than it was with a single `u16` offset.
+    assert_eq!(
+        std::mem::size_of::<PathAndQuery>(),
+        std::mem::size_of::<(Bytes, u16)>()
+    );
+}
+
+#[test]
+fn test_limits() {
+    let limits = Limits::new().max_uri_len(24).max_scheme_len(4);
 
-    assert_eq!(res.unwrap_err().0, ErrorKind::TooLong);
+    let uri = Uri::from_maybe_shared_with_limits("http://example.com/", &limits).unwrap();
+    assert_eq!(uri, "http://example.com/");
+
+    let err = Uri::from_maybe_shared_with_limits("http://example.com/search", &limits).unwrap_err();
+    assert_eq!(err.0, ErrorKind::TooLong);
+    let err = Uri::from_maybe_shared_with_limits("https://example.com/", &limits).unwrap_err();
+    assert_eq!(err.0, ErrorKind::SchemeTooLong);
+    assert!(err.is_too_long());
+
+    let err = Uri::from_maybe_shared_with_limits("http://exa mple/", &limits).unwrap_err();
+    assert!(!err.is_too_long());
+
+    // The scheme is checked before the rest of the URI is parsed.
+    let err = Uri::from_maybe_shared_with_limits("abcde://exa mple/", &limits).unwrap_err();
+    assert_eq!(err.0, ErrorKind::SchemeTooLong);
 }
 
 #[test]
@@ -517,3 +555,68 @@ fn test_partial_eq_path_with_terminating_questionmark() {
 
     assert_eq!(uri, a);
 }
+
+#[test]
+fn test_parse_with_fragment() {
+    let cases = vec![
+        ("/a?b#c", "/a", Some("b"), Some("c")),
+        ("/a#", "/a", None, Some("")),
+        ("/a#b?c#", "/a", None, None),
+        ("http://hyper.rs#top", "/", None, Some("top")),
+        ("http://hyper.rs/x?#a/b?c", "/x", Some(""), Some("a/b?c")),
+        ("/no-fragment", "/no-fragment", None, None),
+    ];
+
+    for (s, path, query, fragment) in cases {
+        let uri = match Uri::parse_with_fragment(s) {
+            Ok(uri) => uri,
+            Err(_) if fragment.is_none() && s.contains('#') => continue,
+            Err(e) => panic!("{:?}: {}", s, e),
+        };
+        assert_eq!(uri.path(), path, "{}", s);
+        assert_eq!(uri.query(), query, "{}", s);
+        assert_eq!(uri.fragment(), fragment, "{}", s);
+        assert_eq!(uri, s);
+        assert_eq!(Uri::parse_with_fragment(uri.to_string()).unwrap(), uri);
+
+        let stripped = Uri::from_str(s).unwrap();
+        assert_eq!(stripped.fragment(), None);
+        let stripped_path_and_query = stripped.path_and_query().unwrap();
+        assert_eq!(
+            uri.path_and_query().unwrap(),
+            stripped_path_and_query.as_str()
+        );
+        if fragment.is_some() {
+            assert_ne!(uri, stripped);
+        }
+
+        let parts = uri.clone().into_parts();
+        assert_eq!(Uri::from_parts(parts).unwrap(), uri);
+    }
+
+    assert!(Uri::parse_with_fragment("/a#b c").is_err());
+    assert!(Uri::parse_with_fragment("hyper.rs:80#x").is_err());
+}
+
+#[test]
+fn test_socket_addr() {
+    let cases = vec![
+        ("http://127.0.0.1/", Some("127.0.0.1:80")),
+        ("HTTPS://10.0.0.1/", Some("10.0.0.1:443")),
+        ("wss://[::1]/", Some("[::1]:443")),
+        ("foo://127.0.0.1:9/", Some("127.0.0.1:9")),
+        ("foo://127.0.0.1/", None),
+        ("http://[fe80::1%253]:81/", Some("[fe80::1%3]:81")),
+        ("http://[fe80::1%25eth0]/", None),
+        ("http://[v1.x]/", None),
+        ("http://localhost/", None),
+        ("127.0.0.1:443", Some("127.0.0.1:443")),
+        ("/path", None),
+    ];
+
+    for (uri, addr) in cases {
+        let uri = Uri::from_str(uri).unwrap();
+        let addr = addr.map(|addr| addr.parse().unwrap());
+        assert_eq!(uri.socket_addr(), addr, "{}", uri);
+    }
+}
@@ -0,0 +1,57 @@
+//! Runs the `data:` URL test cases in `tests/fixtures`.
+
+use std::fs;
+
+use http::uri::DataUrl;
+use serde_json::Value;
+
+const FIXTURES: &str = "tests/fixtures/data-urls";
+
+fn fixture(name: &str) -> Vec<Value> {
+    let path = format!("{}/{}", FIXTURES, name);
+    let tests: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    tests.as_array().expect("array").clone()
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+    value
+        .as_array()
+        .expect("array")
+        .iter()
+        .map(|b| b.as_u64().expect("byte") as u8)
+        .collect()
+}
+
+#[test]
+fn data_url_tests() {
+    let tests = fixture("data-urls.json");
+    for test in &tests {
+        let input = test[0].as_str().unwrap();
+        let result = DataUrl::parse(input);
+        match test[1].as_str() {
+            Some(media_type) => {
+                let url = result.unwrap_or_else(|e| panic!("{:?} failed: {}", input, e));
+                assert_eq!(url.media_type(), media_type, "{:?}", input);
+                assert_eq!(url.body(), &bytes(&test[2])[..], "{:?}", input);
+            }
+            None => assert!(result.is_err(), "{:?} should fail", input),
+        }
+    }
+    assert!(!tests.is_empty(), "no fixtures found");
+}
+
+#[test]
+fn base64_tests() {
+    let tests = fixture("base64.json");
+    for test in &tests {
+        let input = format!("data:;base64,{}", test[0].as_str().unwrap());
+        let result = DataUrl::parse(&input);
+        if test[1].is_null() {
+            assert!(result.is_err(), "{:?} should fail", input);
+        } else {
+            let url = result.unwrap_or_else(|e| panic!("{:?} failed: {}", input, e));
+            assert_eq!(url.body(), &bytes(&test[1])[..], "{:?}", input);
+        }
+    }
+    assert!(!tests.is_empty(), "no fixtures found");
+}
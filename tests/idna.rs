@@ -0,0 +1,165 @@
+//! Runs the UTS #46 conformance tests in `tests/fixtures/idna` against the
+//! `idna` feature.
+#![cfg(feature = "idna")]
+
+use std::char;
+use std::convert::TryFrom;
+use std::fs;
+
+use http::uri::Authority;
+
+const FIXTURE: &str = "tests/fixtures/idna/IdnaTestV2.txt";
+
+struct Test {
+    line: usize,
+    source: String,
+    to_unicode: String,
+    to_unicode_status: Vec<String>,
+    to_ascii: String,
+    to_ascii_status: Vec<String>,
+}
+
+// Parses the lines of the fixture, following its own format description.
+// Only the non-transitional columns are kept, since that is the processing
+// used for hosts.
+fn tests() -> Vec<Test> {
+    let data = fs::read_to_string(FIXTURE).unwrap();
+    let mut tests = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split(';').map(str::trim).collect();
+        let source = unescape(columns[0]);
+        let to_unicode = or(unescape(columns[1]), &source);
+        let to_unicode_status = status(columns[2]).unwrap_or_else(Vec::new);
+        let to_ascii = or(unescape(columns[3]), &to_unicode);
+        let to_ascii_status = status(columns[4]).unwrap_or_else(|| to_unicode_status.clone());
+
+        tests.push(Test {
+            line: i + 1,
+            source,
+            to_unicode,
+            to_unicode_status,
+            to_ascii,
+            to_ascii_status,
+        });
+    }
+    tests
+}
+
+fn or(value: String, default: &str) -> String {
+    if value.is_empty() {
+        default.to_string()
+    } else {
+        value
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let code = u32::from_str_radix(&hex, 16).unwrap();
+                // Lone surrogates can't be in a `String`; they are invalid
+                // anyway.
+                unescaped.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            Some('\\') => unescaped.push('\\'),
+            other => panic!("invalid escape {:?}", other),
+        }
+    }
+    unescaped
+}
+
+// Returns `None` for a blank status, which defaults to another column.
+fn status(s: &str) -> Option<Vec<String>> {
+    if s.is_empty() {
+        return None;
+    }
+    let s = s.trim_start_matches('[').trim_end_matches(']');
+    Some(
+        s.split(',')
+            .map(str::trim)
+            .filter(|code| !code.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+// The contextual rules (C*) are optional for lookups, and hyphens in the
+// third and fourth positions (V2) are allowed, as everyone does.
+fn is_ignored(code: &str) -> bool {
+    code.starts_with('C') || code == "V2"
+}
+
+// Whether the source can be checked as the host of an authority: other
+// ASCII characters would be delimiters, or rejected before any mapping.
+fn is_host(source: &str) -> bool {
+    source
+        .bytes()
+        .all(|b| !b.is_ascii() || b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+}
+
+#[test]
+fn to_ascii() {
+    let mut checked = 0;
+    for test in tests() {
+        if test.source.is_ascii() || !is_host(&test.source) {
+            continue;
+        }
+        if test.to_ascii_status.iter().any(|code| is_ignored(code)) {
+            continue;
+        }
+
+        let result = Authority::try_from(test.source.as_str());
+        if test.to_ascii_status.is_empty() {
+            let authority = result
+                .unwrap_or_else(|e| panic!("line {}: {:?} failed: {}", test.line, test.source, e));
+            assert_eq!(authority.host(), test.to_ascii, "line {}", test.line);
+        } else {
+            assert!(
+                result.is_err(),
+                "line {}: {:?} should fail with {:?}",
+                test.line,
+                test.source,
+                test.to_ascii_status
+            );
+        }
+        checked += 1;
+    }
+    assert!(checked > 2000, "only {} tests checked", checked);
+}
+
+#[test]
+fn to_unicode() {
+    let mut checked = 0;
+    for test in tests() {
+        let is_valid = test.to_unicode_status.is_empty() && test.to_ascii_status.is_empty();
+        if !is_valid || test.to_ascii.is_empty() {
+            continue;
+        }
+
+        let authority = Authority::try_from(test.to_ascii.as_str()).unwrap();
+        assert_eq!(
+            authority.host_unicode(),
+            test.to_unicode,
+            "line {}",
+            test.line
+        );
+        checked += 1;
+    }
+    assert!(checked > 400, "only {} tests checked", checked);
+}
@@ -0,0 +1,209 @@
+//! Runs the structured field test cases in `tests/fixtures`.
+
+use std::fs;
+use std::path::Path;
+
+use http::sf::{BareItem, Decimal, Dictionary, InnerList, Item, List, ListEntry, Parameters};
+use http::HeaderValue;
+use serde_json::Value;
+
+const FIXTURES: &str = "tests/fixtures/structured-fields";
+
+#[derive(Debug, PartialEq)]
+enum Field {
+    Item(Item),
+    List(List),
+    Dictionary(Dictionary),
+}
+
+impl Field {
+    fn parse(header_type: &str, values: &[HeaderValue]) -> Option<Field> {
+        match header_type {
+            "item" => Item::parse(values).ok().map(Field::Item),
+            "list" => List::parse(values).ok().map(Field::List),
+            "dictionary" => Dictionary::parse(values).ok().map(Field::Dictionary),
+            other => panic!("unknown header type {:?}", other),
+        }
+    }
+
+    fn from_json(header_type: &str, expected: &Value) -> Field {
+        match header_type {
+            "item" => Field::Item(item(expected)),
+            "list" => Field::List(array(expected).iter().map(member).collect()),
+            "dictionary" => Field::Dictionary(
+                array(expected)
+                    .iter()
+                    .map(|entry| (string(&entry[0]), member(&entry[1])))
+                    .collect(),
+            ),
+            other => panic!("unknown header type {:?}", other),
+        }
+    }
+
+    fn serialize(&self) -> Option<HeaderValue> {
+        match *self {
+            Field::Item(ref item) => item.to_header_value().ok(),
+            Field::List(ref list) => list.to_header_value().ok(),
+            Field::Dictionary(ref dict) => dict.to_header_value().ok(),
+        }
+    }
+}
+
+fn array(value: &Value) -> &Vec<Value> {
+    value.as_array().expect("array")
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().expect("string").to_string()
+}
+
+fn member(value: &Value) -> ListEntry {
+    if value[0].is_array() {
+        let mut list = InnerList::new(array(&value[0]).iter().map(item).collect());
+        list.params = params(&value[1]);
+        ListEntry::InnerList(list)
+    } else {
+        ListEntry::Item(item(value))
+    }
+}
+
+fn item(value: &Value) -> Item {
+    Item {
+        bare_item: bare_item(&value[0]),
+        params: params(&value[1]),
+    }
+}
+
+fn params(value: &Value) -> Parameters {
+    array(value)
+        .iter()
+        .map(|param| (string(&param[0]), bare_item(&param[1])))
+        .collect()
+}
+
+fn bare_item(value: &Value) -> BareItem {
+    match *value {
+        Value::Bool(b) => BareItem::Boolean(b),
+        Value::String(ref s) => BareItem::String(s.clone()),
+        Value::Number(ref n) => match n.as_i64() {
+            Some(n) => BareItem::Integer(n),
+            None => {
+                let thousandths = (n.as_f64().unwrap() * 1000.0).round();
+                BareItem::Decimal(Decimal::from_thousandths(thousandths as i64))
+            }
+        },
+        Value::Object(ref object) => {
+            let value = &object["value"];
+            match object["__type"].as_str() {
+                Some("token") => BareItem::Token(string(value)),
+                Some("binary") => BareItem::ByteSeq(base32(value.as_str().unwrap())),
+                Some("date") => BareItem::Date(value.as_i64().unwrap()),
+                Some("displaystring") => BareItem::DisplayString(string(value)),
+                other => panic!("unknown type {:?}", other),
+            }
+        }
+        _ => panic!("unexpected bare item {}", value),
+    }
+}
+
+fn base32(s: &str) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut bytes = Vec::new();
+    let (mut bits, mut n) = (0u32, 0);
+    for c in s.bytes().filter(|&c| c != b'=') {
+        let v = ALPHABET.iter().position(|&a| a == c).expect("base32") as u32;
+        bits = (bits << 5 | v) & 0xfff;
+        n += 5;
+        if n >= 8 {
+            n -= 8;
+            bytes.push((bits >> n) as u8);
+        }
+    }
+    bytes
+}
+
+// Returns `None` if a line can't be a header value at all.
+fn header_values(value: &Value) -> Option<Vec<HeaderValue>> {
+    array(value)
+        .iter()
+        .map(|line| HeaderValue::from_bytes(line.as_str().unwrap().as_bytes()).ok())
+        .collect()
+}
+
+fn run(path: &Path) {
+    let tests: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let serializing_only = path.parent().unwrap().ends_with("serializing");
+
+    for test in array(&tests) {
+        let name = format!("{}: {}", path.display(), test["name"].as_str().unwrap());
+        let header_type = test["header_type"].as_str().unwrap();
+        let must_fail = test["must_fail"].as_bool().unwrap_or(false);
+        let can_fail = test["can_fail"].as_bool().unwrap_or(false);
+
+        let expected = if serializing_only {
+            Field::from_json(header_type, &test["expected"])
+        } else {
+            let parsed =
+                header_values(&test["raw"]).and_then(|raw| Field::parse(header_type, &raw));
+            match parsed {
+                Some(parsed) => {
+                    assert!(!must_fail, "{}: parsing should fail", name);
+                    assert_eq!(
+                        parsed,
+                        Field::from_json(header_type, &test["expected"]),
+                        "{}",
+                        name
+                    );
+                    parsed
+                }
+                None => {
+                    assert!(must_fail || can_fail, "{}: parsing failed", name);
+                    continue;
+                }
+            }
+        };
+
+        let canonical = match test.get("canonical") {
+            Some(canonical) => header_values(canonical).unwrap(),
+            None if serializing_only => Vec::new(),
+            None => header_values(&test["raw"]).unwrap(),
+        };
+        match expected.serialize() {
+            Some(serialized) => {
+                assert!(
+                    !(serializing_only && must_fail),
+                    "{}: serialization should fail",
+                    name
+                );
+                assert_eq!(canonical, [serialized], "{}", name);
+            }
+            // Empty lists and dictionaries are not serialized.
+            None => assert!(
+                canonical.is_empty() || (serializing_only && must_fail),
+                "{}: serialization failed",
+                name
+            ),
+        }
+    }
+}
+
+#[test]
+fn structured_fields() {
+    let root = Path::new(FIXTURES);
+    let dirs = [root.join("parsing"), root.join("serializing")];
+    let mut count = 0;
+    for dir in &dirs {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+            .collect();
+        paths.sort();
+        for path in &paths {
+            run(path);
+            count += 1;
+        }
+    }
+    assert!(count > 0, "no fixtures found");
+}
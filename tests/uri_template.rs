@@ -0,0 +1,97 @@
+//! Runs the URI Template test cases in `tests/fixtures`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use http::uri::{TemplateValue, UriTemplate};
+use serde_json::Value;
+
+const FIXTURES: &str = "tests/fixtures/uritemplate-test";
+
+// Numbers are expanded as they are written; `null` is undefined.
+fn scalar(value: &Value) -> Option<String> {
+    match *value {
+        Value::String(ref s) => Some(s.clone()),
+        Value::Number(ref n) => Some(n.to_string()),
+        Value::Null => None,
+        ref other => panic!("unexpected value {}", other),
+    }
+}
+
+fn variables(value: &Value) -> HashMap<String, TemplateValue> {
+    let mut vars = HashMap::new();
+    for (name, value) in value.as_object().expect("object") {
+        let value = match *value {
+            Value::Array(ref list) => TemplateValue::List(list.iter().filter_map(scalar).collect()),
+            Value::Object(ref pairs) => TemplateValue::Assoc(
+                pairs
+                    .iter()
+                    .filter_map(|(k, v)| scalar(v).map(|v| (k.clone(), v)))
+                    .collect(),
+            ),
+            ref other => match scalar(other) {
+                Some(s) => TemplateValue::String(s),
+                None => continue,
+            },
+        };
+        vars.insert(name.clone(), value);
+    }
+    vars
+}
+
+fn run(path: &Path) -> usize {
+    let groups: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let mut count = 0;
+
+    for (group, tests) in groups.as_object().expect("object") {
+        let vars = variables(&tests["variables"]);
+        for case in tests["testcases"].as_array().expect("array") {
+            let template = case[0].as_str().unwrap();
+            let name = format!("{}: {}: {}", path.display(), group, template);
+            let expected: Vec<&str> = match case[1] {
+                Value::String(ref s) => vec![s],
+                Value::Array(ref list) => list.iter().map(|s| s.as_str().unwrap()).collect(),
+                // Either parsing or expanding the template must fail.
+                Value::Bool(false) => {
+                    let result = UriTemplate::parse(template).and_then(|t| t.try_expand(&vars));
+                    assert!(result.is_err(), "{}: expanded to {:?}", name, result);
+                    count += 1;
+                    continue;
+                }
+                ref other => panic!("{}: unexpected result {}", name, other),
+            };
+
+            let template =
+                UriTemplate::parse(template).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            let expanded = template
+                .try_expand(&vars)
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert!(
+                expected.contains(&expanded.as_str()),
+                "{}: expanded to {:?}, expected {:?}",
+                name,
+                expanded,
+                expected
+            );
+            count += 1;
+        }
+    }
+    count
+}
+
+#[test]
+fn uri_template_tests() {
+    let mut paths: Vec<_> = fs::read_dir(FIXTURES)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut count = 0;
+    for path in &paths {
+        count += run(path);
+    }
+    assert!(count > 0, "no fixtures found");
+}
@@ -0,0 +1,113 @@
+//! Runs the WHATWG URL test cases in `tests/fixtures/whatwg-url` against
+//! `Uri::parse_lenient`.
+
+use std::fs;
+
+use http::uri::percent::{self, EncodeSet};
+use http::Uri;
+use serde_json::Value;
+
+const FIXTURE: &str = "tests/fixtures/whatwg-url/urltestdata.json";
+
+// Percent-encodes what RFC 3986 doesn't allow in a path, query or fragment,
+// keeping valid percent-encoded bytes.
+fn strict(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = String::with_capacity(s.len());
+    for (i, c) in s.char_indices() {
+        let is_escape = c == '%'
+            && bytes.len() > i + 2
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+        if is_escape {
+            out.push('%');
+        } else {
+            let mut buf = [0; 4];
+            out.push_str(&percent::encode(
+                c.encode_utf8(&mut buf),
+                EncodeSet::FRAGMENT,
+            ));
+        }
+    }
+    out
+}
+
+// The expected `href`, strictly encoded.
+fn strict_href(test: &Value) -> String {
+    let field = |name: &str| test[name].as_str().expect(name);
+    let href = field("href");
+
+    let mut expected = format!("{}//", field("protocol"));
+    if !field("username").is_empty() || !field("password").is_empty() {
+        expected.push_str(&strict(field("username")));
+        if !field("password").is_empty() {
+            expected.push(':');
+            expected.push_str(&strict(field("password")));
+        }
+        expected.push('@');
+    }
+    expected.push_str(field("host"));
+    expected.push_str(&strict(field("pathname")));
+
+    let search = field("search");
+    let before_hash = href.splitn(2, '#').next().unwrap();
+    if !search.is_empty() || before_hash.ends_with('?') {
+        expected.push('?');
+        expected.push_str(&strict(search.get(1..).unwrap_or("")));
+    }
+    let hash = field("hash");
+    if !hash.is_empty() || href.ends_with('#') {
+        expected.push('#');
+        expected.push_str(&strict(hash.get(1..).unwrap_or("")));
+    }
+    expected
+}
+
+// Whether a host that browsers accept is a valid RFC 3986 host too.
+fn is_strict_host(host: &str) -> bool {
+    host.starts_with('[')
+        || host
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=".contains(&b))
+}
+
+// Without the `idna` feature, only ASCII hosts are parsed, and A-labels
+// aren't checked. Skips percent-encoded non-ASCII input too.
+fn needs_idna(test: &Value) -> bool {
+    let input = test["input"].as_str().unwrap();
+    let hostname = test["hostname"].as_str().unwrap_or("");
+    let decoded = percent::decode_bytes(input.as_bytes());
+    !decoded.map_or(true, |input| input.is_ascii())
+        || input.to_ascii_lowercase().contains("xn--")
+        || hostname.contains("xn--")
+}
+
+#[test]
+fn url_tests() {
+    let tests: Value = serde_json::from_str(&fs::read_to_string(FIXTURE).unwrap()).unwrap();
+    let tests = tests.as_array().expect("array");
+    assert!(!tests.is_empty(), "no fixtures found");
+
+    for test in tests {
+        let input = test["input"].as_str().unwrap();
+        if !cfg!(feature = "idna") && needs_idna(test) {
+            continue;
+        }
+
+        let result = Uri::parse_lenient(input);
+        if test["failure"].as_bool() == Some(true) {
+            assert!(result.is_err(), "{:?} should fail", input);
+            continue;
+        }
+        if !is_strict_host(test["hostname"].as_str().unwrap()) {
+            assert!(result.is_err(), "{:?} should fail", input);
+            continue;
+        }
+
+        let (uri, _) = result.unwrap_or_else(|e| panic!("{:?} failed: {}", input, e));
+        assert_eq!(uri.to_string(), strict_href(test), "{:?}", input);
+        assert_eq!(uri.host(), test["hostname"].as_str(), "{:?}", input);
+        let port = test["port"].as_str().unwrap();
+        assert_eq!(uri.port_u16(), port.parse().ok(), "{:?}", input);
+    }
+}